@@ -0,0 +1,22 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BedError {
+    #[error("Line {0} appears to be invalid")]
+    InvalidLine(usize),
+
+    #[error("File appears to be missing a field @ line {0}")]
+    InvalidFields(usize),
+
+    #[error("Failed to parse chromosome field @ line {0}")]
+    ParseChr(usize),
+
+    #[error("Failed to parse position field @ line {0}")]
+    ParsePos(usize),
+
+    #[error("Interval end coordinate must be greater than its start coordinate @ line {0}")]
+    InvalidInterval(usize),
+
+    #[error("Failed to find any interval within the provided BED file")]
+    EmptyBed,
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use ahash::AHashMap;
+use rust_lapper::{Interval, Lapper};
+use located_error::prelude::*;
+
+use super::read_bed;
+use crate::coordinate::{ChrIdx, Coordinate};
+
+/// `HashMap` of BED intervals, in the form of a BITS Tree (Key = chromosome name | Value = Interval Tree)
+type Regions = AHashMap<ChrIdx, Lapper<u32, ()>>;
+
+/// Genomic inclusion/exclusion mask, defined from a set of BED files.
+/// - `include`: when provided, only positions overlapping these regions are retained.
+/// - `exclude`: positions overlapping these regions are always discarded (e.g. low-complexity regions, segmental
+///   duplications, HLA, etc.)
+#[derive(Debug, Default)]
+pub struct GenomeMask {
+    include: Option<Regions>,
+    exclude: Option<Regions>,
+}
+
+impl GenomeMask {
+    /// Instantiate a new `GenomeMask` from an optional set of inclusion and exclusion BED files.
+    /// 
+    /// # Errors
+    /// - if any of the provided BED files cannot be opened or parsed.
+    pub fn new(include: Option<&Path>, exclude: Option<&Path>) -> Result<Self> {
+        Ok(Self{
+            include: include.map(Self::parse_regions).transpose().loc("While parsing inclusion mask")?,
+            exclude: exclude.map(Self::parse_regions).transpose().loc("While parsing exclusion mask")?,
+        })
+    }
+
+    fn parse_regions(path: &Path) -> Result<Regions> {
+        let mut intervals: AHashMap<ChrIdx, Vec<Interval<u32, ()>>> = AHashMap::new();
+        for interval in read_bed(path)? {
+            intervals.entry(interval.chromosome).or_default().push(Interval{start: interval.start, stop: interval.end, val: ()});
+        }
+        Ok(intervals.into_iter().map(|(chr, intervals)| (chr, Lapper::new(intervals))).collect())
+    }
+
+    /// Exclude an additional region from this mask.
+    /// - `start`: 1-based start coordinate of the region (inclusive).
+    /// - `end`  : 1-based end coordinate of the region (exclusive).
+    pub fn exclude(&mut self, chromosome: ChrIdx, start: u32, end: u32) {
+        let regions = self.exclude.get_or_insert_with(Regions::default);
+        let mut intervals = regions.remove(&chromosome).map(|lapper| lapper.intervals).unwrap_or_default();
+        intervals.push(Interval{start, stop: end, val: ()});
+        regions.insert(chromosome, Lapper::new(intervals));
+    }
+
+    /// Return `true` if no inclusion or exclusion regions were provided, i.e. if every position is retained.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.include.is_none() && self.exclude.is_none()
+    }
+
+    /// Return `true` if a given coordinate should be retained, i.e. if it overlaps the inclusion regions (if any), and
+    /// does not overlap any exclusion region.
+    #[must_use]
+    pub fn allows(&self, coordinate: &Coordinate) -> bool {
+        let overlaps = |regions: &Regions| regions.get(&coordinate.chromosome)
+            .is_some_and(|lapper| lapper.find(coordinate.position.0, coordinate.position.0 + 1).next().is_some());
+
+        let included = match &self.include {
+            Some(regions) => overlaps(regions),
+            None          => true,
+        };
+        included && !self.exclude.as_ref().is_some_and(overlaps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn bed_file(contents: &str) -> Result<tempfile::NamedTempFile> {
+        let mut bed = tempfile::NamedTempFile::new()?;
+        writeln!(bed, "{contents}")?;
+        Ok(bed)
+    }
+
+    #[test]
+    fn empty_mask() {
+        let mask = GenomeMask::default();
+        assert!(mask.is_empty());
+        assert!(mask.allows(&Coordinate::new(1, 100)));
+    }
+
+    #[test]
+    fn include_and_exclude() -> Result<()> {
+        let include = bed_file("1\t99\t200\n2\t0\t1000")?;
+        let exclude = bed_file("2\t499\t500")?;
+        let mask = GenomeMask::new(Some(include.path()), Some(exclude.path()))?;
+        assert!(!mask.is_empty());
+
+        assert!(!mask.allows(&Coordinate::new(1, 99)));
+        assert!( mask.allows(&Coordinate::new(1, 100)));
+        assert!( mask.allows(&Coordinate::new(1, 200)));
+        assert!(!mask.allows(&Coordinate::new(1, 201)));
+        assert!( mask.allows(&Coordinate::new(2, 499)));
+        assert!(!mask.allows(&Coordinate::new(2, 500)));
+        assert!(!mask.allows(&Coordinate::new(3, 100)));
+        Ok(())
+    }
+
+    #[test]
+    fn exclude_region() -> Result<()> {
+        let include = bed_file("X\t0\t1000")?;
+        let mut mask = GenomeMask::new(Some(include.path()), None)?;
+        mask.exclude(ChrIdx::X, 100, 201);
+        mask.exclude(ChrIdx::X, 500, 601);
+        assert!( mask.allows(&Coordinate::new(ChrIdx::X, 99)));
+        assert!(!mask.allows(&Coordinate::new(ChrIdx::X, 100)));
+        assert!(!mask.allows(&Coordinate::new(ChrIdx::X, 200)));
+        assert!( mask.allows(&Coordinate::new(ChrIdx::X, 201)));
+        assert!(!mask.allows(&Coordinate::new(ChrIdx::X, 600)));
+        Ok(())
+    }
+
+    #[test]
+    fn exclude_only() -> Result<()> {
+        let exclude = bed_file("6\t28477796\t33448354")?;
+        let mask = GenomeMask::new(None, Some(exclude.path()))?;
+        assert!(!mask.allows(&Coordinate::new(6, 30_000_000)));
+        assert!( mask.allows(&Coordinate::new(6, 10_000)));
+        assert!( mask.allows(&Coordinate::new(1, 30_000_000)));
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::{collections::HashSet, fs::File, io::{BufRead, BufReader}, path::Path};
+
+use located_error::prelude::*;
+use log::warn;
+
+mod error;
+pub use error::BedError;
+
+mod mask;
+pub use mask::GenomeMask;
+
+use crate::coordinate::{ChrIdx, ChrIdxError};
+
+/// A single interval of a BED file, converted to 1-based `[start, end[` coordinates.
+/// - `chromosome`: chromosome of the interval.
+/// - `start`     : 1-based start coordinate of the interval (inclusive).
+/// - `end`       : 1-based end coordinate of the interval (exclusive).
+/// - `line`      : line number of the interval within its BED file. Used for error reporting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BedInterval {
+    pub chromosome: ChrIdx,
+    pub start     : u32,
+    pub end       : u32,
+    pub line      : usize,
+}
+
+/// Parse the intervals of a BED file. Intervals are returned in order of appearance.
+/// 
+/// # Expected file format:
+/// - Fields         : `<CHROMOSOME>` `<START>` `<END>` (0-based, half-open. Additional fields are ignored)
+/// - Field-separator: `'\t'`
+/// - Empty lines, comments (`#`), and `track` or `browser` lines are skipped.
+/// - Intervals located on contigs that are unknown to the reference genome (e.g. `chrUn_*`, `*_random`, `*_alt`) are
+///   skipped, with a single warning per contig.
+/// 
+/// # Errors
+/// - if `path` cannot be opened or read.
+/// - `InvalidFields` if a line contains less than 3 fields.
+/// - `ParseChr` or `ParsePos` if a line contains an invalid chromosome or coordinate.
+/// - `InvalidInterval` if an interval is empty.
+/// - `EmptyBed` if the file does not contain any interval.
+pub fn read_bed(path: impl AsRef<Path>) -> Result<Vec<BedInterval>> {
+    use BedError::{EmptyBed, InvalidFields, InvalidInterval, InvalidLine, ParseChr, ParsePos};
+    let loc_msg = || format!("While parsing BED file {}", path.as_ref().display());
+    let source = BufReader::new(File::open(path.as_ref()).with_loc(loc_msg)?);
+
+    let mut intervals = Vec::new();
+    let mut unknown_contigs = HashSet::new();
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.with_loc(|| InvalidLine(line_number))?;
+        if line.is_empty() || line.starts_with('#') || line.starts_with("track") || line.starts_with("browser") {
+            continue
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 3 {
+            return Err(InvalidFields(line_number)).with_loc(|| format!("Expected at least 3 fields, got {}", fields.len()))
+        }
+        let chromosome: ChrIdx = match fields[0].parse() {
+            Ok(chromosome) => chromosome,
+            Err(ChrIdxError::UnknownContig(contig)) => {
+                if unknown_contigs.insert(contig) {
+                    warn!("Skipping intervals of BED file {} located on contig '{}', which is not part of the reference genome.", path.as_ref().display(), fields[0]);
+                }
+                continue
+            },
+            Err(e) => return Err(e).with_loc(|| ParseChr(line_number)),
+        };
+        let start     : u32    = fields[1].parse().with_loc(|| ParsePos(line_number))?;
+        let end       : u32    = fields[2].parse().with_loc(|| ParsePos(line_number))?;
+        if start >= end {
+            return Err(InvalidInterval(line_number)).with_loc(loc_msg)
+        }
+        intervals.push(BedInterval{chromosome, start: start + 1, end: end.saturating_add(1), line: line_number});
+    }
+
+    if intervals.is_empty() {
+        return Err(EmptyBed).with_loc(loc_msg)
+    }
+    Ok(intervals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn read_bed_ok() -> Result<()> {
+        let mut bed = tempfile::NamedTempFile::new()?;
+        writeln!(bed, "browser position chr1:1-1000\n# comment\n\nchr1\t0\t100\tname\t0\t+\nX\t99\t100")?;
+        let intervals = read_bed(bed.path())?;
+        assert_eq!(intervals, vec![
+            BedInterval{chromosome: ChrIdx::from(1), start: 1, end: 101, line: 4},
+            BedInterval{chromosome: ChrIdx::X,         start: 100, end: 101, line: 5},
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn read_invalid_bed() -> Result<()> {
+        for contents in ["", "1\t100", "1\t100\t100", "1\tA\t100"] {
+            let mut bed = tempfile::NamedTempFile::new()?;
+            writeln!(bed, "{contents}")?;
+            assert!(read_bed(bed.path()).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn read_bed_unknown_contigs() -> Result<()> {
+        let mut bed = tempfile::NamedTempFile::new()?;
+        writeln!(bed, "chrUn\t0\t100\n1\t0\t100\nchr1_random\t0\t100\nchrUn\t200\t300")?;
+        let intervals = read_bed(bed.path())?;
+        assert_eq!(intervals, vec![BedInterval{chromosome: ChrIdx::from(1), start: 1, end: 101, line: 2}]);
+        Ok(())
+    }
+}
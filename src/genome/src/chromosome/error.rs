@@ -4,6 +4,9 @@ pub enum ChromosomeError {
     #[error("Failed to parse chromosome length")]
     ParseLength,
 
+    #[error("Missing chromosome length")]
+    MissingLength,
+
     #[error("Failed to parse chromosome name")]
     ParseChrIdx
 }
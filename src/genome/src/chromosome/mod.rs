@@ -1,7 +1,7 @@
 use std::{cmp::Ordering, str::FromStr};
 
 use log::debug;
-use located_error::LocatedError;
+use located_error::{LocatedError, LocatedOption};
 use crate::coordinate::ChrIdx;
 
 mod error;
@@ -10,7 +10,7 @@ use error::ChromosomeError;
 /// A simple struct representing a chromosome. This is mainly used to compute Jackknife Blocks
 /// # Fields:
 /// - `index` : 0-based index of the chromosome
-/// - `name`  : 1-based name of the chromosome (i.e. 1-22)
+/// - `name`  : contig id of the chromosome (see `ChrIdx`)
 /// - `length`: length of the chromosome (bp)
 #[derive(Debug, Clone, Copy)]
 pub struct Chromosome {
@@ -23,7 +23,7 @@ impl Chromosome {
     /// Instantiate a new chromosome
     /// # Fields:
     /// - `index` : 0-based index of the chromosome
-    /// - `name`  : 1-based name of the chromosome (i.e. 1-22)
+    /// - `name`  : contig id of the chromosome (see `ChrIdx`)
     /// - `length`: length of the chromosome (bp)
     #[must_use]
     pub fn new(name: impl Into<ChrIdx>, length: u32) -> Chromosome{
@@ -51,16 +51,20 @@ impl Ord for Chromosome {
 }
 
 
+/// Parse a `.fai` line into a Chromosome. Contig names which are not known yet are registered within the
+/// process-wide contig dictionary (see `ChrIdx::register()`).
 impl FromStr for Chromosome {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use ChromosomeError::{ParseChrIdx, ParseLength};
+        use ChromosomeError::{MissingLength, ParseChrIdx, ParseLength};
         let split_line: Vec<&str> = s.split('\t').collect();
-        let name = ChrIdx::from_str(split_line[0])
-            .with_loc(|| ParseChrIdx)?;
-        let length = split_line[1].parse::<u32>()
+        let length = split_line.get(1)
+            .with_loc(|| MissingLength)?
+            .parse::<u32>()
             .with_loc(||ParseLength)?;
+        let name = ChrIdx::register(split_line[0])
+            .with_loc(|| ParseChrIdx)?;
 
         debug!("Chromosome: {name: <10} {length: <12}");
         Ok(Self::new(name, length))
@@ -128,7 +132,15 @@ mod tests {
     fn from_str_xchr() {
         for label in &["X", "chrX"] {
             let chr = Chromosome::from_str(&format!("{label}\t123456789"));
-            assert!(chr.is_ok_and(|c| c.name == ChrIdx(88)));
+            assert!(chr.is_ok_and(|c| c.name == ChrIdx::X));
         }
     }
+
+    #[test]
+    fn from_str_unplaced_contig() {
+        let chr = Chromosome::from_str("chrUn_gl000220\t161802").expect("Unplaced contigs should get registered");
+        assert!(!chr.name.is_numbered());
+        assert_eq!(chr.name.to_string(), "Un_gl000220");
+        assert!("chrUn_gl000220".parse::<ChrIdx>().is_ok_and(|id| id == chr.name));
+    }
 }
\ No newline at end of file
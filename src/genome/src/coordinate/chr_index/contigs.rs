@@ -0,0 +1,91 @@
+use std::sync::{OnceLock, RwLock};
+
+use ahash::AHashMap;
+
+use super::{ChrIdx, ChrIdxError};
+
+/// Contigs which are always known, regardless of the provided reference genome. These are assigned fixed ids (see
+/// `ChrIdx::X`, `ChrIdx::Y` and `ChrIdx::MT`), so that they remain stable across runs and reference genomes.
+/// - Tuple fields: (display name, accepted aliases)
+///
+/// Note that `X_par1` and `X_par2` (e.g. HapMap recombination maps) are folded into `X`: pseudo-autosomal regions are
+/// instead declared and masked through species profiles (see `genome::species::PseudoAutosomalRegion`).
+const BUILTIN_CONTIGS: [(&str, &[&str]); 3] = [
+    ("X",  &["X", "X_par1", "X_par2"]),
+    ("Y",  &["Y"]),
+    ("MT", &["MT", "M"]),
+];
+
+/// Process-wide contig dictionary. Every reader resolves contig names through this dictionary, thus ensuring a given
+/// name is always mapped to the same `ChrIdx`.
+static CONTIGS: OnceLock<RwLock<ContigDictionary>> = OnceLock::new();
+
+/// Access the process-wide contig dictionary, initializing it with the builtin contigs on first use.
+fn contigs() -> &'static RwLock<ContigDictionary> {
+    CONTIGS.get_or_init(|| RwLock::new(ContigDictionary::default()))
+}
+
+/// Bidirectional mapping between contig names and compact `ChrIdx` ids, for contigs that cannot be represented by a
+/// number (e.g. unplaced scaffolds, or non-human assemblies).
+/// - `names`: display name of each dictionary contig. `names[i]` is tied to `ChrIdx(FIRST_ID + i)`
+/// - `ids`  : contig name (or alias) -> id.
+///
+/// # Note
+/// Names are stored without their `chr` prefix, if any.
+#[derive(Debug)]
+pub struct ContigDictionary {
+    names: Vec<String>,
+    ids  : AHashMap<String, ChrIdx>,
+}
+
+impl Default for ContigDictionary {
+    fn default() -> Self {
+        let mut dict = Self{names: Vec::new(), ids: AHashMap::new()};
+        for (name, aliases) in BUILTIN_CONTIGS {
+            let id = dict.next_id().expect("Builtin contigs should never overflow the dictionary");
+            dict.names.push(name.to_string());
+            dict.ids.extend(aliases.iter().map(|alias| ((*alias).to_string(), id)));
+        }
+        dict
+    }
+}
+
+impl ContigDictionary {
+    /// Id of the first dictionary contig. Lower values are reserved for numbered contigs (i.e. `1`, `2`, ..., `255`)
+    pub const FIRST_ID: u16 = u8::MAX as u16 + 1;
+
+    /// Return the id that should be assigned to the next registered contig.
+    fn next_id(&self) -> Result<ChrIdx, ChrIdxError> {
+        u16::try_from(self.names.len()).ok()
+            .and_then(|len| Self::FIRST_ID.checked_add(len))
+            .map(ChrIdx)
+            .ok_or(ChrIdxError::DictionaryOverflow)
+    }
+
+    /// Register a new contig name within the process-wide dictionary and return its id. Names which were already
+    /// registered are left untouched, and keep their previous id.
+    ///
+    /// # Errors
+    /// - `DictionaryOverflow` if the dictionary is full.
+    pub(super) fn register(name: &str) -> Result<ChrIdx, ChrIdxError> {
+        let mut dict = contigs().write().expect("Contig dictionary lock should never get poisoned");
+        if let Some(id) = dict.ids.get(name) {
+            return Ok(*id)
+        }
+        let id = dict.next_id()?;
+        dict.names.push(name.to_string());
+        dict.ids.insert(name.to_string(), id);
+        Ok(id)
+    }
+
+    /// Search the process-wide dictionary for a contig name.
+    pub(super) fn lookup(name: &str) -> Option<ChrIdx> {
+        contigs().read().expect("Contig dictionary lock should never get poisoned").ids.get(name).copied()
+    }
+
+    /// Retrieve the display name of a dictionary contig.
+    pub(super) fn name(id: ChrIdx) -> Option<String> {
+        let index = usize::from(id.0.checked_sub(Self::FIRST_ID)?);
+        contigs().read().expect("Contig dictionary lock should never get poisoned").names.get(index).cloned()
+    }
+}
@@ -1,5 +1,10 @@
 use thiserror::Error;
 
 #[derive(Error, Debug)]
-#[error("Failed to parse Chromosome name into a valid u8")]
-pub struct ChrIdxError(#[from] std::num::ParseIntError);
\ No newline at end of file
+pub enum ChrIdxError {
+    #[error("Unknown contig '{0}'. Contigs that are neither numbered nor X, Y or MT must be defined within the reference genome (see --genome)")]
+    UnknownContig(String),
+
+    #[error("Failed to register contig: the contig dictionary is full")]
+    DictionaryOverflow,
+}
@@ -3,55 +3,127 @@ use std::{str::FromStr, fmt::{Display, Formatter, self}, ops::{Deref, Add}, hash
 mod error;
 pub use error::ChrIdxError;
 
+mod contigs;
+pub use contigs::ContigDictionary;
+
 use located_error::prelude::*;
 
+/// Compact identifier of a contig.
+/// - Numbered contigs (`1`, `2`, ..., `255`) are directly identified by their number.
+/// - Any other contig (`X`, `Y`, `MT`, unplaced scaffolds, non-human assemblies, etc.) is identified through the
+///   process-wide `ContigDictionary`. `X`, `Y` and `MT` are always known, while other names must first get registered
+///   (see `ChrIdx::register()` and `Genome::from_fasta_index()`).
+///
+/// Names are case-sensitive, and an optional `chr` prefix is ignored (i.e. `chr1` and `1` are the same contig).
 #[derive(Debug, Clone, Copy)]
-pub struct ChrIdx(pub u8);
+pub struct ChrIdx(pub u16);
 
 impl FromStr for ChrIdx {
     type Err = ChrIdxError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let inner = match s.replace("chr", "").as_str() {
-            "X" | "X_par1" | "X_par2" => b'X',
-            other => other.parse::<u8>()?
-        };
-        Ok(Self(inner))
+        let name = Self::strip_prefix(s);
+        match name.parse::<u8>() {
+            Ok(number) => Ok(Self::from(number)),
+            Err(_)     => ContigDictionary::lookup(name).ok_or_else(|| ChrIdxError::UnknownContig(s.to_string())),
+        }
     }
 }
 
 impl Display for ChrIdx {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        self.0.fmt(f)
+        match ContigDictionary::name(*self) {
+            Some(name) => f.pad(&name),
+            None       => self.0.fmt(f),
+        }
     }
 }
 
 impl ChrIdx {
+    /// X chromosome.
+    pub const X : Self = Self(ContigDictionary::FIRST_ID);
+    /// Y chromosome.
+    pub const Y : Self = Self(ContigDictionary::FIRST_ID + 1);
+    /// Mitochondrial genome.
+    pub const MT: Self = Self(ContigDictionary::FIRST_ID + 2);
+
     #[must_use]
-    pub fn into_inner(&self) -> u8 {
+    pub fn into_inner(&self) -> u16 {
         self.0
     }
+
+    /// Return the id of a contig, registering its name within the process-wide `ContigDictionary` if it is unknown.
+    ///
+    /// # Errors
+    /// - `DictionaryOverflow` if the contig dictionary is full.
+    pub fn register(name: &str) -> Result<Self, ChrIdxError> {
+        let name = Self::strip_prefix(name);
+        match name.parse::<u8>() {
+            Ok(number) => Ok(Self::from(number)),
+            Err(_)     => ContigDictionary::register(name),
+        }
+    }
+
+    /// Parse a PLINK chromosome code, where `23`, `24`, `25` and `26` respectively stand for `X`, `Y`, the
+    /// pseudo-autosomal region of `X` (`XY`) and `MT`. `0` (unplaced) is rejected.
+    ///
+    /// # Errors
+    /// - `UnknownContig` if the code is `0`, or is not a known contig.
+    pub fn from_plink(code: &str) -> Result<Self, ChrIdxError> {
+        match Self::strip_prefix(code) {
+            "23" | "25" | "XY" => Ok(Self::X),
+            "24"               => Ok(Self::Y),
+            "26"               => Ok(Self::MT),
+            "0"                => Err(ChrIdxError::UnknownContig(code.to_string())),
+            _                  => code.parse(),
+        }
+    }
+
+    /// Parse an EIGENSTRAT chromosome code, where `23`, `24`, `90` and `91` respectively stand for `X`, `Y`, `MT` and
+    /// the pseudo-autosomal region of `X` (`XY`). `0` (unplaced) is rejected.
+    ///
+    /// # Errors
+    /// - `UnknownContig` if the code is `0`, or is not a known contig.
+    pub fn from_eigenstrat(code: &str) -> Result<Self, ChrIdxError> {
+        match Self::strip_prefix(code) {
+            "23" | "91" | "XY" => Ok(Self::X),
+            "24"               => Ok(Self::Y),
+            "90"               => Ok(Self::MT),
+            "0"                => Err(ChrIdxError::UnknownContig(code.to_string())),
+            _                  => code.parse(),
+        }
+    }
+
+    /// Check whether this contig is identified by a number (i.e. `1`, `2`, ..., `255`).
+    #[must_use]
+    pub fn is_numbered(&self) -> bool {
+        self.0 < ContigDictionary::FIRST_ID
+    }
+
+    fn strip_prefix(name: &str) -> &str {
+        name.strip_prefix("chr").unwrap_or(name)
+    }
 }
 
 impl From<u8> for ChrIdx {
     fn from(value: u8) -> Self {
-        Self(value)
+        Self(u16::from(value))
     }
 }
 
-impl From<ChrIdx> for u8 {
-    fn from(val: ChrIdx) -> u8 {
+impl From<ChrIdx> for u16 {
+    fn from(val: ChrIdx) -> u16 {
         val.0
     }
 }
 
-impl AsRef<u8> for ChrIdx {
-    fn as_ref(&self) -> &u8 {
+impl AsRef<u16> for ChrIdx {
+    fn as_ref(&self) -> &u16 {
         &self.0
     }
 }
 
 impl Deref for ChrIdx {
-    type Target = u8;
+    type Target = u16;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -106,6 +178,12 @@ mod tests {
         assert_eq!(want, got);
     }
 
+    #[test]
+    fn display_named() {
+        assert_eq!(format!("{:_^6}", ChrIdx::X), "__X___");
+        assert_eq!(ChrIdx::MT.to_string(), "MT");
+    }
+
     #[test]
     fn add() {
         assert_eq!(ChrIdx(17) + ChrIdx(2), ChrIdx(19));
@@ -113,11 +191,60 @@ mod tests {
 
     #[test]
     fn as_ref(){
-        assert_eq!(ChrIdx(17).as_ref(), &17u8);
+        assert_eq!(ChrIdx(17).as_ref(), &17u16);
     }
 
     #[test]
     fn into_inner(){
-        assert_eq!(ChrIdx(17).into_inner(), 17u8);
+        assert_eq!(ChrIdx(17).into_inner(), 17u16);
+    }
+
+    #[test]
+    fn from_str_numbered() {
+        for name in ["17", "chr17"] {
+            assert!(name.parse::<ChrIdx>().is_ok_and(|chr| chr == ChrIdx(17) && chr.is_numbered()));
+        }
+    }
+
+    #[test]
+    fn from_str_builtin() {
+        for name in ["X", "chrX", "X_par1", "X_par2"] {
+            assert!(name.parse::<ChrIdx>().is_ok_and(|chr| chr == ChrIdx::X));
+        }
+        assert!("chrY".parse::<ChrIdx>().is_ok_and(|chr| chr == ChrIdx::Y));
+        assert!("chrM".parse::<ChrIdx>().is_ok_and(|chr| chr == ChrIdx::MT));
+        assert!(!ChrIdx::X.is_numbered());
+    }
+
+    #[test]
+    fn from_str_unknown() {
+        assert!(matches!("chr_unregistered_contig".parse::<ChrIdx>(), Err(ChrIdxError::UnknownContig(_))));
+    }
+
+    #[test]
+    fn from_plink() {
+        for (code, want) in [("23", ChrIdx::X), ("24", ChrIdx::Y), ("25", ChrIdx::X), ("XY", ChrIdx::X), ("26", ChrIdx::MT), ("MT", ChrIdx::MT), ("22", ChrIdx(22))] {
+            assert!(ChrIdx::from_plink(code).is_ok_and(|chr| chr == want), "{code}");
+        }
+        assert!(ChrIdx::from_plink("0").is_err());
+    }
+
+    #[test]
+    fn from_eigenstrat() {
+        for (code, want) in [("23", ChrIdx::X), ("24", ChrIdx::Y), ("90", ChrIdx::MT), ("91", ChrIdx::X), ("22", ChrIdx(22))] {
+            assert!(ChrIdx::from_eigenstrat(code).is_ok_and(|chr| chr == want), "{code}");
+        }
+        assert!(ChrIdx::from_eigenstrat("0").is_err());
+    }
+
+    #[test]
+    fn register() -> Result<(), ChrIdxError> {
+        let id = ChrIdx::register("chrUn_test_registered_contig")?;
+        assert!(!id.is_numbered());
+        assert_eq!(ChrIdx::register("Un_test_registered_contig")?, id);
+        assert_eq!("chrUn_test_registered_contig".parse::<ChrIdx>()?, id);
+        assert_eq!(id.to_string(), "Un_test_registered_contig");
+        assert_eq!(ChrIdx::register("chr12")?, ChrIdx(12));
+        Ok(())
     }
 }
\ No newline at end of file
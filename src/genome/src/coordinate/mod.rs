@@ -120,25 +120,26 @@ impl Coordinate {
     }
 }
 
-/// Convert a Coordinate into a length 5 raw byte array: 
-/// First byte is chromosome as u8
-/// bytes 1..5 is position, encoded into u32 big endian.
-/// [{chr u8}, {pos u32_big_endian}]
-impl From<Coordinate> for [u8; 5] {
-    fn from(value: Coordinate) -> [u8; 5] {
-        let mut out: [u8;5] = [0; 5];
-        out[0] = u8::from(value.chromosome);
-        u32::from(value.position).to_be_bytes().iter().enumerate().for_each(|(i,byte)| out[i+1] = *byte);
+/// Length of a Coordinate, once encoded as a raw byte array (see `From<Coordinate> for [u8; COORDINATE_BYTES]`)
+pub const COORDINATE_BYTES: usize = 6;
+
+/// Convert a Coordinate into a length 6 raw byte array: 
+/// bytes 0..2 is chromosome, encoded into u16 big endian.
+/// bytes 2..6 is position, encoded into u32 big endian.
+/// [{chr u16_big_endian}, {pos u32_big_endian}]
+impl From<Coordinate> for [u8; COORDINATE_BYTES] {
+    fn from(value: Coordinate) -> [u8; COORDINATE_BYTES] {
+        let mut out = [0; COORDINATE_BYTES];
+        out[0..2].copy_from_slice(&u16::from(value.chromosome).to_be_bytes());
+        out[2..6].copy_from_slice(&u32::from(value.position).to_be_bytes());
         out
     }
 }
 
-impl From<[u8; 5]> for Coordinate {
-    fn from(value: [u8; 5]) -> Self {
-        let chromosome = ChrIdx(value[0]);
-        let mut position_bytes = [0; 4];
-        value[1..5].iter().enumerate().for_each(|(i, byte)| position_bytes[i] = *byte);
-        let position = Position(u32::from_be_bytes(position_bytes));
+impl From<[u8; COORDINATE_BYTES]> for Coordinate {
+    fn from(value: [u8; COORDINATE_BYTES]) -> Self {
+        let chromosome = ChrIdx(u16::from_be_bytes([value[0], value[1]]));
+        let position   = Position(u32::from_be_bytes([value[2], value[3], value[4], value[5]]));
         Coordinate::new(chromosome, position)
     }
 }
@@ -156,8 +157,8 @@ impl TryFrom<&[u8]> for Coordinate {
     type Error = CoordinateError;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let chromosome = ChrIdx::from(value[0]);
-        let raw_pos    = u32::from_be_bytes(value[1..5].try_into()?);
+        let chromosome = ChrIdx(u16::from_be_bytes(value[0..2].try_into()?));
+        let raw_pos    = u32::from_be_bytes(value[2..COORDINATE_BYTES].try_into()?);
         let position   = Position::from(raw_pos);
         Ok(Self{chromosome, position})
         
@@ -211,12 +212,29 @@ mod tests {
 
     #[test]
     fn from_bytes(){
-        let bytes = [17, 7, 91, 205, 21]; // 123456789 u32
+        let bytes = [0, 17, 7, 91, 205, 21]; // 123456789 u32
 
         let coord = Coordinate::from(bytes);
         assert!(coord.chromosome == ChrIdx(17) && coord.position == Position(123_456_789));
     }
 
+    #[test]
+    fn bytes_roundtrip(){
+        for coordinate in [Coordinate::new(ChrIdx(17), Position(123_456_789)), Coordinate::new(ChrIdx::X, Position(42))] {
+            let bytes: [u8; COORDINATE_BYTES] = coordinate.into();
+            assert_eq!(Coordinate::from(bytes), coordinate);
+            assert!(Coordinate::try_from(bytes.as_slice()).is_ok_and(|c| c == coordinate));
+        }
+    }
+
+    #[test]
+    fn bytes_ordering(){
+        // ---- FST indexes rely on the lexicographic order of keys matching that of coordinates.
+        let lower : [u8; COORDINATE_BYTES] = Coordinate::new(ChrIdx(22), Position(u32::MAX)).into();
+        let higher: [u8; COORDINATE_BYTES] = Coordinate::new(ChrIdx::X, Position(1)).into();
+        assert!(lower < higher);
+    }
+
     #[test]
     fn genome_coordinate_matches(){
         let coordinate = Coordinate::new(ChrIdx(10), Position(20));
@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+use rust_lapper::Interval;
+
+use super::{GeneticMapError, RecombinationRange};
+use crate::coordinate::ChrIdx;
+
+/// Recombination ranges of a single chromosome, parsed from a single genetic map file.
+/// - `path`     : genetic map file from which these ranges were parsed. Used for error reporting.
+/// - `anchored` : whether the first range carries an explicit start coordinate (deCODE maps). Otherwise, the first
+///   range either starts at 0, or where the previous map of this chromosome ends (see `MapChunk::merge()`).
+/// - `intervals`: recombination ranges, in order of appearance.
+pub struct MapChunk {
+    pub path     : PathBuf,
+    pub anchored : bool,
+    pub intervals: Vec<Interval<u32, RecombinationRange>>,
+}
+
+impl MapChunk {
+    pub fn new(path: PathBuf, anchored: bool) -> Self {
+        Self{path, anchored, intervals: Vec::new()}
+    }
+
+    /// Lowest position known to be covered by this map.
+    fn lower_bound(&self) -> u32 {
+        match (self.anchored, self.intervals.first()) {
+            (_, None)                  => u32::MAX,
+            (true, Some(interval))     => interval.start,
+            (false, Some(interval))    => interval.stop,
+        }
+    }
+
+    /// Highest position covered by this map.
+    fn upper_bound(&self) -> u32 {
+        self.intervals.iter().map(|interval| interval.stop).max().unwrap_or(0)
+    }
+
+    /// Merge the maps of a single chromosome (e.g. `chrX_par1`, `chrX` and `chrX_par2`), in order of position,
+    /// regardless of the order in which they were parsed. The first range of each map lacking an explicit start
+    /// coordinate is extended back to the end of the previous map, rather than to the start of the chromosome.
+    ///
+    /// # Errors
+    /// - `OverlappingMaps` if two maps cover overlapping regions of the chromosome (e.g. both the male and female
+    ///   maps of a chromosome). Recombination would otherwise get counted twice within these regions.
+    pub fn merge(chr: ChrIdx, mut chunks: Vec<Self>) -> Result<Vec<Interval<u32, RecombinationRange>>, GeneticMapError> {
+        chunks.sort_by_key(Self::lower_bound);
+        let mut merged: Vec<Interval<u32, RecombinationRange>> = Vec::new();
+        let mut previous: Option<(PathBuf, u32)> = None; // Path and upper bound of the previous map.
+        for mut chunk in chunks {
+            if let Some((previous_path, previous_stop)) = previous.take() {
+                if chunk.lower_bound() < previous_stop {
+                    return Err(GeneticMapError::OverlappingMaps{chr, first: previous_path, second: chunk.path})
+                }
+                if let Some(first) = chunk.intervals.first_mut().filter(|_| !chunk.anchored) {
+                    first.start = previous_stop;
+                    first.val   = first.val.clone().with_start(previous_stop);
+                }
+            }
+            previous = Some((chunk.path.clone(), chunk.upper_bound()));
+            merged.append(&mut chunk.intervals);
+        }
+        Ok(merged)
+    }
+}
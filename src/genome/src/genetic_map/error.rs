@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 use thiserror::{Error, self};
 
+use crate::coordinate::ChrIdx;
+
 
 #[derive(Error, Debug)]
 pub enum GeneticMapError {
@@ -10,9 +12,12 @@ pub enum GeneticMapError {
     #[error("Failed to read contents of the provided directory")]
     ReadDir,
 
-    #[error("Failed to find or parse any genetic-map in the provided directory.")]
+    #[error("Failed to find or parse any genetic-map in the provided directory or files.")]
     EmptyDir,
 
+    #[error("Unrecognized genetic map format. Expected either a HapMap, SHAPEIT/Eagle, PLINK (.map), deCODE or Bhérer et al. genetic map")]
+    UnknownFormat,
+
     #[error("Line {0} appears to be invalid")]
     InvalidLine(usize),
 
@@ -26,5 +31,11 @@ pub enum GeneticMapError {
     ParseRate(usize),
 
     #[error("File appears to be missing a field @ line {0}")]
-    InvalidFields(usize)
+    InvalidFields(usize),
+
+    #[error("Genetic maps {} and {} both cover overlapping regions of chromosome {chr}. Each region of a chromosome should be covered by a single genetic map (e.g. provide either the male or female map of a chromosome, not both)", first.display(), second.display())]
+    OverlappingMaps{chr: ChrIdx, first: PathBuf, second: PathBuf},
+
+    #[error("Genetic map of chromosome {chr} extends up to position {stop}, past the end of the chromosome ({length} bp). Are the genetic maps and the reference genome from the same build?")]
+    OutOfBounds{chr: ChrIdx, stop: u32, length: u32},
 }
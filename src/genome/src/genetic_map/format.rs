@@ -0,0 +1,135 @@
+use located_error::prelude::*;
+
+use super::error::GeneticMapError;
+use crate::coordinate::{ChrIdx, ChrIdxError};
+
+/// Supported genetic recombination map layouts. Fields may either be tab or space separated.
+/// - `HapMap`: `<chr> <pos(bp)> <rate(cM/Mb)> <map(cM)>` (headed. e.g. HapMap-phaseII)
+/// - `Eagle` : `chr position COMBINED_rate(cM/Mb) Genetic_Map(cM)` (headed. SHAPEIT/Eagle maps, where `23` is `X`)
+/// - `Plink` : `<chr> <id> <map(cM)> <pos(bp)>` (headerless. PLINK `.map` files, see `ChrIdx::from_plink()`)
+/// - `Decode`: `Chr Begin End cMperMb cM` (headed. deCODE maps)
+/// - `Bherer`: `pos chr cM` (headed. Bhérer et al. 2017 refined maps)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapFormat {
+    HapMap,
+    Eagle,
+    Plink,
+    Decode,
+    Bherer,
+}
+
+/// A single entry of a genetic recombination map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MapEntry {
+    /// Recombination rate (cM/Mb) between the previous entry and `stop`.
+    Rate{chr: ChrIdx, stop: u32, rate: f64},
+    /// Genetic position (cM) of `stop`. The recombination rate is interpolated from the previous entry.
+    Position{chr: ChrIdx, stop: u32, cm: f64},
+    /// Recombination rate (cM/Mb) within `[start, stop]`
+    Range{chr: ChrIdx, start: u32, stop: u32, rate: f64},
+}
+
+impl MapEntry {
+    #[must_use]
+    pub fn chromosome(&self) -> ChrIdx {
+        match self {
+            Self::Rate{chr, ..} | Self::Position{chr, ..} | Self::Range{chr, ..} => *chr,
+        }
+    }
+}
+
+impl MapFormat {
+    /// Detect the format of a genetic map, using its first line (i.e. its header, if any).
+    #[must_use]
+    pub fn detect(line: &str) -> Option<Self> {
+        let fields = line.split_whitespace().map(str::to_ascii_lowercase).collect::<Vec<String>>();
+        match fields.iter().map(String::as_str).collect::<Vec<&str>>().as_slice() {
+            [_, _, cm, pos] if cm.parse::<f64>().is_ok() && pos.parse::<u32>().is_ok() => Some(Self::Plink),
+            ["chr", "position", _, _] => Some(Self::Eagle),
+            [_, _, _, _]              => Some(Self::HapMap),
+            [_, "begin", "end", _, _] => Some(Self::Decode),
+            ["pos", "chr", "cm"]      => Some(Self::Bherer),
+            _                         => None,
+        }
+    }
+
+    /// Check whether the first line of a genetic map is a header (`true`), or an entry (`false`).
+    #[must_use]
+    pub fn has_header(self) -> bool {
+        self != Self::Plink
+    }
+
+    /// Parse a single line of a genetic map.
+    /// # Arguments
+    /// - `line`: line of the genetic map.
+    /// - `i`   : 0-based index of the line, used for error reporting.
+    ///
+    /// # Errors
+    /// - `InvalidFields` if the line does not contain the expected number of fields.
+    /// - `ParseChr`, `ParsePos` or `ParseRate` if any of the fields contains invalid information.
+    pub fn parse(self, line: &str, i: usize) -> Result<MapEntry> {
+        use GeneticMapError::{InvalidFields, ParseChr, ParsePos, ParseRate};
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let expected = match self {
+            Self::HapMap | Self::Eagle | Self::Plink => 4,
+            Self::Decode => 5,
+            Self::Bherer => 3,
+        };
+        if fields.len() != expected {
+            return Err(InvalidFields(i)).with_loc(|| format!("Expected {expected} fields, got {}", fields.len()))
+        }
+
+        let chr   = |field: &str| self.chromosome(field).with_loc(|| ParseChr(i));
+        let pos   = |field: &str| field.parse::<u32>().with_loc(|| ParsePos(i));
+        let float = |field: &str| field.parse::<f64>().with_loc(|| ParseRate(i));
+        Ok(match self {
+            Self::HapMap | Self::Eagle => MapEntry::Rate{chr: chr(fields[0])?, stop: pos(fields[1])?, rate: float(fields[2])?},
+            Self::Plink  => MapEntry::Position{chr: chr(fields[0])?, stop: pos(fields[3])?, cm: float(fields[2])?},
+            Self::Decode => MapEntry::Range{chr: chr(fields[0])?, start: pos(fields[1])?, stop: pos(fields[2])?, rate: float(fields[3])?},
+            Self::Bherer => MapEntry::Position{chr: chr(fields[1])?, stop: pos(fields[0])?, cm: float(fields[2])?},
+        })
+    }
+
+    /// Parse the chromosome field of an entry. Eagle and PLINK maps follow PLINK chromosome codes.
+    fn chromosome(self, field: &str) -> Result<ChrIdx, ChrIdxError> {
+        match self {
+            Self::Eagle | Self::Plink => ChrIdx::from_plink(field),
+            _                         => field.parse(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        let headers = [
+            ("Chromosome\tPosition(bp)\tRate(cM/Mb)\tMap(cM)",            Some(MapFormat::HapMap)),
+            ("chr position COMBINED_rate(cM/Mb) Genetic_Map(cM)",         Some(MapFormat::Eagle)),
+            ("1\trs123\t0.587\t752566",                                   Some(MapFormat::Plink)),
+            ("Chr\tBegin\tEnd\tcMperMb\tcM",                              Some(MapFormat::Decode)),
+            ("pos\tchr\tcM",                                              Some(MapFormat::Bherer)),
+            ("position COMBINED_rate(cM/Mb) Genetic_Map(cM)",             None),
+            ("",                                                          None),
+        ];
+        for (header, want) in headers {
+            assert_eq!(MapFormat::detect(header), want, "{header}");
+        }
+    }
+
+    #[test]
+    fn parse() -> Result<()> {
+        assert_eq!(MapFormat::Eagle.parse("23 2699520 0.5 0.01", 1)?, MapEntry::Rate{chr: ChrIdx::X, stop: 2_699_520, rate: 0.5});
+        assert_eq!(MapFormat::Plink.parse("1 rs123 0.587 752566", 0)?, MapEntry::Position{chr: ChrIdx(1), stop: 752_566, cm: 0.587});
+        assert_eq!(MapFormat::Plink.parse("23 rs456 0.1 2699521", 0)?, MapEntry::Position{chr: ChrIdx::X, stop: 2_699_521, cm: 0.1});
+        assert_eq!(MapFormat::Plink.parse("25 rs789 0.0 60001", 0)?, MapEntry::Position{chr: ChrIdx::X, stop: 60_001, cm: 0.0});
+        assert_eq!(MapFormat::Decode.parse("chr1\t10\t20\t1.5\t0.1", 1)?, MapEntry::Range{chr: ChrIdx(1), start: 10, stop: 20, rate: 1.5});
+        assert_eq!(MapFormat::Bherer.parse("55550\tchr1\t0.0", 1)?, MapEntry::Position{chr: ChrIdx(1), stop: 55550, cm: 0.0});
+
+        let err = MapFormat::Bherer.parse("55550\tchr1", 7);
+        assert!(err.is_err_and(|e| matches!(e.downcast_ref::<GeneticMapError>(), Some(GeneticMapError::InvalidFields(7)))));
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 use std::{collections::HashMap, path::{Path, PathBuf}, io::{BufReader, BufRead}, fs::{self, File}};
 
+use flate2::read::MultiGzDecoder;
 use rust_lapper::{Interval, Lapper};
 
 use located_error::prelude::*;
+use log::debug;
 //use anyhow::{anyhow, Result};
 
 mod error;
@@ -11,8 +13,19 @@ use error::GeneticMapError;
 mod recomb_range;
 use recomb_range::RecombinationRange;
 
-use crate::coordinate::{Coordinate, Position, ChrIdx};
+mod format;
+use format::{MapEntry, MapFormat};
 
+mod chunk;
+use chunk::MapChunk;
+
+use crate::{coordinate::{Coordinate, Position, ChrIdx}, Genome};
+
+/// File extensions of genetic recombination maps. Each of these may additionally carry a `.gz` extension.
+const MAP_EXTENSIONS: [&str; 3] = ["txt", "map", "tsv"];
+
+/// Leading magic bytes of gzip (and BGZF) compressed files.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 
 /// `HashMap` of Genetic Maps, in the form of a BITS Tree (Key = chromosome name | Value = Interval Tree)
 /// 
@@ -24,14 +37,38 @@ impl GeneticMap {
     /// Instantiate a `GeneticMap` from an OS directory.
     /// 
     /// # Arguments
-    /// - `dir`: path leading to a directory containing genetic recombination maps (`.txt`)
+    /// - `dir`: path leading to a directory containing genetic recombination maps (`.txt`, `.map` or `.tsv`, optionally
+    ///   gzip-compressed)
     /// 
     /// # Errors
     /// - if no genetic map was found within the input directory
     pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        Self::from_files(Self::fetch_genetic_maps(dir)?)
+    }
+
+    /// Instantiate a `GeneticMap` from an explicit list of genetic recombination map files. The maps of a chromosome
+    /// which is spread across multiple files are merged in order of position (see `MapChunk::merge()`).
+    /// 
+    /// # Errors
+    /// - if any of the provided maps cannot be parsed (see `GeneticMap::parse_map()`)
+    /// - `OverlappingMaps` if two maps cover overlapping regions of the same chromosome.
+    /// - if `maps` is empty.
+    pub fn from_files(maps: impl IntoIterator<Item = impl AsRef<Path>>) -> Result<Self> {
+        // ---- Parse every map, while grouping their recombination ranges per chromosome.
+        let mut chunks: HashMap<ChrIdx, Vec<MapChunk>> = HashMap::new();
+        for map in maps {
+            let map = map.as_ref();
+            let parsed = Self::parse_map(map).with_loc(|| GeneticMapError::ParseMap{map: map.to_path_buf()})?;
+            for (chr, chunk) in parsed {
+                chunks.entry(chr).or_default().push(chunk);
+            }
+        }
+
+        // ---- Merge the maps of each chromosome into a single BITS tree.
         let mut out = Self::default();
-        for map in Self::fetch_genetic_maps(dir)? {
-            out.from_map(&map).with_loc(|| GeneticMapError::ParseMap{map: map.clone()})?;
+        for (chr, chunks) in chunks {
+            let intervals = MapChunk::merge(chr, chunks).loc("While merging genetic maps")?;
+            out.0.insert(chr, Lapper::new(intervals));
         }
 
         match out.0.keys().len() {
@@ -40,67 +77,120 @@ impl GeneticMap {
         }.loc(GeneticMapError::EmptyDir)
     }
 
-    /// Parse a BITS tree from a given genetic recombination map file, and add it to `self.0`
-    /// Expected fields of a recombination map file are:
-    ///  <Chromosome>    <Position(bp)>    <Recomb. rate (cM/Mb)>    <Map(cM)>
+    /// Parse the recombination ranges of each chromosome from a given genetic recombination map file. The format of
+    /// the file is detected from its first line (See `MapFormat`). Gzip-compressed files are transparently decompressed.
+    /// 
+    /// Supported layouts are:
+    /// - HapMap        : `<Chromosome>    <Position(bp)>    <Recomb. rate (cM/Mb)>    <Map(cM)>`
+    /// - SHAPEIT/Eagle : `chr position COMBINED_rate(cM/Mb) Genetic_Map(cM)`
+    /// - PLINK (`.map`): `<Chromosome>    <Variant id>    <Map(cM)>    <Position(bp)>` (headerless)
+    /// - deCODE        : `Chr    Begin    End    cMperMb    cM`
+    /// - Bhérer et al. : `pos    chr    cM`
+    /// 
+    /// Recombination rates of PLINK and Bhérer et al. maps are interpolated from the genetic positions of consecutive
+    /// entries.
     /// 
     /// # Arguments
     /// - `path`: path leading to a genetic recombination map
     /// 
     /// # Errors
     /// - when the value of `path` is not found and/or does not have read permissions
+    /// - `UnknownFormat` if the format of the map cannot be detected from its first line.
     /// - can return either `ParseIntError` or `ParseFloatError` if one of the fields
     ///   contains invalid information. 
-    pub fn from_map(&mut self, path: impl AsRef<Path> ) -> Result<()> {
-        use GeneticMapError::{InvalidFields, InvalidLine, ParseChr, ParsePos, ParseRate};
+    fn parse_map(path: &Path) -> Result<HashMap<ChrIdx, MapChunk>> {
+        use GeneticMapError::{InvalidLine, UnknownFormat};
 
-        // ---- Open the genetic recombination file and initialize HashMap
-        let source = BufReader::new(File::open(path).loc("Failed to open file")?);
-        let mut intervals: HashMap<ChrIdx, Vec<Interval<u32, RecombinationRange>>> = HashMap::new();
+        // ---- Open the genetic recombination file and detect its format from its first line.
+        let mut lines = Self::open(path).loc("Failed to open file")?.lines().enumerate().peekable();
+        let format = match lines.peek() {
+            Some((_, Ok(line))) => MapFormat::detect(line),
+            _                   => None,
+        }.ok_or(UnknownFormat).loc("While detecting the format of the genetic map")?;
+        debug!("Genetic map format of {}: {format:?}", path.display());
+
+        if format.has_header() {
+            lines.next(); // Skip header
+        }
 
         // ---- Parse file and generate intervals from each line.
-        let mut start = 0;
-        for (i, line) in source.lines().enumerate().skip(1) { // Skip header
+        let mut chunks: HashMap<ChrIdx, MapChunk> = HashMap::new();
+        let mut genetic_positions: HashMap<ChrIdx, f64> = HashMap::new(); // Last known position (cM) of each chromosome.
+        for (i, line) in lines {
             let line = line.with_loc(|| InvalidLine(i))?;
-            if line.is_empty(){ continue } // Skip empty lines.
-            let fields: Vec<&str> = line.split('\t').collect::<Vec<&str>>();
+            if line.trim().is_empty(){ continue } // Skip empty lines.
 
-            match fields.len() { // baild if we don't have 4 fields.
-                4 => Ok(()),
-                n => Err(InvalidFields(i)).with_loc(|| format!("Expected 4 fields, got {n}"))//).with_loc(|| InvalidFields(i))
-            }?;
-
-            let chr : ChrIdx = fields[0].parse().with_loc(|| ParseChr(i))?;
-            let stop: u32    = fields[1].parse::<u32>().with_loc(|| ParsePos(i))?;
-            let rate: f64    = fields[2].parse::<f64>().with_loc(|| ParseRate(i))?;
+            let entry = format.parse(&line, i)?;
+            let chunk = chunks.entry(entry.chromosome())
+                .or_insert_with(|| MapChunk::new(path.to_path_buf(), matches!(entry, MapEntry::Range{..})));
+            let previous = chunk.intervals.last().map_or(0, |interval| interval.stop);
+            let (start, stop, rate) = match entry {
+                MapEntry::Rate{stop, rate, ..}         => (previous, stop, rate),
+                MapEntry::Range{start, stop, rate, ..} => (start, stop, rate),
+                MapEntry::Position{chr, stop, cm}      => {
+                    let previous_cm = genetic_positions.insert(chr, cm).unwrap_or(0.0);
+                    let rate = match stop > previous {
+                        true  => (cm - previous_cm).max(0.0) / f64::from(stop - previous) * 1_000_000.0, // cM/Mb
+                        false => 0.0,
+                    };
+                    (previous, stop, rate)
+                },
+            };
 
             // ---- Instantiate a new Interval, tied to a recombination range.
-            let interval = Interval{ start, stop, val: RecombinationRange::new(start, stop, rate) };
-            intervals.entry(chr).or_default().push(interval);
-            start = stop;
+            chunk.intervals.push(Interval{ start, stop, val: RecombinationRange::new(start, stop, rate) });
         }
+        Ok(chunks)
+    }
 
-        // ---- Internalize intervals within `self`
-        self.0.extend(intervals.into_iter().map(|(c,int)| (c, Lapper::new(int))));
-
-        Ok(())
+    /// Open a genetic recombination map, while transparently decompressing it if it is gzip-compressed.
+    fn open(path: &Path) -> std::io::Result<Box<dyn BufRead>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Ok(match reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+            true  => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+            false => Box::new(reader),
+        })
     }
 
-    /// Iterate through the contents of an OS directory and return a list of found `.txt` files.
+    /// Iterate through the contents of an OS directory and return a list of found genetic maps (`.txt`, `.map` or
+    /// `.tsv`, optionally followed by `.gz`).
     /// # Arguments
-    /// - `input_dir`: path leading to a directory containing genetic recombination maps (`.txt`)
+    /// - `input_dir`: path leading to a directory containing genetic recombination maps.
     fn fetch_genetic_maps(input_dir: impl AsRef<Path>) -> Result<impl Iterator <Item = PathBuf>> {
         // Get a list of files within the specified directory
         let paths = fs::read_dir(input_dir).with_loc(|| GeneticMapError::ReadDir)?
             .filter_map(Result::ok)        
-            .filter(|f| {
-                // Filter out anything that does not end with the .txt file extension (case insensitive).
-                f.path().extension().is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
-            }).map(|f| f.path());
+            .map(|f| f.path())
+            .filter(|path| {
+                // Filter out anything that does not end with a genetic map file extension (case insensitive).
+                let path = match path.extension() {
+                    Some(ext) if ext.eq_ignore_ascii_case("gz") => path.with_extension(""),
+                    _                                           => path.clone(),
+                };
+                path.extension().is_some_and(|ext| MAP_EXTENSIONS.iter().any(|map_ext| ext.eq_ignore_ascii_case(map_ext)))
+            });
         
         Ok(paths)
     }
 
+    /// Ensure none of the recombination ranges extend beyond the end of their chromosome. Chromosomes which are not
+    /// part of `genome` are ignored.
+    /// 
+    /// # Errors
+    /// - `OutOfBounds` if a recombination range ends past the length of its chromosome. This usually means the genetic
+    ///   maps were not generated using the same genome build as `genome`.
+    pub fn check_bounds(&self, genome: &Genome) -> Result<()> {
+        for (chr, intervals) in &self.0 {
+            let Some(chromosome) = genome.get(chr) else { continue };
+            let stop = intervals.intervals.iter().map(|interval| interval.stop).max().unwrap_or(0);
+            if stop > chromosome.length {
+                return Err(GeneticMapError::OutOfBounds{chr: *chr, stop, length: chromosome.length})
+                    .loc("While checking genetic maps against the reference genome")
+            }
+        }
+        Ok(())
+    }
+
     /// Compute a probability of genetic recombination on a given chromosome, within a range
     /// (typically, the current and the previous position)
     /// # Parameters
@@ -182,6 +272,99 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn check_bounds() -> anyhow::Result<()> {
+        use crate::Chromosome;
+        let tmpdir    = tempfile::tempdir()?;
+        let file_path = tmpdir.path().join("map.txt");
+        let mut file  = File::create(file_path.clone())?;
+        writeln!(file, "\
+            Chromosome\tPosition(bp)\tRate(cM/Mb)\tMap(cM)\n\
+            chr1\t50000\t0.10\t0.000\n\
+            chr1\t100000\t0.10\t0.002\n\
+            "
+        )?;
+        let map = GeneticMap::from_dir(tmpdir.path())?;
+
+        assert!(map.check_bounds(&Genome::from(&[Chromosome::new(1, 100_000)])).is_ok());
+        assert!(map.check_bounds(&Genome::from(&[Chromosome::new(2, 10)])).is_ok()); // Missing chromosomes are ignored.
+
+        let err = map.check_bounds(&Genome::from(&[Chromosome::new(1, 99_999)]));
+        assert!(err.is_err_and(|e| matches!(e.downcast_ref::<GeneticMapError>(), Some(GeneticMapError::OutOfBounds{stop: 100_000, ..}))));
+        Ok(())
+    }
+
+    #[test]
+    fn gzipped_plink_map() -> anyhow::Result<()> {
+        use flate2::{write::GzEncoder, Compression};
+        let tmpdir    = tempfile::tempdir()?;
+        let file_path = tmpdir.path().join("chr1.map.gz");
+        let mut file  = GzEncoder::new(File::create(file_path.clone())?, Compression::default());
+        write!(file, "1 rs1 0.0 1000000\n1 rs2 1.0 2000000\n1 rs3 1.0 3000000\n")?;
+        file.finish()?;
+
+        let map = GeneticMap::from_dir(tmpdir.path())?;
+        let rates = map.0[&ChrIdx(1)].intervals.iter().map(|interval| interval.val.rate()).collect::<Vec<f64>>();
+        assert_eq!(rates.len(), 3);
+        assert!(rates.iter().zip([0.0, 1.0, 0.0]).all(|(got, want)| (got - want).abs() < 1e-9), "{rates:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn from_files() -> anyhow::Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let decode = tmpdir.path().join("decode.tsv");
+        let bherer = tmpdir.path().join("female_chrX.txt");
+        writeln!(File::create(&decode)?, "Chr\tBegin\tEnd\tcMperMb\tcM\nchr1\t0\t1000\t0.5\t0.0005\nchr1\t1000\t2000\t1.5\t0.002")?;
+        writeln!(File::create(&bherer)?, "pos\tchr\tcM\n1000\tX\t0.0\n2000\tX\t0.002")?;
+        writeln!(File::create(tmpdir.path().join("notes.txt"))?, "Some notes about these maps")?;
+
+        let map = GeneticMap::from_files([&decode, &bherer])?;
+        assert_eq!(map.0[&ChrIdx(1)].intervals.len(), 2);
+        assert!(map.0[&ChrIdx::X].intervals.last().is_some_and(|interval| (interval.val.rate() - 2.0).abs() < 1e-9));
+
+        // ---- Unrelated files are not skipped when loading an entire directory.
+        assert!(GeneticMap::from_dir(tmpdir.path()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn merge_maps() -> anyhow::Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let par1   = tmpdir.path().join("chrX_par1.txt");
+        let chrx   = tmpdir.path().join("chrX.txt");
+        writeln!(File::create(&par1)?, "Chromosome\tPosition(bp)\tRate(cM/Mb)\tMap(cM)\nchrX_par1\t1000000\t1.0\t0.0\nchrX_par1\t2699520\t1.0\t0.0")?;
+        writeln!(File::create(&chrx)?, "Chromosome\tPosition(bp)\tRate(cM/Mb)\tMap(cM)\nchrX\t3000000\t2.0\t0.0\nchrX\t154931043\t2.0\t0.0")?;
+
+        // ---- The first range of chrX.txt starts where chrX_par1.txt ends, regardless of the order of the files.
+        let across_boundary = Coordinate::new(ChrIdx::X, 2_800_000);
+        let expected = 1e-8 * f64::from(2_699_520 - 2_600_000 + 1) + 2e-8 * f64::from(2_800_000 - 2_699_520 + 1);
+        let expected = 0.5 * (1.0 - f64::exp(-2.0 * expected));
+        for files in [[&par1, &chrx], [&chrx, &par1]] {
+            let map = GeneticMap::from_files(files)?;
+            let ranges = map.0[&ChrIdx::X].intervals.iter().map(|interval| (interval.start, interval.stop)).collect::<Vec<_>>();
+            assert_eq!(ranges, [(0, 1_000_000), (1_000_000, 2_699_520), (2_699_520, 3_000_000), (3_000_000, 154_931_043)]);
+            let prob = map.compute_recombination_prob(&across_boundary, Position(2_600_000));
+            assert!((prob - expected).abs() < 1e-12, "{prob} != {expected}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn overlapping_maps() -> anyhow::Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        for name in ["male_chrX.txt", "female_chrX.txt"] {
+            writeln!(File::create(tmpdir.path().join(name))?, "pos\tchr\tcM\n1000\tX\t0.0\n2000\tX\t0.002")?;
+        }
+        let map = GeneticMap::from_dir(tmpdir.path());
+        assert!(map.is_err_and(|e| matches!(e.downcast_ref::<GeneticMapError>(), Some(GeneticMapError::OverlappingMaps{..}))));
+
+        // ---- Maps of distinct chromosomes never overlap.
+        writeln!(File::create(tmpdir.path().join("female_chrX.txt"))?, "pos\tchr\tcM\n1000\t7\t0.0\n2000\t7\t0.002")?;
+        assert!(GeneticMap::from_dir(tmpdir.path()).is_ok());
+        Ok(())
+    }
+
     #[test]
     fn x_chromosome() -> anyhow::Result<()> {
         let tmpdir    = tempfile::tempdir()?;
@@ -202,7 +385,7 @@ mod tests {
 
         //
         assert_eq!(map.0.keys().len(), 1);        // Should only contain a single key, for X-chromosome
-        assert!(map.0.contains_key(&ChrIdx(88))); // ASCII encoding
+        assert!(map.0.contains_key(&ChrIdx::X));
         Ok(())
     }
 }
\ No newline at end of file
@@ -29,6 +29,11 @@ impl RecombinationRange {
         RecombinationRange{range: Range{start, end}, prob}
     }
 
+    /// Move the start of this range, while keeping its probability of recombination per base.
+    pub fn with_start(self, start: u32) -> RecombinationRange {
+        RecombinationRange{range: Range{start, end: self.range.end}, ..self}
+    }
+
     /// Return the probability of a recombination occuring within this range.
     pub fn prob(&self) -> &f64 {
         &self.prob
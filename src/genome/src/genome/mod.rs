@@ -8,7 +8,7 @@ use std::{
 
 use crate::{
     chromosome::Chromosome,
-    coordinate::ChrIdx
+    coordinate::{ChrIdx, Coordinate}
 };
 
 mod error;
@@ -16,9 +16,11 @@ use error::FastaIndexReaderError;
 
 use log::{warn, info, debug};
 
-/// `BTreeMap` of chromosomes, with Key: chromosome name (u8) | Value: Chromosome
+/// `BTreeMap` of chromosomes, with Key: chromosome name (`ChrIdx`) | Value: Chromosome
+/// 
+/// The order in which chromosomes were added is kept alongside (see `Genome::order()`).
 #[derive(Debug, Clone)]
-pub struct Genome(BTreeMap<ChrIdx, Chromosome>);
+pub struct Genome(BTreeMap<ChrIdx, Chromosome>, Vec<ChrIdx>);
 
 impl Deref for Genome {
     type Target = BTreeMap<ChrIdx, Chromosome>;
@@ -33,7 +35,7 @@ impl Genome {
     /// # @TODO convert this into default()
     #[must_use]
     pub fn new() -> Genome {
-        Genome(BTreeMap::new())
+        Genome(BTreeMap::new(), Vec::new())
     }
 
     /// Create a new genome from a slice of Chromosomes
@@ -47,6 +49,9 @@ impl Genome {
     }
 
     /// Read a `.fasta.fai` file and parse it into a vector of Chromosome structs.
+    /// 
+    /// Every contig name found within the index is registered within the process-wide contig dictionary (see
+    /// `ChrIdx::register()`), in order of appearance. Contigs are thus assigned the same id by every subsequent reader.
     /// # Arguments
     /// - `path`: Path leading to either a `.fasta`, `.fasta.fai`, `.fa` or `.fa.fai` file.
     ///   In the case of a `.fasta` or `.fa` file, a companion `.fai` with a matching file-stem
@@ -62,7 +67,9 @@ impl Genome {
     ///   - `path` extension is neither (`.fa`, `.fasta` or `.fai`)
     /// - returns `FastaIndexReaderError::FileNotFound` if no matching `.fai` file could be found within the 
     ///   target directory
-    /// - returns `FastaIndexReaderError::ParseInt` if there is an invalid length value at column 1
+    /// - returns `FastaIndexReaderError::ParseLine` if a line of the index cannot be read.
+    /// 
+    /// Lines carrying an invalid length value at column 1 are skipped.
     pub fn from_fasta_index(path: &str) -> Result<Genome,FastaIndexReaderError> {
         use FastaIndexReaderError::{InvalidExt, FileNotFound, ParseLine};
         info!("Parsing reference genome: {path}");
@@ -107,21 +114,50 @@ impl Genome {
     }
 
     pub fn add_chromosome(&mut self, chromosome: Chromosome) -> Option<Chromosome> {
-        self.0.insert(chromosome.name, chromosome)
+        let previous = self.0.insert(chromosome.name, chromosome);
+        if previous.is_none() {
+            self.1.push(chromosome.name);
+        }
+        previous
+    }
+
+    /// Chromosomes of the reference genome, in order of appearance (i.e. following the `.fai` index). Chromosomes
+    /// removed through `Genome::pop()` or `Genome::retain()` are kept, so that every genome split from the same
+    /// reference shares the same order.
+    #[must_use]
+    pub fn order(&self) -> &[ChrIdx] {
+        &self.1
     }
 
     pub fn pop_xchr(&mut self) -> Option<Genome> {
-        let idx = ChrIdx(b'X');
-        self.0.remove(&idx).map(|chr| Genome(BTreeMap::from([(idx, chr)])))
+        self.pop(ChrIdx::X)
+    }
+
+    /// Remove a chromosome from this genome, and return it as a new, single-chromosome genome.
+    pub fn pop(&mut self, idx: ChrIdx) -> Option<Genome> {
+        self.0.remove(&idx).map(|chr| Genome(BTreeMap::from([(idx, chr)]), self.1.clone()))
+    }
+
+    /// Only keep the chromosomes satisfying the predicate `f`. Returns the ids of the removed chromosomes.
+    pub fn retain(&mut self, mut f: impl FnMut(&Chromosome) -> bool) -> Vec<ChrIdx> {
+        let mut removed = Vec::new();
+        self.0.retain(|name, chr| f(chr) || {removed.push(*name); false});
+        removed
     }
 
+    /// Check whether a coordinate lies beyond the end of its chromosome. Coordinates located on chromosomes which are
+    /// not part of this genome are never considered out of bounds.
+    #[must_use]
+    pub fn out_of_bounds(&self, coordinate: &Coordinate) -> bool {
+        self.0.get(&coordinate.chromosome).is_some_and(|chr| u32::from(coordinate.position) > chr.length)
+    }
 }
 
 /// Simply returns a default genome index in case the user did not provide a specific .fasta.fai file. 
 impl Default for Genome {
     fn default() -> Self {
         warn!("No reference genome provided. Using default.");
-        Genome(BTreeMap::from([
+        let chromosomes = BTreeMap::from([
             (ChrIdx::from( 1), Chromosome::new( 1, 249_250_621)),
             (ChrIdx::from( 2), Chromosome::new( 2, 243_199_373)),
             (ChrIdx::from( 3), Chromosome::new( 3, 198_022_430)),
@@ -144,8 +180,10 @@ impl Default for Genome {
             (ChrIdx::from(20), Chromosome::new(20,  63_025_520)),
             (ChrIdx::from(21), Chromosome::new(21,  48_129_895)),
             (ChrIdx::from(22), Chromosome::new(22,  51_304_566)),
-            (ChrIdx::from(b'X'), Chromosome::new(b'X', 155_270_560))
-        ]))
+            (ChrIdx::X,          Chromosome::new(ChrIdx::X, 155_270_560))
+        ]);
+        let order = chromosomes.keys().copied().collect();
+        Genome(chromosomes, order)
     }
 }
 
@@ -155,6 +193,7 @@ mod tests {
     use tempfile::tempdir;
     use std::{io::Write, fs::File};
     use anyhow::Result;
+    use crate::coordinate::Position;
 
     #[test]
     fn from_fasta_index_fai() -> Result<()> {
@@ -227,12 +266,70 @@ mod tests {
         let tmpdir = tempdir()?;
         let fai_path = tmpdir.path().join("genome.fa.fai");
         let mut file = File::create(fai_path.clone())?;
-        writeln!(file, "1\t249250621\t52\t60\t61\nY\t123456789\t52\t60\t61")?;
+        writeln!(file, "1\t249250621\t52\t60\t61\nY\tinvalid\t52\t60\t61")?;
         let genome = Genome::from_fasta_index(fai_path.to_str().expect("Invalid path")).expect("Failed to generate genome");
         assert_eq!(genome.keys().len(), 1);
         Ok(())
     }
 
+    #[test]
+    fn arbitrary_contigs() -> Result<()> {
+        let tmpdir = tempdir()?;
+        let fai_path = tmpdir.path().join("genome.fa.fai");
+        let mut file = File::create(fai_path.clone())?;
+        writeln!(file, "\
+            chr1\t248956422\t112\t70\t71\n\
+            chrY\t57227415\t52\t60\t61\n\
+            chrM\t16569\t52\t60\t61\n\
+            NC_037328.1\t158534110\t52\t60\t61\n\
+            chr1_KI270706v1_random\t175055\t52\t60\t61\
+            "
+        )?;
+        let genome = Genome::from_fasta_index(fai_path.to_str().expect("Invalid path")).expect("Failed to generate genome");
+        assert_eq!(genome.keys().len(), 5);
+        assert_eq!(genome[&ChrIdx(1)].length, 248_956_422);
+        assert_eq!(genome[&ChrIdx::Y].length, 57_227_415);
+        assert_eq!(genome[&ChrIdx::MT].length, 16_569);
+        for name in ["NC_037328.1", "chr1_KI270706v1_random"] {
+            let chr: ChrIdx = name.parse()?;
+            assert!(genome.contains_key(&chr));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn order() -> Result<()> {
+        let tmpdir = tempdir()?;
+        let fai_path = tmpdir.path().join("genome.fa.fai");
+        writeln!(File::create(&fai_path)?, "chrM\t16571\t6\t50\t51\nchr2\t243199373\t52\t60\t61\nchr1\t249250621\t52\t60\t61\nchrX\t155270560\t52\t60\t61")?;
+        let mut genome = Genome::from_fasta_index(fai_path.to_str().expect("Invalid path"))?;
+        let order = [ChrIdx::MT, ChrIdx(2), ChrIdx(1), ChrIdx::X];
+        assert_eq!(genome.order(), order);
+
+        // ---- Order is kept when splitting the genome.
+        let xchr = genome.pop_xchr().expect("Missing X chromosome");
+        genome.retain(|chr| chr.name.is_numbered());
+        assert_eq!(genome.order(), order);
+        assert_eq!(xchr.order(), order);
+        Ok(())
+    }
+
+    #[test]
+    fn retain() {
+        let mut genome = Genome::default();
+        let removed = genome.retain(|chr| chr.name.is_numbered());
+        assert_eq!(removed, vec![ChrIdx::X]);
+        assert_eq!(genome.len(), 22);
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let genome = Genome::from(&[Chromosome::new(1, 1000)]);
+        assert!(!genome.out_of_bounds(&Coordinate::new(ChrIdx(1), Position(1000))));
+        assert!( genome.out_of_bounds(&Coordinate::new(ChrIdx(1), Position(1001))));
+        assert!(!genome.out_of_bounds(&Coordinate::new(ChrIdx(2), Position(1001))));
+    }
+
     #[test]
     fn file_not_found() -> Result<()> {
         let tmpdir        = tempdir()?;
@@ -0,0 +1,7 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum JackknifeBlocksError {
+    #[error("Block overlaps with a previously defined block @ line {0}")]
+    OverlappingBlocks(usize),
+}
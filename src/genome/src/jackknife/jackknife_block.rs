@@ -19,7 +19,7 @@ impl Pseudovalue {
 ///Chromosome Block for Jackknife resampling. Implemented within struct `JackknifeBlocks`, which is itself implemented
 ///within structs `pwd_from_stdin::pileup::Comparison`
 /// # Fields
-/// - `chromosome`  : chromosome name (warn: not by index -> cannot handle specific names, such as "X", "MT", "chr7", etc.)
+/// - `chromosome`  : chromosome name (see `ChrIdx`)
 /// - `range`       : `[start, end[` coordinates of the block.
 /// - `site_counts` : counts the number of overlapping SNPs for a given pair of individuals.
 /// - `pwd_counts`  : counts the number of pairwise differences for a given pair of individuals.
@@ -31,18 +31,18 @@ impl Pseudovalue {
 ///       See: `Self::find_block()` or `JackknifeBlocks::find_blocks()`
 ///  - `Hash`               : along `chromosome` and `range` fields.
 ///  - `Display`            : Pretty print for file and/or console output Recursively called by `JackknifeBlocks` when it itself is displayed.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct JackknifeBlock {
-    pub chromosome : ChrIdx,
-    pub range      : Range<Position>,
-    site_counts    : u32,
-    pwd_counts     : f64,
+    pub chromosome         : ChrIdx,
+    pub range              : Range<Position>,
+    pub(crate) site_counts : u32,
+    pub(crate) pwd_counts  : f64,
 }
 
 impl JackknifeBlock {
     /// Instantiate a new Jackknife block from an interval
     /// # Arguments:
-    /// - `chromosome`: name of the chromosome (u8 cast)
+    /// - `chromosome`: name of the chromosome
     /// - `start`     : 0-based start-coordinate of the block.
     /// - `end`       : 0-based end-coordinate of the block
     #[must_use]
@@ -61,6 +61,25 @@ impl JackknifeBlock {
         self.site_counts += 1;
     }
 
+    /// Set the number of sites and the sum of pairwise differences of this block, e.g. when restoring a block from a
+    /// previous run.
+    #[must_use]
+    pub fn with_counts(self, site_counts: u32, pwd_counts: f64) -> JackknifeBlock {
+        JackknifeBlock{site_counts, pwd_counts, ..self}
+    }
+
+    /// Number of overlapping sites found within this block.
+    #[must_use]
+    pub fn site_counts(&self) -> u32 {
+        self.site_counts
+    }
+
+    /// Sum of the local pairwise differences found within this block.
+    #[must_use]
+    pub fn pwd_counts(&self) -> f64 {
+        self.pwd_counts
+    }
+
     #[must_use]
     pub fn compute_unequal_delete_m_pseudo_value(&self, sum_pwd: f64, sum_overlap: u32) -> Pseudovalue {
         // Cast once, compute later...
@@ -1,11 +1,13 @@
 use crate::genome::Genome;
 use itertools::Itertools;
-use std::fmt::{self, Display, Formatter};
+use std::{collections::BTreeMap, fmt::{self, Display, Formatter}, iter, path::Path};
 
-use super::JackknifeBlock;
-use crate::coordinate::{ChrIdx, Coordinate};
+use super::{JackknifeBlock, JackknifeBlocksError};
+use crate::bed::{self, BedInterval};
+use crate::coordinate::{ChrIdx, Coordinate, Position};
 
 use ahash::AHashMap;
+use located_error::prelude::*;
 
 #[derive(Debug)]
 pub struct JackknifeEstimates {
@@ -13,7 +15,15 @@ pub struct JackknifeEstimates {
     pub variance: f64,
 }
 
-#[derive(Debug)]
+impl JackknifeEstimates {
+    /// Jackknife standard error of the estimate.
+    #[must_use]
+    pub fn standard_error(&self) -> f64 {
+        self.variance.sqrt()
+    }
+}
+
+#[derive(Debug, Clone)]
 /// A simple struct representing a `HashMap` of `JackknifeBlock` for a given
 /// genome. Implemented within struct Comparison. See: `pwd_from_stdin::pileup::Comparison`
 ///   - `HashMap` is indexed according to the chromosome name.
@@ -48,6 +58,89 @@ impl JackknifeBlocks {
         JackknifeBlocks{blocks: jackknives}
     }
 
+    /// Instantiate a set of jackknife blocks from the intervals of a BED file (e.g. LD blocks, chromosome arms).
+    /// 
+    /// Intervals are expected in the standard BED format (0-based, half-open), and must not overlap. Regions of the
+    /// genome that are not covered by any interval are assigned to additional blocks, spanning each gap. Thus,
+    /// chromosomes that are absent from the BED file are covered by a single block. See `bed::read_bed()` for the
+    /// expected file format.
+    /// 
+    /// # Errors
+    /// - if the BED file cannot be opened or parsed.
+    /// - `OverlappingBlocks` if two intervals overlap.
+    pub fn from_bed(path: impl AsRef<Path>, genome: &Genome) -> Result<JackknifeBlocks> {
+        let mut intervals: BTreeMap<ChrIdx, Vec<BedInterval>> = BTreeMap::new();
+        for interval in bed::read_bed(path)? {
+            intervals.entry(interval.chromosome).or_default().push(interval);
+        }
+
+        // ---- Fill the gaps between intervals, and ensure every chromosome of the genome is covered.
+        for chr in genome.values() {
+            intervals.entry(chr.name).or_default();
+        }
+
+        let mut jackknives = AHashMap::with_capacity(intervals.len());
+        for (chromosome, mut chr_intervals) in intervals {
+            chr_intervals.sort_unstable();
+            let mut blocks = Vec::with_capacity(2 * chr_intervals.len() + 1);
+            let mut previous_end = 1;
+            for BedInterval{start, end, line, ..} in chr_intervals {
+                if start < previous_end {
+                    return Err(JackknifeBlocksError::OverlappingBlocks(line)).loc("While parsing jackknife blocks")
+                }
+                if start > previous_end {
+                    blocks.push(JackknifeBlock::new(chromosome, previous_end, start));
+                }
+                blocks.push(JackknifeBlock::new(chromosome, start, end));
+                previous_end = end;
+            }
+
+            let chr_end = genome.get(&chromosome).map_or(0, |chr| chr.length.saturating_add(1));
+            if chr_end > previous_end {
+                blocks.push(JackknifeBlock::new(chromosome, previous_end, chr_end));
+            }
+            jackknives.insert(chromosome, blocks);
+        }
+        Ok(JackknifeBlocks{blocks: jackknives})
+    }
+
+    /// Instantiate a set of jackknife blocks containing an equal number of SNPs, using the coordinates of a set of
+    /// observed SNPs. Blocks never span across chromosomes: any incomplete trailing block of a chromosome is merged
+    /// with the preceding one, meaning blocks contain between `snps_per_block` and `2*snps_per_block - 1` SNPs.
+    /// 
+    /// Note that the returned blocks are empty: use `tally()` to count the observations within each block.
+    #[must_use]
+    pub fn from_snp_counts(coordinates: impl IntoIterator<Item = Coordinate>, snps_per_block: u32) -> JackknifeBlocks {
+        let snps_per_block = snps_per_block.max(1) as usize;
+
+        let mut positions: BTreeMap<ChrIdx, Vec<Position>> = BTreeMap::new();
+        for coordinate in coordinates {
+            positions.entry(coordinate.chromosome).or_default().push(coordinate.position);
+        }
+
+        let blocks = positions.into_iter().map(|(chromosome, mut positions)| {
+            positions.sort_unstable();
+            positions.dedup();
+
+            let mut starts: Vec<Position> = positions.iter().step_by(snps_per_block).copied().collect();
+            if starts.len() > 1 && positions.len() % snps_per_block != 0 {
+                starts.pop();
+            }
+
+            let last = positions[positions.len() - 1];
+            let ends = starts.iter().skip(1).copied().chain(iter::once(Position(last.0.saturating_add(1))));
+            let blocks = starts.iter().zip(ends).map(|(start, end)| JackknifeBlock::new(chromosome, *start, end));
+            (chromosome, blocks.collect())
+        }).collect();
+
+        JackknifeBlocks{blocks}
+    }
+
+    /// Iterate over every block, sorted by chromosome.
+    pub fn iter(&self) -> impl Iterator<Item = &JackknifeBlock> {
+        self.blocks.keys().sorted().flat_map(|chr| &self.blocks[chr])
+    }
+
     /// Search for a given block, using an `SNPCoord` struct.
     /// Return the block containing the `SNPCoord` position. 
     pub fn find_block(&mut self, coordinate: &Coordinate) -> Option<&mut JackknifeBlock> {
@@ -59,37 +152,79 @@ impl JackknifeBlocks {
     /// Compute jackknifed avg. PWD estimate for each chromosome block
     #[must_use]
     pub fn compute_unequal_delete_m_pseudo_values(&self, sum_pwd: f64, sum_overlap: u32) -> JackknifeEstimates {
+        Self::delete_m_estimates(self.blocks.values().flatten(), sum_pwd, sum_overlap)
+    }
+
+    /// Compute jackknifed avg. PWD estimates, using only the blocks of a given chromosome. Returns `None` if the
+    /// chromosome is not part of the genome.
+    #[must_use]
+    pub fn compute_chromosome_pseudo_values(&self, chromosome: ChrIdx) -> Option<JackknifeEstimates> {
+        let blocks = self.blocks.get(&chromosome)?;
+        let sum_pwd     = blocks.iter().map(|block| block.pwd_counts).sum::<f64>();
+        let sum_overlap = blocks.iter().map(|block| block.site_counts).sum::<u32>();
+        Some(Self::delete_m_estimates(blocks.iter(), sum_pwd, sum_overlap))
+    }
+
+    /// Generate a new set of blocks sharing the same boundaries as `self`, but whose counts are solely computed from
+    /// a given subset of local pairwise differences. Coordinates that do not belong to any block are ignored.
+    #[must_use]
+    pub fn tally(&self, pwds: impl IntoIterator<Item = (Coordinate, f64)>) -> JackknifeBlocks {
+        let blocks = self.blocks.iter()
+            .map(|(chromosome, blocks)| {
+                let blocks = blocks.iter().map(|block| JackknifeBlock::new(block.chromosome, block.range.start, block.range.end));
+                (*chromosome, blocks.collect())
+            })
+            .collect();
+
+        let mut tally = JackknifeBlocks{blocks};
+        for (coordinate, pwd) in pwds {
+            if let Some(block) = tally.find_block(&coordinate) {
+                block.add_count();
+                block.add_pwd(pwd);
+            }
+        }
+        tally
+    }
+
+    /// Unequal delete-m jackknife estimates (Busing et al. 1999), computed across a given set of blocks. Empty blocks
+    /// are not counted as jackknife replicates.
+    fn delete_m_estimates<'a>(blocks: impl Iterator<Item = &'a JackknifeBlock> + Clone, sum_pwd: f64, sum_overlap: u32) -> JackknifeEstimates {
         let mut theta_jk: f64 = 0.0;
-        for chromosome_blocks in self.blocks.values() {
-            for block in chromosome_blocks {
-                let pseudo_value = block.compute_unequal_delete_m_pseudo_value(sum_pwd, sum_overlap);
-                if pseudo_value.hj.is_finite() {
-                    theta_jk += pseudo_value.weigthed_pseudovalue();
-                }
+        for block in blocks.clone() {
+            let pseudo_value = block.compute_unequal_delete_m_pseudo_value(sum_pwd, sum_overlap);
+            if pseudo_value.hj.is_finite() {
+                theta_jk += pseudo_value.weigthed_pseudovalue();
             }
         }
 
         // Compute Jackknife variance estimate 
         let mut var_jk  : f64 = 0.0;
-        for chromosome_blocks in self.blocks.values() {
-            for block in chromosome_blocks {
-                let pseudo_value = block.compute_unequal_delete_m_pseudo_value(sum_pwd, sum_overlap);
-                if pseudo_value.hj.is_finite() {
-                    var_jk += f64::powf(pseudo_value.weigthed_pseudovalue() - theta_jk, 2.0) / (pseudo_value.hj - 1.0);
-                }
+        let mut g       : u32 = 0;
+        for block in blocks {
+            let pseudo_value = block.compute_unequal_delete_m_pseudo_value(sum_pwd, sum_overlap);
+            if pseudo_value.hj.is_finite() {
+                var_jk += f64::powf(pseudo_value.theta_j - theta_jk, 2.0) / (pseudo_value.hj - 1.0);
+                g += 1;
             }
         }
-        #[allow(clippy::cast_possible_truncation)] // Don't think we're going to be facing truncation (we're not working with Z.mays)
-        let g = self.blocks.values().map(Vec::len).sum::<usize>() as u32;
         let var_jk = var_jk / f64::from(g);                                
 
         JackknifeEstimates{estimate: theta_jk, variance: var_jk}
     }
-
-    
 }
 
 // Good Stuff: https://github.com/apolitical/impl-display-for-vec
+impl FromIterator<JackknifeBlock> for JackknifeBlocks {
+    /// Collect a set of blocks. Blocks are expected to be sorted within each chromosome.
+    fn from_iter<I: IntoIterator<Item = JackknifeBlock>>(iter: I) -> Self {
+        let mut blocks: AHashMap<ChrIdx, Vec<JackknifeBlock>> = AHashMap::new();
+        for block in iter {
+            blocks.entry(block.chromosome).or_default().push(block);
+        }
+        JackknifeBlocks{blocks}
+    }
+}
+
 impl Display for JackknifeBlocks {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         self.blocks.keys().sorted().try_fold((), |(), chr  | {
@@ -103,7 +238,7 @@ impl Display for JackknifeBlocks {
 #[cfg(test)]
 mod tests{
     use super::*;
-    use std::fmt::Write;
+    use std::{fmt::Write, io::Write as _};
     use super::super::{CHROM_FORMAT_LEN, COUNT_FORMAT_LEN, RANGE_FORMAT_LEN};
     use crate::chromosome::Chromosome;
 
@@ -150,6 +285,82 @@ mod tests{
         assert_eq!(expected_output, format!("{blocks}"));
     }
 
+    #[test]
+    fn tally_and_chromosome_estimates() {
+        let genome = Genome::from(&[Chromosome::new(1, 10_001), Chromosome::new(2, 10_001)]);
+        let blocks = JackknifeBlocks::new(&genome, 1000);
+
+        let coordinates = [Coordinate::new(1, 10), Coordinate::new(1, 2010), Coordinate::new(2, 10), Coordinate::new(3, 10)];
+        let pwds = [1.0, 0.0, 1.0, 1.0];
+        let tally = blocks.tally(coordinates.into_iter().zip(pwds));
+        assert_eq!(tally.blocks[&ChrIdx::from(1)][0].site_counts, 1);
+        assert_eq!(tally.blocks[&ChrIdx::from(1)][2].site_counts, 1);
+        assert_eq!(tally.blocks[&ChrIdx::from(2)][0].pwd_counts, 1.0);
+        assert_eq!(tally.blocks.values().flatten().map(|block| block.site_counts).sum::<u32>(), 3);
+
+        let estimates = tally.compute_chromosome_pseudo_values(ChrIdx::from(1)).expect("Chromosome 1 should be found");
+        assert!((estimates.estimate - 0.5).abs() < 1e-9);
+        assert!(estimates.standard_error().is_finite());
+        assert!(tally.compute_chromosome_pseudo_values(ChrIdx::from(3)).is_none());
+    }
+
+    #[test]
+    fn from_bed() -> Result<()> {
+        let genome = Genome::from(&[Chromosome::new(1, 10_000), Chromosome::new(2, 10_000)]);
+        let mut bed = tempfile::NamedTempFile::new()?;
+        writeln!(bed, "track name=blocks\n1\t999\t5000\tarm_p\n1\t5000\t9000\tarm_q\nchr3\t0\t100")?;
+        let blocks = JackknifeBlocks::from_bed(bed.path(), &genome)?;
+
+        let ranges = |chr: u8| blocks.blocks[&ChrIdx::from(chr)].iter().map(|block| (block.range.start.0, block.range.end.0)).collect::<Vec<_>>();
+        assert_eq!(ranges(1), vec![(1, 1000), (1000, 5001), (5001, 9001), (9001, 10_001)]);
+        assert_eq!(ranges(2), vec![(1, 10_001)]);
+        assert_eq!(ranges(3), vec![(1, 101)]);
+        Ok(())
+    }
+
+    #[test]
+    fn from_invalid_bed() -> Result<()> {
+        let genome = Genome::default();
+        for contents in ["", "1\t100", "1\t100\t100", "1\t0\t100\n1\t50\t150", "1\tA\t100"] {
+            let mut bed = tempfile::NamedTempFile::new()?;
+            writeln!(bed, "{contents}")?;
+            assert!(JackknifeBlocks::from_bed(bed.path(), &genome).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn from_snp_counts() {
+        let coordinates = [10, 20, 30, 40, 50, 60, 70].map(|position| Coordinate::new(1, position));
+        let snps_on_chr2 = [Coordinate::new(2, 5)];
+        let blocks = JackknifeBlocks::from_snp_counts(coordinates.iter().chain(&snps_on_chr2).copied(), 3);
+
+        let ranges = |chr: u8| blocks.blocks[&ChrIdx::from(chr)].iter().map(|block| (block.range.start.0, block.range.end.0)).collect::<Vec<_>>();
+        assert_eq!(ranges(1), vec![(10, 40), (40, 71)]);
+        assert_eq!(ranges(2), vec![(5, 6)]);
+
+        let tally = blocks.tally(coordinates.iter().map(|coordinate| (*coordinate, 1.0)));
+        let counts = tally.blocks[&ChrIdx::from(1)].iter().map(|block| block.site_counts).collect::<Vec<_>>();
+        assert_eq!(counts, vec![3, 4]);
+    }
+
+    #[test]
+    fn iter_and_collect() {
+        let genome = Genome::from(&[Chromosome::new(2, 2_001), Chromosome::new(1, 3_001)]);
+        let blocks = JackknifeBlocks::new(&genome, 1000)
+            .tally([(Coordinate::new(1, 10), 1.0), (Coordinate::new(2, 1500), 0.5)]);
+
+        let summary = |blocks: &JackknifeBlocks| blocks.iter()
+            .map(|block| (block.chromosome.0, block.range.start.0, block.site_counts(), block.pwd_counts()))
+            .collect::<Vec<_>>();
+        assert_eq!(summary(&blocks), vec![(1, 1, 1, 1.0), (1, 1001, 0, 0.0), (1, 2001, 0, 0.0), (2, 1, 0, 0.0), (2, 1001, 1, 0.5)]);
+
+        let collected = blocks.iter()
+            .map(|block| JackknifeBlock::new(block.chromosome, block.range.start, block.range.end).with_counts(block.site_counts(), block.pwd_counts()))
+            .collect::<JackknifeBlocks>();
+        assert_eq!(summary(&collected), summary(&blocks));
+    }
+
     #[test]
     fn search_block() {
         let genome = Genome::default();
@@ -3,6 +3,9 @@ pub const CHROM_FORMAT_LEN: usize = 4;
 pub const RANGE_FORMAT_LEN: usize = 15;
 pub const COUNT_FORMAT_LEN: usize = 5;
 
+mod error;
+pub use error::JackknifeBlocksError;
+
 mod jackknife_block;
 pub use jackknife_block::JackknifeBlock;
 
@@ -9,6 +9,9 @@ pub use snp::SNPCoord;
 
 pub mod jackknife;
 
+pub mod bed;
+pub use bed::GenomeMask;
+
 mod genetic_map;
 pub use genetic_map::GeneticMap;
 
@@ -19,4 +22,7 @@ mod chromosome;
 pub use chromosome::Chromosome;
 
 mod sex;
-pub use sex::Sex;
\ No newline at end of file
+pub use sex::Sex;
+
+pub mod species;
+pub use species::SpeciesProfile;
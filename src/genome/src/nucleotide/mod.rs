@@ -10,21 +10,42 @@ pub use phred::{Phred, PHRED_ASCII_BASE};
 
 
 /// Simple struct representing a given nucleotides.
-/// - base  : the nucleotide character -> generally preformatted by Pileup
-/// - phred : Base-quality. Expressed in phred-33 scale.
+/// - base        : the nucleotide character -> generally preformatted by Pileup
+/// - phred       : Base-quality. Expressed in phred-33 scale.
+/// - reverse     : whether or not the read carrying this nucleotide was aligned on the reverse strand.
+/// - end_distance: 0-based distance between this nucleotide and the nearest known end of its read (i.e. the 5' end
+///   when only 5'-relative read positions are available), when known.
+/// - mapq        : mapping quality of the read carrying this nucleotide, when known.
 /// 
 /// # TODO: migrate nucleotide formating from `Pileup::new()` to `Nucleotide::new()`
 #[derive(Debug, Clone, Copy)]
 pub struct Nucleotide {
     pub base: Allele,
     pub phred: Phred,
+    pub reverse: bool,
+    pub end_distance: Option<u32>,
+    pub mapq: Option<u8>,
 }
 
 impl Nucleotide {
     /// Instantiate a new `Nucleotide` struct, from a parsed pileup `base` and associated PHRED-33 `score` 
     #[must_use]
     pub fn new(base: Allele, score: impl Into<Phred>) -> Nucleotide {
-        Nucleotide { base, phred: score.into() }
+        Nucleotide { base, phred: score.into(), reverse: false, end_distance: None, mapq: None }
+    }
+
+    /// Attach the alignment context of the read carrying this nucleotide.
+    /// - `reverse`     : whether or not the read was aligned on the reverse strand.
+    /// - `end_distance`: 0-based distance to the nearest end of the read, if known.
+    #[must_use]
+    pub fn with_read_context(self, reverse: bool, end_distance: Option<u32>) -> Nucleotide {
+        Nucleotide { reverse, end_distance, ..self }
+    }
+
+    /// Attach the mapping quality of the read carrying this nucleotide.
+    #[must_use]
+    pub fn with_mapping_quality(self, mapq: u8) -> Nucleotide {
+        Nucleotide { mapq: Some(mapq), ..self }
     }
 
     pub fn try_new<T>(base: T, score: char) -> Result<Nucleotide, NucleotideError> 
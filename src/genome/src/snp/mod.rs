@@ -36,6 +36,21 @@ impl Display for SNPCoord {
     }
 }
 
+/// Substitution class of a bi-allelic SNP.
+/// - `Transition`  : purine <-> purine (A <-> G), or pyrimidine <-> pyrimidine (C <-> T) substitution.
+/// - `Transversion`: purine <-> pyrimidine substitution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SubstitutionClass { Transition, Transversion }
+
+impl Display for SubstitutionClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transition   => write!(f, "Ts"),
+            Self::Transversion => write!(f, "Tv"),
+        }
+    }
+}
+
 impl SNPCoord {
 
     pub fn new(chromosome: impl Into<ChrIdx>, position: impl Into<Position>, reference: Allele, alternate: Allele) -> Self {
@@ -58,6 +73,20 @@ impl SNPCoord {
     pub fn has_known_alleles(&self) -> bool {
         self.reference.is_known() && self.alternate.is_known()
     }
+
+    /// Return the substitution class of this SNP. Returns `None` if either the REF or ALT allele is unknown, or if
+    /// both alleles are identical.
+    #[must_use]
+    pub fn substitution_class(&self) -> Option<SubstitutionClass> {
+        use Allele::{A, C, G, T};
+        if !self.has_known_alleles() || self.reference == self.alternate {
+            return None
+        }
+        match [self.reference, self.alternate] {
+            [A, G] | [G, A] | [C, T] | [T, C] => Some(SubstitutionClass::Transition),
+            _                                 => Some(SubstitutionClass::Transversion),
+        }
+    }
 }
 
 
@@ -103,6 +132,17 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn substitution_class() -> Result<()> {
+        assert_eq!(SNPCoord::try_new(1, 10, 'A', 'G')?.substitution_class(), Some(SubstitutionClass::Transition));
+        assert_eq!(SNPCoord::try_new(1, 10, 'T', 'C')?.substitution_class(), Some(SubstitutionClass::Transition));
+        assert_eq!(SNPCoord::try_new(1, 10, 'A', 'C')?.substitution_class(), Some(SubstitutionClass::Transversion));
+        assert_eq!(SNPCoord::try_new(1, 10, 'G', 'T')?.substitution_class(), Some(SubstitutionClass::Transversion));
+        assert_eq!(SNPCoord::try_new(1, 10, 'A', 'N')?.substitution_class(), None);
+        assert_eq!(SNPCoord::try_new(1, 10, 'A', 'A')?.substitution_class(), None);
+        Ok(())
+    }
+
     #[test]
     fn snpcoord_full_equality() -> Result<()> {
         let coord1 = SNPCoord::try_new(1, 100_510, 'A', 'C')?;
@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::coordinate::ChrIdxError;
+
+#[derive(Error, Debug)]
+pub enum SpeciesError {
+    #[error("Unknown species profile '{name}'. Expected either a path to a TOML file, or one of the following presets: {presets}")]
+    UnknownPreset{name: String, presets: String},
+
+    #[error("Unknown genome build '{name}'. Expected one of the following builds: {builds}")]
+    UnknownBuild{name: String, builds: String},
+
+    #[error("Failed to read species profile {}", path.display())]
+    ReadProfile{path: PathBuf, #[source] err: std::io::Error},
+
+    #[error("Failed to parse species profile")]
+    ParseProfile(#[source] toml::de::Error),
+
+    #[error("Failed to register contig '{0}'")]
+    RegisterContig(String, #[source] ChrIdxError),
+
+    #[error("Contig '{0}' is declared more than once within the species profile")]
+    DuplicateContig(String),
+
+    #[error("Pseudo-autosomal region '{0}' is either empty, or extends past the end of the sex-chromosome")]
+    InvalidRegion(String),
+
+    #[error("Species profile does not declare any autosome")]
+    MissingAutosomes,
+}
@@ -0,0 +1,447 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{coordinate::ChrIdx, Chromosome, Genome, GenomeMask, Sex};
+
+mod error;
+pub use error::SpeciesError;
+
+use located_error::prelude::*;
+use log::{debug, info, warn};
+use serde::Deserialize;
+
+/// Built-in species profiles. Tuple fields: (preset name, TOML definition)
+const PRESETS: [(&str, &str); 4] = [
+    ("human",  include_str!("presets/human.toml")),
+    ("cattle", include_str!("presets/cattle.toml")),
+    ("dog",    include_str!("presets/dog.toml")),
+    ("horse",  include_str!("presets/horse.toml")),
+];
+
+/// Built-in human genome builds. Tuple fields: (assembly name, TOML definition). The first build is the one used by the
+/// `human` species preset.
+const HUMAN_BUILDS: [(&str, &str); 3] = [
+    ("GRCh37", include_str!("presets/human.toml")),
+    ("GRCh38", include_str!("presets/human-grch38.toml")),
+    ("CHM13",  include_str!("presets/human-chm13.toml")),
+];
+
+/// Sex-determination system of a species.
+/// - `XY`: males are heterogametic (e.g. mammals)
+/// - `ZW`: females are heterogametic (e.g. birds)
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum SexSystem {
+    XY,
+    ZW,
+}
+
+impl SexSystem {
+    /// Sex carrying a single copy of the sex-chromosome which is transmitted by both parents (i.e. `X` or `Z`)
+    #[must_use]
+    pub fn hemizygous_sex(&self) -> Sex {
+        match self {
+            Self::XY => Sex::Male,
+            Self::ZW => Sex::Female,
+        }
+    }
+}
+
+/// Region of the sex-chromosome which is shared with its counterpart (e.g. `PAR1` and `PAR2`, between `X` and `Y`), and
+/// thus recombines like an autosome. Coordinates are 1-based and inclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PseudoAutosomalRegion {
+    pub name : String,
+    pub start: u32,
+    pub end  : u32,
+}
+
+/// Sex-chromosome which is transmitted by both parents (i.e. `X` or `Z`), along with the sex carrying a single copy of
+/// it, and its pseudo-autosomal regions. This is all pedigree simulations require to apply sex-chromosomal inheritance
+/// rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SexChromosome {
+    pub chromosome: ChrIdx,
+    pub hemizygous: Sex,
+    pub pseudo_autosomal_regions: Vec<PseudoAutosomalRegion>,
+}
+
+impl SexChromosome {
+    /// Exclude the pseudo-autosomal regions of this sex-chromosome from a genomic mask. Sex-chromosomal inheritance
+    /// rules do not apply within these regions, which should thus be excluded from both observed and simulated
+    /// comparisons.
+    pub fn mask_pseudo_autosomal_regions(&self, mask: &mut GenomeMask) {
+        for region in &self.pseudo_autosomal_regions {
+            debug!("Excluding pseudo-autosomal region {} ({}:{}-{})", region.name, self.chromosome, region.start, region.end);
+            mask.exclude(self.chromosome, region.start, region.end.saturating_add(1));
+        }
+    }
+}
+
+/// Raw contents of a species profile TOML file.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ProfileFile {
+    name          : String,
+    #[serde(default)]
+    assembly      : Option<String>,
+    sex_system    : SexSystem,
+    sex_chromosome: ChromosomeEntry,
+    autosomes     : BTreeMap<String, u32>,
+    #[serde(default)]
+    pseudo_autosomal_regions: Vec<PseudoAutosomalRegion>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChromosomeEntry {
+    name  : String,
+    length: u32,
+}
+
+/// Chromosomal layout of a species: which contigs should be considered as autosomes, what the sex-determination
+/// system is, and the default length of each chromosome (used when no reference genome is provided).
+///
+/// Profiles are either built-in presets (see `SpeciesProfile::presets()`), or TOML files, such as:
+/// ```toml
+/// name       = "chicken"
+/// assembly   = "GRCg7b"   # Optional
+/// sex-system = "ZW"       # Either "XY" or "ZW"
+///
+/// [sex-chromosome]        # X chromosome for XY species, Z chromosome for ZW species.
+/// name   = "Z"
+/// length = 86_044_486
+///
+/// [[pseudo-autosomal-regions]]  # Optional. 1-based, inclusive coordinates along the sex-chromosome.
+/// name  = "PAR1"
+/// start = 1
+/// end   = 100_000
+///
+/// [autosomes]             # <name> = <length>
+/// 1 = 196_449_156
+/// 2 = 149_539_284
+/// ```
+///
+/// Every contig name declared within the profile is registered within the process-wide contig dictionary.
+#[derive(Debug, Clone)]
+pub struct SpeciesProfile {
+    pub name       : String,
+    pub assembly   : Option<String>,
+    pub sex_system : SexSystem,
+    sex_chromosome : Chromosome,
+    autosomes      : Vec<Chromosome>,
+    pseudo_autosomal_regions: Vec<PseudoAutosomalRegion>,
+}
+
+impl Default for SpeciesProfile {
+    fn default() -> Self {
+        Self::from_preset(Self::DEFAULT).expect("Default species preset should always be valid")
+    }
+}
+
+impl SpeciesProfile {
+    /// Name of the preset used when the user does not specify any species profile.
+    pub const DEFAULT: &'static str = "human";
+
+    /// Load a species profile from either a preset name, or a path leading to a TOML file.
+    ///
+    /// # Errors
+    /// - `UnknownPreset` if `profile` is neither an existing file, nor a known preset.
+    /// - See `SpeciesProfile::from_toml()` and `SpeciesProfile::from_preset()`
+    pub fn new(profile: &str) -> Result<Self> {
+        let loc_msg = || format!("While loading species profile '{profile}'");
+        match Path::new(profile).is_file() {
+            true  => Self::from_toml(profile).with_loc(loc_msg),
+            false => Self::from_preset(profile).with_loc(loc_msg),
+        }
+    }
+
+    /// Load one of the built-in species profiles.
+    ///
+    /// # Errors
+    /// - `UnknownPreset` if `name` does not match any preset.
+    pub fn from_preset(name: &str) -> Result<Self> {
+        let Some((_, contents)) = PRESETS.iter().find(|(preset, _)| *preset == name) else {
+            return Err(SpeciesError::UnknownPreset{name: name.to_string(), presets: Self::presets().collect::<Vec<_>>().join(", ")})
+                .loc("While searching for a matching species preset")
+        };
+        Self::parse(contents).with_loc(|| format!("While parsing species preset '{name}'"))
+    }
+
+    /// Load the human species profile, using the chromosome lengths of a given genome build (e.g. `GRCh38`).
+    /// Matching is case-insensitive.
+    ///
+    /// # Errors
+    /// - `UnknownBuild` if `build` does not match any of the built-in human genome builds.
+    pub fn from_build(build: &str) -> Result<Self> {
+        let Some((_, contents)) = HUMAN_BUILDS.iter().find(|(assembly, _)| assembly.eq_ignore_ascii_case(build)) else {
+            return Err(SpeciesError::UnknownBuild{name: build.to_string(), builds: Self::builds().collect::<Vec<_>>().join(", ")})
+                .loc("While searching for a matching genome build")
+        };
+        Self::parse(contents).with_loc(|| format!("While parsing genome build '{build}'"))
+    }
+
+    /// Load a species profile from a TOML file.
+    ///
+    /// # Errors
+    /// - `ReadProfile` if the file cannot be read.
+    /// - See `SpeciesProfile::parse()`
+    pub fn from_toml(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        info!("Parsing species profile: {}", path.display());
+        let contents = fs::read_to_string(path)
+            .map_err(|err| SpeciesError::ReadProfile{path: path.to_path_buf(), err})
+            .loc("While reading species profile")?;
+        Self::parse(&contents).with_loc(|| format!("While parsing species profile {}", path.display()))
+    }
+
+    /// Name of every built-in species profile.
+    pub fn presets() -> impl Iterator<Item = &'static str> {
+        PRESETS.iter().map(|(name, _)| *name)
+    }
+
+    /// Name of every built-in human genome build.
+    pub fn builds() -> impl Iterator<Item = &'static str> {
+        HUMAN_BUILDS.iter().map(|(assembly, _)| *assembly)
+    }
+
+    /// Parse the contents of a TOML species profile, and register its contigs.
+    ///
+    /// # Errors
+    /// - `ParseProfile` if `contents` is not a valid profile.
+    /// - `MissingAutosomes` if the profile does not declare any autosome.
+    /// - `RegisterContig` if any contig name cannot be registered.
+    /// - `DuplicateContig` if any contig is declared twice (e.g. `1` and `chr1`)
+    /// - `InvalidRegion` if a pseudo-autosomal region is empty, or extends past the end of the sex-chromosome.
+    fn parse(contents: &str) -> Result<Self> {
+        use SpeciesError::{DuplicateContig, InvalidRegion, MissingAutosomes, ParseProfile, RegisterContig};
+        let file: ProfileFile = toml::from_str(contents).map_err(ParseProfile).loc("While deserializing species profile")?;
+        if file.autosomes.is_empty() {
+            return Err(MissingAutosomes).loc("While validating species profile")
+        }
+
+        let register = |name: &str, length: u32| -> Result<Chromosome> {
+            let idx = ChrIdx::register(name).map_err(|err| RegisterContig(name.to_string(), err))?;
+            Ok(Chromosome::new(idx, length))
+        };
+
+        let sex_chromosome = register(&file.sex_chromosome.name, file.sex_chromosome.length)?;
+        let mut autosomes = file.autosomes.iter()
+            .map(|(name, length)| register(name, *length))
+            .collect::<Result<Vec<Chromosome>>>()?;
+        autosomes.sort();
+
+        // ---- Ensure no contig was declared twice.
+        let mut contigs = autosomes.iter().chain(std::iter::once(&sex_chromosome)).map(|chr| chr.name).collect::<Vec<_>>();
+        contigs.sort();
+        if let Some(duplicate) = contigs.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(DuplicateContig(duplicate[0].to_string())).loc("While validating species profile")
+        }
+
+        // ---- Ensure pseudo-autosomal regions lie within the sex-chromosome.
+        if let Some(region) = file.pseudo_autosomal_regions.iter().find(|par| par.start == 0 || par.start > par.end || par.end > sex_chromosome.length) {
+            return Err(InvalidRegion(region.name.clone())).loc("While validating species profile")
+        }
+
+        debug!("Species profile '{}': {} autosomes | sex-chromosome: {} ({:?})", file.name, autosomes.len(), sex_chromosome.name, file.sex_system);
+        Ok(Self{
+            name: file.name,
+            assembly: file.assembly,
+            sex_system: file.sex_system,
+            sex_chromosome,
+            autosomes,
+            pseudo_autosomal_regions: file.pseudo_autosomal_regions
+        })
+    }
+
+    /// Return the sex-chromosome of this species, along with its hemizygous sex.
+    #[must_use]
+    pub fn sex_chromosome(&self) -> SexChromosome {
+        SexChromosome{
+            chromosome: self.sex_chromosome.name,
+            hemizygous: self.sex_system.hemizygous_sex(),
+            pseudo_autosomal_regions: self.pseudo_autosomal_regions.clone(),
+        }
+    }
+
+    /// Check whether `chr` is declared as an autosome within this profile.
+    #[must_use]
+    pub fn is_autosome(&self, chr: ChrIdx) -> bool {
+        self.autosomes.iter().any(|autosome| autosome.name == chr)
+    }
+
+    /// Default genome of this species (i.e. every autosome, and the sex-chromosome), used when the user does not
+    /// provide any reference genome.
+    #[must_use]
+    pub fn genome(&self) -> Genome {
+        let assembly = self.assembly.as_ref().map(|assembly| format!(" ({assembly})")).unwrap_or_default();
+        warn!("No reference genome provided. Using the default chromosome lengths of the '{}' species profile{assembly}.", self.name);
+        let mut genome = Genome::from(&self.autosomes);
+        genome.add_chromosome(self.sex_chromosome);
+        genome
+    }
+
+    /// Warn the user if the chromosome lengths of a reference genome differ from the ones declared within this profile.
+    /// This usually means the reference genome and the selected genome build do not match.
+    pub fn check_lengths(&self, genome: &Genome) {
+        let mismatches = self.autosomes.iter().chain(std::iter::once(&self.sex_chromosome))
+            .filter_map(|expected| genome.get(&expected.name).filter(|chr| chr.length != expected.length).map(|chr| (chr, expected)))
+            .collect::<Vec<_>>();
+        if mismatches.is_empty() {
+            return
+        }
+        let assembly = self.assembly.as_deref().unwrap_or(&self.name);
+        warn!("{} chromosome(s) of the reference genome do not match the lengths of the {assembly} genome build. Is this the right build?", mismatches.len());
+        for (chr, expected) in mismatches {
+            debug!("Chromosome {}: {} bp (expected {} bp)", chr.name, chr.length, expected.length);
+        }
+    }
+
+    /// Split a genome into its autosomes and its sex-chromosome. Contigs that are neither declared as autosomes, nor
+    /// as the sex-chromosome (e.g. `Y`, `W`, `MT`, unplaced scaffolds) are excluded.
+    #[must_use]
+    pub fn split(&self, mut genome: Genome) -> (Genome, Option<Genome>) {
+        let sex_chromosome = genome.pop(self.sex_chromosome.name);
+
+        let skipped = genome.retain(|chr| self.is_autosome(chr.name));
+        if ! skipped.is_empty() {
+            warn!("Excluding {} contig(s) which are not autosomes of the '{}' species profile.", skipped.len(), self.name);
+            debug!("Excluded contigs: {}", skipped.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "));
+        }
+
+        let missing = self.autosomes.iter().filter(|autosome| ! genome.contains_key(&autosome.name)).count();
+        if missing > 0 {
+            warn!("{missing} autosome(s) of the '{}' species profile are missing from the reference genome. Is this the right species?", self.name);
+        }
+        (genome, sex_chromosome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::{Coordinate, Position};
+
+    #[test]
+    fn presets_are_valid() {
+        for preset in SpeciesProfile::presets() {
+            assert!(SpeciesProfile::from_preset(preset).is_ok_and(|profile| profile.name == preset), "{preset}");
+        }
+    }
+
+    #[test]
+    fn builds_are_valid() {
+        for build in SpeciesProfile::builds() {
+            let profile = SpeciesProfile::from_build(&build.to_lowercase()).expect(build);
+            assert_eq!((profile.name.as_str(), profile.assembly.as_deref()), ("human", Some(build)));
+            assert_eq!(profile.sex_chromosome().chromosome, ChrIdx::X);
+            assert_eq!(profile.genome().len(), 23);
+            assert_eq!(profile.sex_chromosome().pseudo_autosomal_regions.len(), 2);
+        }
+        assert!(SpeciesProfile::from_build("hg19").is_err_and(|e| {
+            matches!(e.downcast_ref::<SpeciesError>(), Some(SpeciesError::UnknownBuild{..}))
+        }));
+    }
+
+    #[test]
+    fn unknown_preset() {
+        assert!(SpeciesProfile::new("unicorn").is_err_and(|e| {
+            matches!(e.downcast_ref::<SpeciesError>(), Some(SpeciesError::UnknownPreset{..}))
+        }));
+    }
+
+    #[test]
+    fn default_matches_default_genome() {
+        let genome = SpeciesProfile::default().genome();
+        let want   = Genome::default();
+        assert_eq!(genome.len(), want.len());
+        for (chr, want) in want.iter() {
+            assert_eq!(genome[chr].length, want.length, "{chr}");
+        }
+    }
+
+    #[test]
+    fn zw_profile() -> Result<()> {
+        let profile = SpeciesProfile::parse("\
+            name       = \"chicken\"\n\
+            sex-system = \"ZW\"\n\
+            [sex-chromosome]\n\
+            name   = \"Z\"\n\
+            length = 86_044_486\n\
+            [autosomes]\n\
+            1 = 196_449_156\n\
+            2 = 149_539_284\n\
+        ")?;
+        let z = ChrIdx::register("Z")?;
+        assert_eq!(profile.sex_chromosome(), SexChromosome{chromosome: z, hemizygous: Sex::Female, pseudo_autosomal_regions: vec![]});
+        assert!(profile.is_autosome(ChrIdx(2)) && !profile.is_autosome(z));
+
+        let (autosomes, sex_chromosome) = profile.split(profile.genome());
+        assert_eq!(autosomes.keys().copied().collect::<Vec<_>>(), vec![ChrIdx(1), ChrIdx(2)]);
+        assert!(sex_chromosome.is_some_and(|genome| genome.contains_key(&z)));
+        Ok(())
+    }
+
+    #[test]
+    fn split_excludes_other_contigs() {
+        let profile = SpeciesProfile::default();
+        let genome  = Genome::from(&[Chromosome::new(1, 1000), Chromosome::new(ChrIdx::Y, 1000), Chromosome::new(ChrIdx::X, 1000)]);
+        let (autosomes, sex_chromosome) = profile.split(genome);
+        assert_eq!(autosomes.keys().copied().collect::<Vec<_>>(), vec![ChrIdx(1)]);
+        assert!(sex_chromosome.is_some_and(|genome| genome.contains_key(&ChrIdx::X)));
+    }
+
+    #[test]
+    fn duplicate_contig() {
+        let profile = SpeciesProfile::parse("\
+            name       = \"duplicate\"\n\
+            sex-system = \"XY\"\n\
+            [sex-chromosome]\n\
+            name   = \"X\"\n\
+            length = 1000\n\
+            [autosomes]\n\
+            1    = 1000\n\
+            chr1 = 1000\n\
+        ");
+        assert!(profile.is_err_and(|e| matches!(e.downcast_ref::<SpeciesError>(), Some(SpeciesError::DuplicateContig(_)))));
+    }
+
+    #[test]
+    fn pseudo_autosomal_regions() -> Result<()> {
+        let sex_chromosome = SpeciesProfile::from_build("GRCh37")?.sex_chromosome();
+        let mut mask = GenomeMask::default();
+        sex_chromosome.mask_pseudo_autosomal_regions(&mut mask);
+
+        assert!( mask.allows(&Coordinate::new(ChrIdx::X, Position(60_000))));
+        assert!(!mask.allows(&Coordinate::new(ChrIdx::X, Position(60_001))));
+        assert!(!mask.allows(&Coordinate::new(ChrIdx::X, Position(2_699_520))));
+        assert!( mask.allows(&Coordinate::new(ChrIdx::X, Position(2_699_521))));
+        assert!(!mask.allows(&Coordinate::new(ChrIdx::X, Position(155_000_000))));
+        assert!( mask.allows(&Coordinate::new(ChrIdx(1), Position(100_000))));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_pseudo_autosomal_region() {
+        let profile = SpeciesProfile::parse("\
+            name       = \"invalid\"\n\
+            sex-system = \"XY\"\n\
+            [sex-chromosome]\n\
+            name   = \"X\"\n\
+            length = 1000\n\
+            [[pseudo-autosomal-regions]]\n\
+            name  = \"PAR1\"\n\
+            start = 900\n\
+            end   = 1001\n\
+            [autosomes]\n\
+            1 = 1000\n\
+        ");
+        assert!(profile.is_err_and(|e| matches!(e.downcast_ref::<SpeciesError>(), Some(SpeciesError::InvalidRegion(_)))));
+    }
+
+    #[test]
+    fn missing_autosomes() {
+        let profile = SpeciesProfile::parse("name = \"empty\"\nsex-system = \"XY\"\nautosomes = {}\n[sex-chromosome]\nname = \"X\"\nlength = 1000\n");
+        assert!(profile.is_err_and(|e| matches!(e.downcast_ref::<SpeciesError>(), Some(SpeciesError::MissingAutosomes))));
+    }
+}
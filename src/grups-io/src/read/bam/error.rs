@@ -0,0 +1,30 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BamReaderError {
+    #[error("Failed to open alignment file {0}")]
+    OpenFile(String, #[source] std::io::Error),
+
+    #[error(
+        "CRAM input is not supported. Please convert {0} to BAM beforehand (e.g. 'samtools view -b -T <reference.fa> -o <out.bam> <in.cram>')"
+    )]
+    CramUnsupported(String),
+
+    #[error("Invalid BAM magic number. Is this file a valid BGZF-compressed BAM file?")]
+    InvalidMagic,
+
+    #[error("Failed to read the contents of the BAM file")]
+    ReadBam(#[source] std::io::Error),
+
+    #[error("BAM header contains an invalid reference sequence name")]
+    InvalidReferenceName(#[source] std::str::Utf8Error),
+
+    #[error("BAM record is truncated or malformed (block size: {0})")]
+    TruncatedRecord(usize),
+
+    #[error("Invalid CIGAR operation code: {0}")]
+    InvalidCigarOp(u32),
+
+    #[error("Failed to read BAM list file {0}")]
+    ReadList(String, #[source] std::io::Error),
+}
@@ -0,0 +1,63 @@
+use super::{BamRecord, BamReference, BAM_MAGIC, SEQ_NT16};
+
+/// Minimal plain text header of mock BAM files.
+const MOCK_TEXT: &[u8] = b"@HD\tVN:1.6\tSO:coordinate\n";
+
+/// Read name of mock BAM records.
+const MOCK_READ_NAME: &[u8] = b"read\0";
+
+/// Encode the magic number, plain text header and reference sequence dictionary of an uncompressed BAM stream. Mainly
+/// used to generate mock alignment files within tests (see `BamReader::from_reader()`).
+#[must_use]
+pub fn encode_header(references: &[BamReference]) -> Vec<u8> {
+    #![allow(clippy::cast_possible_truncation)]
+    let mut bytes = BAM_MAGIC.to_vec();
+    bytes.extend((MOCK_TEXT.len() as u32).to_le_bytes());
+    bytes.extend(MOCK_TEXT);
+    bytes.extend((references.len() as u32).to_le_bytes());
+    for reference in references {
+        bytes.extend((reference.name.len() as u32 + 1).to_le_bytes());
+        bytes.extend(reference.name.as_bytes());
+        bytes.push(0);
+        bytes.extend(reference.length.to_le_bytes());
+    }
+    bytes
+}
+
+/// Encode a single alignment record, prefixed with its `block_size`. Mainly used to generate mock alignment files
+/// within tests (see `BamRecord::from_bytes()`). Read names are set to `read`, and mate information is left undefined.
+///
+/// # Panics
+/// - if `record.seq` contains a character which cannot be 4-bit encoded (i.e. not within `=ACMGRSVTWYHKDBN`).
+#[must_use]
+pub fn encode_record(record: &BamRecord) -> Vec<u8> {
+    #![allow(clippy::cast_possible_truncation)]
+    let mut block = Vec::new();
+    block.extend(record.ref_id.to_le_bytes());
+    block.extend(record.pos.to_le_bytes());
+    block.push(MOCK_READ_NAME.len() as u8);
+    block.push(record.mapq);
+    block.extend(0u16.to_le_bytes());                         // bin
+    block.extend((record.cigar.len() as u16).to_le_bytes());
+    block.extend(record.flag.to_le_bytes());
+    block.extend((record.seq.len() as u32).to_le_bytes());
+    block.extend((-1i32).to_le_bytes());                      // next_refID
+    block.extend((-1i32).to_le_bytes());                      // next_pos
+    block.extend(0i32.to_le_bytes());                         // tlen
+    block.extend(MOCK_READ_NAME);
+    for cigar in &record.cigar {
+        block.extend((cigar.len << 4 | u32::from(cigar.op)).to_le_bytes());
+    }
+
+    let codes: Vec<u8> = record.seq.iter()
+        .map(|base| SEQ_NT16.iter().position(|code| code == base).expect("Invalid BAM nucleotide") as u8)
+        .collect();
+    for pair in codes.chunks(2) {
+        block.push(pair[0] << 4 | pair.get(1).copied().unwrap_or(0));
+    }
+    block.extend(&record.qual);
+
+    let mut bytes = (block.len() as u32).to_le_bytes().to_vec();
+    bytes.extend(block);
+    bytes
+}
@@ -0,0 +1,438 @@
+use std::{fs::File, io::{self, BufRead, BufReader, Read}, path::{Path, PathBuf}};
+
+use located_error::LocatedError;
+use gzp::{deflate::Bgzf, par::decompress::ParDecompressBuilder};
+use anyhow::Result;
+use log::debug;
+
+mod error;
+pub use error::BamReaderError;
+
+pub mod mock;
+
+const BAM_MAGIC : &[u8; 4]  = b"BAM\x01";
+const CRAM_MAGIC: &[u8; 4]  = b"CRAM";
+const SEQ_NT16  : &[u8; 16] = b"=ACMGRSVTWYHKDBN"; // 4-bit encoded BAM nucleotides.
+const MISSING_QUAL: u8      = 0xff;                // Base quality value of a record that lacks a QUAL field.
+
+/// Single CIGAR operation type, as encoded within a BAM record (`MIDNSHP=X`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CigarOp {Match, Ins, Del, RefSkip, SoftClip, HardClip, Pad, Equal, Diff}
+
+impl TryFrom<u32> for CigarOp {
+    type Error = BamReaderError;
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        use CigarOp::{Match, Ins, Del, RefSkip, SoftClip, HardClip, Pad, Equal, Diff};
+        Ok(match value {
+            0 => Match,
+            1 => Ins,
+            2 => Del,
+            3 => RefSkip,
+            4 => SoftClip,
+            5 => HardClip,
+            6 => Pad,
+            7 => Equal,
+            8 => Diff,
+            other => return Err(BamReaderError::InvalidCigarOp(other))
+        })
+    }
+}
+
+impl From<CigarOp> for u32 {
+    fn from(op: CigarOp) -> Self {
+        use CigarOp::{Match, Ins, Del, RefSkip, SoftClip, HardClip, Pad, Equal, Diff};
+        match op {
+            Match    => 0,
+            Ins      => 1,
+            Del      => 2,
+            RefSkip  => 3,
+            SoftClip => 4,
+            HardClip => 5,
+            Pad      => 6,
+            Equal    => 7,
+            Diff     => 8,
+        }
+    }
+}
+
+impl CigarOp {
+    /// Return `true` if this operation consumes bases of the reference sequence.
+    #[must_use]
+    pub fn consumes_reference(&self) -> bool {
+        matches!(self, Self::Match | Self::Del | Self::RefSkip | Self::Equal | Self::Diff)
+    }
+
+    /// Return `true` if this operation consumes bases of the read sequence.
+    #[must_use]
+    pub fn consumes_query(&self) -> bool {
+        matches!(self, Self::Match | Self::Ins | Self::SoftClip | Self::Equal | Self::Diff)
+    }
+}
+
+/// A single CIGAR operation, along with its length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cigar {
+    pub op : CigarOp,
+    pub len: u32,
+}
+
+/// Reference sequence dictionary entry, as defined within the BAM header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BamReference {
+    pub name  : String,
+    pub length: u32,
+}
+
+/// Decoded BAM alignment record. Only the fields that are relevant to pileup construction are kept.
+/// # Fields:
+/// - `ref_id`: 0-based index of the reference sequence within the BAM header (-1 if unmapped).
+/// - `pos`   : 0-based leftmost mapping position.
+/// - `mapq`  : mapping quality.
+/// - `flag`  : bitwise SAM flag.
+/// - `cigar` : vector of CIGAR operations.
+/// - `seq`   : read sequence, as uppercase ASCII characters.
+/// - `qual`  : raw base qualities (not ASCII encoded). Set to `0xff` when missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BamRecord {
+    pub ref_id: i32,
+    pub pos   : i32,
+    pub mapq  : u8,
+    pub flag  : u16,
+    pub cigar : Vec<Cigar>,
+    pub seq   : Vec<u8>,
+    pub qual  : Vec<u8>,
+}
+
+impl BamRecord {
+    pub const FLAG_UNMAPPED     : u16 = 0x4;
+    pub const FLAG_REVERSE      : u16 = 0x10;
+    pub const FLAG_SECONDARY    : u16 = 0x100;
+    pub const FLAG_QC_FAIL      : u16 = 0x200;
+    pub const FLAG_DUPLICATE    : u16 = 0x400;
+    pub const FLAG_SUPPLEMENTARY: u16 = 0x800;
+
+    /// Decode a raw BAM record, i.e. every byte following the `block_size` field.
+    /// # Errors
+    /// - `TruncatedRecord` if the announced field lengths overflow the provided buffer.
+    /// - `InvalidCigarOp` if any CIGAR operation code is invalid.
+    pub fn from_bytes(block: &[u8]) -> Result<Self, BamReaderError> {
+        use BamReaderError::TruncatedRecord;
+        let truncated = || TruncatedRecord(block.len());
+        let ref_id      = read_i32(block, 0).ok_or_else(truncated)?;
+        let pos         = read_i32(block, 4).ok_or_else(truncated)?;
+        let l_read_name = usize::from(*block.get(8).ok_or_else(truncated)?);
+        let mapq        = *block.get(9).ok_or_else(truncated)?;
+        let n_cigar_op  = usize::from(read_u16(block, 12).ok_or_else(truncated)?);
+        let flag        = read_u16(block, 14).ok_or_else(truncated)?;
+        let l_seq       = read_u32(block, 16).ok_or_else(truncated)? as usize;
+
+        // ---- Compute the offset of each variable-length field.
+        let cigar_start = 32 + l_read_name;
+        let seq_start   = cigar_start + 4 * n_cigar_op;
+        let qual_start  = seq_start + l_seq / 2 + l_seq % 2;
+        let qual_end    = qual_start + l_seq;
+        if qual_end > block.len() {
+            return Err(truncated())
+        }
+
+        let cigar = (0..n_cigar_op).map(|i| {
+            let raw = read_u32(block, cigar_start + 4 * i).ok_or_else(truncated)?;
+            Ok(Cigar{op: CigarOp::try_from(raw & 0xf)?, len: raw >> 4})
+        }).collect::<Result<Vec<Cigar>, BamReaderError>>()?;
+
+        let seq = (0..l_seq).map(|i| {
+            let packed = block[seq_start + i / 2];
+            let code   = if i % 2 == 0 { packed >> 4 } else { packed & 0xf };
+            SEQ_NT16[usize::from(code)]
+        }).collect();
+
+        let qual = block[qual_start..qual_end].to_vec();
+        Ok(Self{ref_id, pos, mapq, flag, cigar, seq, qual})
+    }
+
+    /// Return `true` if all of the bits of `flag` are set for this record.
+    #[must_use]
+    pub fn has_flag(&self, flag: u16) -> bool {
+        self.flag & flag == flag
+    }
+
+    #[must_use]
+    pub fn is_unmapped(&self) -> bool {
+        self.ref_id < 0 || self.pos < 0 || self.has_flag(Self::FLAG_UNMAPPED)
+    }
+
+    #[must_use]
+    pub fn is_reverse(&self) -> bool {
+        self.has_flag(Self::FLAG_REVERSE)
+    }
+
+    #[must_use]
+    pub fn is_duplicate(&self) -> bool {
+        self.has_flag(Self::FLAG_DUPLICATE)
+    }
+
+    /// Return `true` if this record is a secondary alignment, or failed platform/vendor quality checks.
+    /// Such records are skipped by default by `samtools mpileup`.
+    #[must_use]
+    pub fn is_secondary_or_qc_fail(&self) -> bool {
+        self.flag & (Self::FLAG_SECONDARY | Self::FLAG_QC_FAIL) != 0
+    }
+
+    /// Number of reference bases spanned by this alignment.
+    #[must_use]
+    pub fn reference_span(&self) -> u32 {
+        self.cigar.iter().filter(|c| c.op.consumes_reference()).map(|c| c.len).sum()
+    }
+
+    /// Return `true` if this record carries no base qualities.
+    #[must_use]
+    pub fn missing_qualities(&self) -> bool {
+        self.qual.first() == Some(&MISSING_QUAL)
+    }
+}
+
+/// Sequential reader of BGZF-compressed `.bam` alignment files.
+/// # Fields:
+/// - `source`    : Boxed decompressed data stream, positionned right after the BAM header.
+/// - `references`: Reference sequence dictionary, as defined within the BAM header.
+/// - `buf`       : Raw byte buffer, containing the contents of the last read record.
+pub struct BamReader {
+    source    : Box<dyn Read>,
+    references: Vec<BamReference>,
+    buf       : Vec<u8>,
+}
+
+impl BamReader {
+    /// Open a `.bam` file and parse its header.
+    /// # Arguments:
+    /// - `path`   : path leading to the `.bam` file.
+    /// - `threads`: number of additional decompression threads.
+    ///
+    /// # Errors
+    /// - `CramUnsupported` if `path` targets a CRAM file.
+    /// - `OpenFile` if `path` targets an invalid location (`FileNotFound` or `PermissionDenied`)
+    /// - if the header of the file cannot be parsed.
+    pub fn new(path: &Path, threads: usize) -> Result<Self> {
+        use BamReaderError::{CramUnsupported, OpenFile};
+        let loc_msg = || format!("While attempting to open BAM file {}", path.display());
+
+        let mut file = File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| OpenFile(path.display().to_string(), e))
+            .with_loc(loc_msg)?;
+
+        // ---- Bail early if the user provided us with a CRAM file.
+        let is_cram = path.extension().is_some_and(|ext| ext == "cram")
+            || file.fill_buf().map_err(|e| OpenFile(path.display().to_string(), e)).with_loc(loc_msg)?.starts_with(CRAM_MAGIC);
+        if is_cram {
+            return Err(CramUnsupported(path.display().to_string())).with_loc(loc_msg)
+        }
+
+        let source = ParDecompressBuilder::<Bgzf>::new()
+            .maybe_num_threads(threads)
+            .maybe_par_from_reader(file);
+        Self::from_reader(source).with_loc(loc_msg)
+    }
+
+    /// Instantiate a `BamReader` from an already decompressed BAM data stream, and parse its header.
+    /// # Errors
+    /// - `InvalidMagic` if the stream does not begin with the expected BAM magic number.
+    /// - `ReadBam` if the header is truncated.
+    /// - `InvalidReferenceName` if any reference sequence name is not valid UTF8.
+    pub fn from_reader(mut source: Box<dyn Read>) -> Result<Self> {
+        use BamReaderError::{InvalidMagic, InvalidReferenceName, ReadBam};
+        let loc_msg = "While parsing BAM header";
+
+        let mut magic = [0u8; 4];
+        source.read_exact(&mut magic).map_err(ReadBam).loc(loc_msg)?;
+        if &magic != BAM_MAGIC {
+            return Err(InvalidMagic).loc(loc_msg)
+        }
+
+        // ---- Skip the plain text SAM header.
+        let l_text = read_le_u32(&mut source).loc(loc_msg)?;
+        io::copy(&mut source.by_ref().take(u64::from(l_text)), &mut io::sink()).map_err(ReadBam).loc(loc_msg)?;
+
+        // ---- Parse the reference sequence dictionary.
+        let n_ref = read_le_u32(&mut source).loc(loc_msg)?;
+        let mut references = Vec::with_capacity(n_ref as usize);
+        for _ in 0..n_ref {
+            let l_name = read_le_u32(&mut source).loc(loc_msg)?;
+            let mut name = vec![0u8; l_name as usize];
+            source.read_exact(&mut name).map_err(ReadBam).loc(loc_msg)?;
+            name.pop(); // NUL terminator
+            let name   = std::str::from_utf8(&name).map_err(InvalidReferenceName).loc(loc_msg)?.to_string();
+            let length = read_le_u32(&mut source).loc(loc_msg)?;
+            references.push(BamReference{name, length});
+        }
+        debug!("BAM reference dictionary: {references:?}");
+        Ok(Self{source, references, buf: Vec::new()})
+    }
+
+    /// Access the reference sequence dictionary of this BAM file.
+    #[must_use]
+    pub fn references(&self) -> &[BamReference] {
+        &self.references
+    }
+
+    /// Read and decode the next alignment record. Returns `None` once the end of the file is reached.
+    /// # Errors
+    /// - `ReadBam` if the stream could not be read.
+    /// - `TruncatedRecord` if the file ends in the middle of a record, or if the record is malformed.
+    pub fn read_record(&mut self) -> Result<Option<BamRecord>> {
+        use BamReaderError::{ReadBam, TruncatedRecord};
+        let loc_msg = "While reading the next BAM record";
+
+        // ---- Read the block size, while checking for a clean EOF.
+        let mut block_size = [0u8; 4];
+        let mut filled = 0;
+        while filled < block_size.len() {
+            match self.source.read(&mut block_size[filled..]) {
+                Ok(0)  => break,
+                Ok(n)  => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(ReadBam(e)).loc(loc_msg)
+            }
+        }
+        match filled {
+            0 => return Ok(None),
+            4 => (),
+            n => return Err(TruncatedRecord(n)).loc(loc_msg)
+        }
+
+        let block_size = u32::from_le_bytes(block_size) as usize;
+        self.buf.resize(block_size, 0);
+        self.source.read_exact(&mut self.buf).map_err(|_| TruncatedRecord(block_size)).loc(loc_msg)?;
+        Ok(Some(BamRecord::from_bytes(&self.buf).loc(loc_msg)?))
+    }
+}
+
+/// Parse a list of input `.bam` files. Files are expected to be listed one per line. Empty lines and lines
+/// starting with a '#' character are ignored.
+/// # Errors
+/// - `ReadList` if `path` targets an invalid location or cannot be read.
+pub fn parse_bam_list(path: &Path) -> Result<Vec<PathBuf>> {
+    use BamReaderError::ReadList;
+    let read_err = |e| ReadList(path.display().to_string(), e);
+    let reader = File::open(path).map(BufReader::new).map_err(read_err)?;
+
+    let mut bams = Vec::new();
+    for line in reader.lines() {
+        let line = line.map_err(read_err)?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue
+        }
+        bams.push(PathBuf::from(line));
+    }
+    Ok(bams)
+}
+
+fn read_le_u32(source: &mut impl Read) -> Result<u32, BamReaderError> {
+    let mut bytes = [0u8; 4];
+    source.read_exact(&mut bytes).map_err(BamReaderError::ReadBam)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u16(block: &[u8], offset: usize) -> Option<u16> {
+    block.get(offset..offset+2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(block: &[u8], offset: usize) -> Option<u32> {
+    block.get(offset..offset+4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_i32(block: &[u8], offset: usize) -> Option<i32> {
+    block.get(offset..offset+4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn encode_header(references: &[(&str, u32)]) -> Vec<u8> {
+        let references: Vec<BamReference> = references.iter()
+            .map(|(name, length)| BamReference{name: name.to_string(), length: *length})
+            .collect();
+        mock::encode_header(&references)
+    }
+
+    fn encode_record(ref_id: i32, pos: i32, mapq: u8, flag: u16, cigar: &[(u32, u32)], seq: &str, qual: &[u8]) -> Vec<u8> {
+        let cigar = cigar.iter().map(|(len, op)| Ok(Cigar{op: CigarOp::try_from(*op)?, len: *len})).collect::<Result<_, BamReaderError>>();
+        let seq   = seq.as_bytes().to_vec();
+        mock::encode_record(&BamRecord{ref_id, pos, mapq, flag, cigar: cigar.expect("Invalid CIGAR operation"), seq, qual: qual.to_vec()})
+    }
+
+    #[test]
+    fn parse_header() -> Result<()> {
+        let bytes  = encode_header(&[("1", 249_250_621), ("chrX", 155_270_560)]);
+        let reader = BamReader::from_reader(Box::new(Cursor::new(bytes)))?;
+        assert_eq!(reader.references(), &[
+            BamReference{name: "1".to_string(), length: 249_250_621},
+            BamReference{name: "chrX".to_string(), length: 155_270_560}
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_records() -> Result<()> {
+        let mut bytes = encode_header(&[("22", 51_304_566)]);
+        bytes.extend(encode_record(0, 99, 37, 0x10, &[(2, 4), (3, 0), (1, 2), (2, 0)], "GATCGA", &[30, 31, 32, 33, 34, 35]));
+        bytes.extend(encode_record(0, 150, 0, 0x400, &[(3, 0)], "ACG", &[20, 20, 20]));
+
+        let mut reader = BamReader::from_reader(Box::new(Cursor::new(bytes)))?;
+        let record = reader.read_record()?.expect("First record should be readable");
+        assert_eq!(record.ref_id, 0);
+        assert_eq!(record.pos, 99);
+        assert_eq!(record.mapq, 37);
+        assert!(record.is_reverse());
+        assert!(!record.is_duplicate());
+        assert_eq!(record.seq, b"GATCGA");
+        assert_eq!(record.qual, vec![30, 31, 32, 33, 34, 35]);
+        assert_eq!(record.cigar[0], Cigar{op: CigarOp::SoftClip, len: 2});
+        assert_eq!(record.reference_span(), 6);
+
+        let record = reader.read_record()?.expect("Second record should be readable");
+        assert!(record.is_duplicate());
+        assert_eq!(record.seq, b"ACG");
+
+        assert!(reader.read_record()?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_record() -> Result<()> {
+        let mut bytes = encode_header(&[("22", 51_304_566)]);
+        let record    = encode_record(0, 99, 37, 0, &[(3, 0)], "GAT", &[30, 31, 32]);
+        bytes.extend(&record[..record.len() - 2]);
+        let mut reader = BamReader::from_reader(Box::new(Cursor::new(bytes)))?;
+        assert!(reader.read_record().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_magic() {
+        let bytes = b"SAM\x01".to_vec();
+        assert!(BamReader::from_reader(Box::new(Cursor::new(bytes))).is_err());
+    }
+
+    #[test]
+    fn reject_cram() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let path   = tmpdir.path().join("sample.bam");
+        File::create(&path)?.write_all(b"CRAM\x03\x00")?;
+        let err = BamReader::new(&path, 0).err().expect("CRAM files should be rejected");
+        assert!(err.chain().any(|e| e.to_string().contains("CRAM input is not supported")));
+        Ok(())
+    }
+
+    #[test]
+    fn read_bam_list() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let path   = tmpdir.path().join("bams.list");
+        File::create(&path)?.write_all(b"# samples\nind1.bam\n\n  ind2.bam  \n")?;
+        assert_eq!(parse_bam_list(&path)?, vec![PathBuf::from("ind1.bam"), PathBuf::from("ind2.bam")]);
+        Ok(())
+    }
+}
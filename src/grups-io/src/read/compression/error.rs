@@ -0,0 +1,10 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CompressionError {
+    #[error("Failed to open file {0}")]
+    OpenFile(String, #[source] std::io::Error),
+
+    #[error("Failed to read the leading bytes of the input stream")]
+    ReadMagic(#[source] std::io::Error),
+}
@@ -0,0 +1,144 @@
+use std::{fs::File, io::{BufRead, BufReader, Read}, path::Path};
+
+use located_error::LocatedError;
+use gzp::{deflate::Bgzf, par::decompress::ParDecompressBuilder};
+use flate2::read::MultiGzDecoder;
+use anyhow::Result;
+use log::debug;
+
+mod error;
+pub use error::CompressionError;
+
+const GZIP_MAGIC : [u8; 2] = [0x1f, 0x8b];
+const GZIP_FEXTRA: u8      = 0x04;     // FLG bit indicating the presence of an extra field.
+const BGZF_SUBFIELD: [u8; 2] = *b"BC"; // Extra subfield identifier of BGZF blocks.
+
+/// Compression format of an input data stream, as detected from its leading magic bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {Plain, Gzip, Bgzf}
+
+impl Compression {
+    /// Detect the compression format of a data stream, using its first bytes.
+    ///
+    /// A BGZF block is a gzip member carrying an extra field, whose first subfield identifier is 'BC'. Thus, any gzip
+    /// stream that does not match this layout is considered as 'regular' gzip.
+    #[must_use]
+    pub fn detect(magic: &[u8]) -> Self {
+        if !magic.starts_with(&GZIP_MAGIC) {
+            return Self::Plain
+        }
+        let has_extra = magic.get(3).is_some_and(|flg| flg & GZIP_FEXTRA != 0);
+        match has_extra && magic.get(12..14) == Some(&BGZF_SUBFIELD) {
+            true  => Self::Bgzf,
+            false => Self::Gzip,
+        }
+    }
+}
+
+/// Open a file and transparently decompress it if it is gzip or BGZF-compressed. The compression format is detected from
+/// the file's magic bytes, rather than its extension.
+/// # Arguments:
+/// - `path`   : path leading to the (possibly compressed) file.
+/// - `threads`: number of additional decompression threads. Only relevant for BGZF-compressed files.
+///
+/// # Errors
+/// - `OpenFile` if `path` targets an invalid location (`FileNotFound` or `PermissionDenied`)
+/// - `ReadMagic` if the first bytes of the file cannot be read.
+pub fn open(path: &Path, threads: usize) -> Result<Box<dyn BufRead>> {
+    let file = File::open(path)
+        .map_err(|e| CompressionError::OpenFile(path.display().to_string(), e))
+        .loc("While attempting to open a possibly compressed file")?;
+    from_reader(file, threads).with_loc(|| format!("While detecting the compression format of {}", path.display()))
+}
+
+/// Wrap a data stream within a `BufRead`, while transparently decompressing it if it is gzip or BGZF-compressed.
+/// # Arguments:
+/// - `reader` : input data stream (e.g. a file or the standard input).
+/// - `threads`: number of additional decompression threads. Only relevant for BGZF-compressed streams.
+///
+/// # Errors
+/// - `ReadMagic` if the first bytes of the stream cannot be read.
+pub fn from_reader<R: Read + Send + 'static>(reader: R, threads: usize) -> Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(reader);
+    let compression = Compression::detect(reader.fill_buf().map_err(CompressionError::ReadMagic)?);
+    debug!("Detected input compression format: {compression:?}");
+    Ok(match compression {
+        Compression::Plain => Box::new(reader),
+        Compression::Gzip  => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Compression::Bgzf  => Box::new(BufReader::new(
+            ParDecompressBuilder::<Bgzf>::new().maybe_num_threads(threads).maybe_par_from_reader(reader)
+        )),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use flate2::{Compression as Level, Crc, write::{DeflateEncoder, GzEncoder}};
+
+    const CONTENTS: &str = "22\t51057923\tC\t2\tTT\tJE\n22\t51057924\tA\t1\tA\tJ\n";
+
+    fn gzip(data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    }
+
+    pub(crate) fn bgzf_block(data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(data)?;
+        let cdata = encoder.finish()?;
+        let mut crc = Crc::new();
+        crc.update(data);
+
+        let bsize = u16::try_from(cdata.len() + 25)?;
+        let mut block = vec![0x1f, 0x8b, 8, GZIP_FEXTRA, 0, 0, 0, 0, 0, 0xff, 6, 0, b'B', b'C', 2, 0];
+        block.extend(bsize.to_le_bytes());
+        block.extend(cdata);
+        block.extend(crc.sum().to_le_bytes());
+        block.extend(u32::try_from(data.len())?.to_le_bytes());
+        Ok(block)
+    }
+
+    fn read_all(reader: Box<dyn BufRead>) -> Result<String> {
+        Ok(reader.lines().collect::<Result<Vec<String>, _>>()?.join("\n") + "\n")
+    }
+
+    #[test]
+    fn detect_format() -> Result<()> {
+        assert_eq!(Compression::detect(CONTENTS.as_bytes()), Compression::Plain);
+        assert_eq!(Compression::detect(&gzip(CONTENTS.as_bytes())?), Compression::Gzip);
+        assert_eq!(Compression::detect(&bgzf_block(CONTENTS.as_bytes())?), Compression::Bgzf);
+        assert_eq!(Compression::detect(&[]), Compression::Plain);
+        Ok(())
+    }
+
+    #[test]
+    fn read_plain() -> Result<()> {
+        let reader = from_reader(Cursor::new(CONTENTS.as_bytes().to_vec()), 0)?;
+        assert_eq!(read_all(reader)?, CONTENTS);
+        Ok(())
+    }
+
+    #[test]
+    fn read_gzip() -> Result<()> {
+        let reader = from_reader(Cursor::new(gzip(CONTENTS.as_bytes())?), 0)?;
+        assert_eq!(read_all(reader)?, CONTENTS);
+        Ok(())
+    }
+
+    #[test]
+    fn read_bgzf() -> Result<()> {
+        let (first, second) = CONTENTS.split_at(10);
+        let mut data = bgzf_block(first.as_bytes())?;
+        data.extend(bgzf_block(second.as_bytes())?);
+        data.extend(bgzf_block(&[])?); // EOF marker block
+        let tmpdir = tempfile::tempdir()?;
+        let path   = tmpdir.path().join("test.pileup.gz");
+        File::create(&path)?.write_all(&data)?;
+
+        assert_eq!(read_all(open(&path, 0)?)?, CONTENTS);
+        Ok(())
+    }
+}
@@ -14,6 +14,12 @@ pub enum FSTReaderError {
     BuildFST(#[source] fst::Error),
 
     #[error("Could not find any valid paired '.fst.frq' file for {}", path.display())]
-    MatchFrqFile{path: PathBuf}
+    MatchFrqFile{path: PathBuf},
+
+    #[error("Failed to read the contents of the companion contig file.")]
+    ReadContigs(#[source] std::io::Error),
+
+    #[error("Contig '{name}' listed in {} is either unknown, or tied to a different id within the current reference genome", path.display())]
+    ContigMismatch{name: String, path: PathBuf},
 
 }
\ No newline at end of file
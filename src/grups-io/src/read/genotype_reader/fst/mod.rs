@@ -1,4 +1,4 @@
-use std::{str, fs::{self, File}, io::Read, path::{Path, PathBuf}, sync::Arc};
+use std::{str, fs::{self, File}, io::{Read, BufRead, BufReader}, path::{Path, PathBuf}, sync::Arc};
 
 use crate::{
     parse,
@@ -6,12 +6,12 @@ use crate::{
     read::genotype_reader::{GenotypeReader, GenotypeReaderError},
 };
 
-use genome::coordinate::Coordinate;
+use genome::coordinate::{Coordinate, ChrIdx, COORDINATE_BYTES};
 use located_error::prelude::*;
 
 use memmap2::Mmap;
 use ahash::AHashMap;
-use log::{info, debug};
+use log::{info, debug, warn};
 use fst::{Set, IntoStreamer, automaton::{Automaton, Str, StartsWith}, Streamer};
 use parking_lot::RwLock;
 
@@ -20,6 +20,46 @@ use error::FSTReaderError;
 
 pub const FST_EXT: &str = "fst";
 pub const FRQ_EXT: &str = "fst.frq";
+pub const CTG_EXT: &str = "fst.contigs";
+
+/// Layout of the coordinate bytes, at the beginning of each key of an FST set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyLayout {
+    /// `{chr(u8)}{pos(u32_be)}`, with the X-chromosome encoded as `b'X'`. Used by sets lacking a `.fst.contigs` file,
+    /// i.e. sets generated prior to the introduction of the contig dictionary.
+    Legacy,
+    /// `{chr(u16_be)}{pos(u32_be)}` (see `Coordinate`).
+    Contigs,
+}
+
+impl KeyLayout {
+    /// Chromosome byte of the X-chromosome, within `Legacy` sets.
+    const LEGACY_XCHR: u8 = b'X';
+
+    /// Number of leading bytes of a key, which encode its coordinate.
+    fn coordinate_len(self) -> usize {
+        match self {
+            Self::Legacy  => COORDINATE_BYTES - 1,
+            Self::Contigs => COORDINATE_BYTES,
+        }
+    }
+
+    /// Encode `coordinate` into a key prefix. Returns the encoded bytes, along with the offset at which the prefix
+    /// starts, or `None` if the coordinate cannot be represented using this layout.
+    fn encode(self, coordinate: &Coordinate) -> Option<([u8; COORDINATE_BYTES], usize)> {
+        let mut bytes: [u8; COORDINATE_BYTES] = (*coordinate).into();
+        match self {
+            Self::Contigs => Some((bytes, 0)),
+            Self::Legacy  => {
+                bytes[1] = match coordinate.chromosome {
+                    chr if chr == ChrIdx::X => Self::LEGACY_XCHR,
+                    chr                     => u8::try_from(chr.0).ok()?,
+                };
+                Some((bytes, 1))
+            }
+        }
+    }
+}
 
 
 /// Generic trait to either obtain a Set<Mmap> or a Set<Vec<u8>>.
@@ -72,6 +112,7 @@ pub struct FSTReader<T: SetRead<T> + AsRef<[u8]>> {
     frequency_set: Arc<RwLock<Set<T>>>,
     genotypes    : AHashMap<u128, [u8; 2]>,
     frequencies  : AHashMap<String, f32>,
+    layout       : KeyLayout,
 }
 
 impl <T: AsRef<[u8]> + SetRead<T>>Clone for FSTReader<T> {
@@ -80,7 +121,8 @@ impl <T: AsRef<[u8]> + SetRead<T>>Clone for FSTReader<T> {
             genotypes_set: Arc::clone(&self.genotypes_set),
             frequency_set: Arc::clone(&self.frequency_set),
             genotypes:     self.genotypes.clone(),
-            frequencies:   self.frequencies.clone()
+            frequencies:   self.frequencies.clone(),
+            layout:        self.layout,
         }
     }
 }
@@ -128,14 +170,53 @@ impl<T: AsRef<[u8]> + SetRead<T>>FSTReader<T> {
         let loc_msg = || format!("While attempting to create FSTReader from {path}");
         let genotypes_set = Arc::new(RwLock::new(<T as SetRead<T>>::get_fst_memory(path).with_loc(loc_msg)?));
         let frequency_set = Arc::new(RwLock::new(<T as SetRead<T>>::get_fst_memory(&format!("{path}.frq")).with_loc(loc_msg)?)); //@TODO: const FRQ_EXT should be used in place.
-        Ok(Self{genotypes_set, frequency_set, genotypes: AHashMap::new(), frequencies: AHashMap::new()})
+        let layout = Self::check_contigs(Path::new(path)).with_loc(loc_msg)?;
+        Ok(Self{genotypes_set, frequency_set, genotypes: AHashMap::new(), frequencies: AHashMap::new(), layout})
+    }
+
+    /// Ensure the contig ids of the companion `.fst.contigs` file (if any) match the ones of the current contig
+    /// dictionary. Sets containing non-numbered contigs are only valid if the same reference genome was used during
+    /// indexation and during the current run.
+    /// 
+    /// Returns the `KeyLayout` of the set: sets lacking a `.fst.contigs` file are considered as `Legacy`.
+    /// # Errors:
+    /// - returns `ReadContigs` if the `.fst.contigs` file exists, but cannot be read.
+    /// - returns `ContigMismatch` if any contig name is either unknown or tied to a different id.
+    fn check_contigs(fst: &Path) -> Result<KeyLayout> {
+        use FSTReaderError::{ReadContigs, ContigMismatch};
+        let contigs = fst.with_extension(CTG_EXT);
+        if ! contigs.exists() {
+            warn!("No companion '.{CTG_EXT}' file found for {}. Assuming a legacy FST index.", fst.display());
+            return Ok(KeyLayout::Legacy)
+        }
+        let reader = BufReader::new(File::open(&contigs).map_err(ReadContigs).loc("While opening contig file")?);
+        for line in reader.lines() {
+            let line = line.map_err(ReadContigs).loc("While reading contig file")?;
+            let Some((id, name)) = line.split_once('\t') else { continue };
+            let found = name.parse::<ChrIdx>().ok();
+            if id.parse::<u16>().ok() != found.map(|chr| chr.into_inner()) {
+                return Err(ContigMismatch{name: name.to_string(), path: contigs})
+                    .loc("Note that sets containing non-numbered contigs must be used with the same '--genome' they were indexed with.")
+            }
+        }
+        Ok(KeyLayout::Contigs)
     }
 
     /// Public wrapper for `find_chromosome()`. returns a sorted, unduplicated list of chromosomes contained within the set.
-    pub fn find_chromosomes(&self) -> Result<Vec<u8>> {
+    /// Chromosome ids are encoded as two big-endian bytes, and are thus retrieved from the first two levels of the set.
+    pub fn find_chromosomes(&self) -> Result<Vec<ChrIdx>> {
         let set = self.genotypes_set.read();
-        let root = set.as_fst().root();
-        let mut chromosomes = root.transitions().map(|transition| transition.inp).collect::<Vec<_>>();
+        let fst  = set.as_fst();
+        let mut chromosomes = Vec::new();
+        for high in fst.root().transitions() {
+            match self.layout {
+                KeyLayout::Legacy if high.inp == KeyLayout::LEGACY_XCHR => chromosomes.push(ChrIdx::X),
+                KeyLayout::Legacy   => chromosomes.push(ChrIdx::from(high.inp)),
+                KeyLayout::Contigs  => for low in fst.node(high.addr).transitions() {
+                    chromosomes.push(ChrIdx(u16::from_be_bytes([high.inp, low.inp])));
+                },
+            }
+        }
         chromosomes.sort_unstable();
         chromosomes.dedup();
         Ok(chromosomes)
@@ -143,7 +224,7 @@ impl<T: AsRef<[u8]> + SetRead<T>>FSTReader<T> {
 
     /// @TODO: This should be a macro ?
     #[inline]
-    fn format_coordinate_pattern(coord_bytes: &[u8; 5]) -> StartsWith<Str<'_>> {
+    fn format_coordinate_pattern(coord_bytes: &[u8]) -> StartsWith<Str<'_>> {
         let regex = unsafe { str::from_utf8_unchecked(coord_bytes) };
         Str::new(regex).starts_with()
     }
@@ -154,15 +235,15 @@ impl<T: AsRef<[u8]> + SetRead<T>>FSTReader<T> {
     pub fn search_coordinate_genotypes(&mut self, coordinate: &Coordinate) {
         // ---- Create a new fst-matcher, matching any entry starting with our chromosome coordinates.
         // ---- genotype-fst index fields are '{chr} {pos} {id} {alleles}'
-        let coord_bytes: [u8; 5] = (*coordinate).into();
-        let matcher = Self::format_coordinate_pattern(&coord_bytes);
+        let Some((coord_bytes, offset)) = self.layout.encode(coordinate) else { return };
+        let matcher = Self::format_coordinate_pattern(&coord_bytes[offset..]);
 
         // ---- Search through the set and format each match within `self.genotypes` (key=<sample-id>, val=<alleles>)
         let gset = self.genotypes_set.read();
         let mut stream = gset.search(&matcher).into_stream();
         while let Some(key) = stream.next() {
             // ---- Retrieve alleles and sample id.
-            let key = &mut key[5..].to_vec();                // Skip coordinate bytes.
+            let key = &mut key[self.layout.coordinate_len()..].to_vec(); // Skip coordinate bytes.
             let (id, alleles) = key.split_at(key.len() - 2); // alleles are the last two u8.
 
             // ---- Hash sample id. to u128
@@ -182,16 +263,16 @@ impl<T: AsRef<[u8]> + SetRead<T>>FSTReader<T> {
     #[inline]
     pub fn search_coordinate_frequencies(&mut self, coordinate: &Coordinate) {
         // ---- Create a new fst-matcher, matching any entry starting with our chromosome coordinates.
-        //----- genotype-fst index fields are '{chr(u8)}{pos(u32_be)}{pop(chars)}{freq(f32_be)}'
-        let coord_bytes: [u8; 5] = (*coordinate).into();
-        let matcher = Self::format_coordinate_pattern(&coord_bytes);
+        //----- genotype-fst index fields are '{chr(u16_be)}{pos(u32_be)}{pop(chars)}{freq(f32_be)}'
+        let Some((coord_bytes, offset)) = self.layout.encode(coordinate) else { return };
+        let matcher = Self::format_coordinate_pattern(&coord_bytes[offset..]);
 
         // ---- Search through the set and format each match within `self.frequencies` (key=<sample-id>, val=<allele-frequency>)
         let fset = self.frequency_set.read();
         let mut stream = fset.search(&matcher).into_stream();
         while let Some(key) = stream.next() {
             // ---- Retrieve population tag and frequency.
-            let key              = &mut key[5..].to_vec(); // Go from the end of the coordinate to the end.
+            let key              = &mut key[self.layout.coordinate_len()..].to_vec(); // Go from the end of the coordinate to the end.
             let (tag, freq_be)   = key.split_at(key.len()-4); 
 
             // ---- Parse population tag
@@ -238,20 +319,19 @@ impl<T: AsRef<[u8]> + SetRead<T>>FSTReader<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use genome::coordinate::ChrIdx;
     
-    fn fst_line(chr: u8, coord: u32, id: &str, alleles: [u8; 2]) -> Vec<u8> {
-        // ---- {chr(u8)}{pos(u32_be)}{sample_id(chars)}{allele(u8)}{allele(u8)}   
-        let mut line = vec![ChrIdx(chr).into()];
+    fn fst_line(chr: u16, coord: u32, id: &str, alleles: [u8; 2]) -> Vec<u8> {
+        // ---- {chr(u16_be)}{pos(u32_be)}{sample_id(chars)}{allele(u8)}{allele(u8)}   
+        let mut line = ChrIdx(chr).to_be_bytes().to_vec();
         line.extend_from_slice(&coord.to_be_bytes());
         line.extend_from_slice(id.as_bytes());
         line.extend_from_slice(&alleles.iter().map(|a| a + 48).collect::<Vec<u8>>());
         line
     }
 
-    fn frq_line(chr: u8, coord: u32, pop: &str, freq: f32) -> Vec<u8> {
-        // ---- {chr(u8)}{pos(u32_be)}{pop(chars)}{freq(f32_be)}   
-        let mut line = vec![ChrIdx(chr).into()];
+    fn frq_line(chr: u16, coord: u32, pop: &str, freq: f32) -> Vec<u8> {
+        // ---- {chr(u16_be)}{pos(u32_be)}{pop(chars)}{freq(f32_be)}   
+        let mut line = ChrIdx(chr).to_be_bytes().to_vec();
         line.extend_from_slice(&coord.to_be_bytes());
         line.extend_from_slice(pop.as_bytes());
         line.extend_from_slice(&freq.to_be_bytes());
@@ -277,7 +357,7 @@ mod tests {
             frq_line(1, 60000, "EUR", 0.125),
         ]).expect("Failed to generate test genotype set")));
 
-        FSTReader::<Vec<u8>>{genotypes_set, frequency_set, genotypes: AHashMap::new(), frequencies: AHashMap::new()}
+        FSTReader::<Vec<u8>>{genotypes_set, frequency_set, genotypes: AHashMap::new(), frequencies: AHashMap::new(), layout: KeyLayout::Contigs}
     }
 
     #[test]
@@ -372,15 +452,16 @@ mod tests {
             fst_line(7, 60000, "HG00097", [0, 0]),
             fst_line(9, 60000, "HG00098", [0, 0]),
             fst_line(21, 60000, "HG00099", [0, 1]),
+            fst_line(300, 60000, "HG00099", [0, 1]),
         ]).expect("Failed to genererate test genotype set")));
 
         let frequency_set = Set::from_iter(vec![frq_line(1, 50000, "AFR", 0.0)])
             .expect("Failed to generate test frequency set.");
         let frequency_set = Arc::new(RwLock::new(frequency_set));
 
-        let reader = FSTReader::<Vec<u8>>{genotypes_set, frequency_set, genotypes: AHashMap::new(), frequencies: AHashMap::new()};
+        let reader = FSTReader::<Vec<u8>>{genotypes_set, frequency_set, genotypes: AHashMap::new(), frequencies: AHashMap::new(), layout: KeyLayout::Contigs};
 
-        assert!(reader.find_chromosomes().is_ok_and(|v| v == vec![1,3,5,7,9,21]));
+        assert!(reader.find_chromosomes().is_ok_and(|v| v == [1,3,5,7,9,21,300].map(ChrIdx)));
     }
 
     #[test]
@@ -398,4 +479,45 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_check_contigs() -> anyhow::Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let fst_path = tmpdir.path().join(format!("genome.{FST_EXT}"));
+        assert!(FSTReader::<Vec<u8>>::check_contigs(&fst_path).is_ok_and(|layout| layout == KeyLayout::Legacy));
+
+        let contigs_path = tmpdir.path().join(format!("genome.{CTG_EXT}"));
+        std::fs::write(&contigs_path, format!("{}\tX\n", ChrIdx::X.into_inner()))?;
+        assert!(FSTReader::<Vec<u8>>::check_contigs(&fst_path).is_ok_and(|layout| layout == KeyLayout::Contigs));
+
+        std::fs::write(&contigs_path, "1000\tUnknown_contig_for_fst_reader\n")?;
+        assert!(FSTReader::<Vec<u8>>::check_contigs(&fst_path).is_err_and(|e| {
+            matches!(e.downcast_ref::<FSTReaderError>(), Some(FSTReaderError::ContigMismatch{..}))
+        }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_layout() {
+        let legacy_line = |chr: u8, coord: u32, id: &str, alleles: [u8; 2]| {
+            let mut line = fst_line(0, coord, id, alleles)[1..].to_vec();
+            line[0] = chr;
+            line
+        };
+        let genotypes_set = Arc::new(RwLock::new(Set::from_iter(vec![
+            legacy_line(1, 50000, "HG00096", [0, 1]),
+            legacy_line(b'X', 50000, "HG00096", [1, 1]),
+        ]).expect("Failed to generate test genotype set")));
+        let frequency_set = Arc::new(RwLock::new(Set::from_iter(Vec::<Vec<u8>>::new()).expect("Failed to generate test frequency set")));
+
+        let mut reader = FSTReader::<Vec<u8>>{genotypes_set, frequency_set, genotypes: AHashMap::new(), frequencies: AHashMap::new(), layout: KeyLayout::Legacy};
+        assert!(reader.find_chromosomes().is_ok_and(|v| v == [ChrIdx(1), ChrIdx::X]));
+
+        let tag = SampleTag::new("HG00096", None, None);
+        for (chromosome, want) in [(ChrIdx(1), [0, 1]), (ChrIdx::X, [1, 1])] {
+            reader.clear_buffers();
+            reader.search_coordinate_genotypes(&Coordinate::new(chromosome, 50_000));
+            assert!(reader.get_alleles(&tag).is_ok_and(|alleles| alleles == want));
+        }
+    }
 }
@@ -7,7 +7,7 @@ pub use vcf::VCFReader;
 
 pub mod fst;
 pub use self::fst::FSTReader;
-pub use self::fst::{FST_EXT, FRQ_EXT};
+pub use self::fst::{FST_EXT, FRQ_EXT, CTG_EXT};
 
 
 mod error;
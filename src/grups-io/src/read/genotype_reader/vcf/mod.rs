@@ -425,7 +425,7 @@ mod tests {
             [[0, 0], [0, 1], [1, 1], [1, 1]]    // GT   0   0|1 1   1|1
         ];
         for allele_row in expected_alleles {
-            assert_eq!(reader.parse_coordinate()?.chromosome, ChrIdx(b'X'));
+            assert_eq!(reader.parse_coordinate()?.chromosome, ChrIdx::X);
 
             reader.fill_genotypes()?;
             for (want, sample) in allele_row.iter().zip(samples.iter().enumerate().map(|(i, s)| SampleTag::new(s, Some(i), None))) {
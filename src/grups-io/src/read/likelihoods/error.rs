@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LikelihoodError {
+    #[error("Failed to read the contents of the VCF file")]
+    ReadVcf(#[source] std::io::Error),
+
+    #[error("VCF file is missing its '#CHROM' header line")]
+    MissingHeader,
+
+    #[error("Failed to parse the following VCF record: '{0}'")]
+    InvalidRecord(String),
+
+    #[error("Invalid {field} value for sample n°{sample}: '{value}'")]
+    InvalidValue{field: String, sample: usize, value: String},
+}
@@ -0,0 +1,330 @@
+use std::{fmt::{self, Display, Formatter}, io::BufRead, path::Path};
+
+use genome::{coordinate::ChrIdx, snp::Allele, SNPCoord};
+use located_error::LocatedError;
+use anyhow::Result;
+use log::{debug, info};
+
+use super::compression;
+
+mod error;
+pub use error::LikelihoodError;
+
+const VCF_HEADER_PREFIX : &str  = "#CHROM";
+const VCF_SAMPLES_START : usize = 9;
+const MAX_POSTERIOR_PHRED: f64  = 60.0; // Cap the phred-scaled confidence of near-certain genotypes.
+
+/// FORMAT field from which genotype probabilities are extracted.
+/// - `Gp`: genotype posterior probabilities.
+/// - `Pl`: phred-scaled genotype likelihoods.
+/// - `Gl`: log10-scaled genotype likelihoods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LikelihoodField {Gp, Pl, Gl}
+
+impl LikelihoodField {
+    /// Default field priority, when the user did not explicitly request one.
+    pub const PRIORITY: [Self; 3] = [Self::Gp, Self::Pl, Self::Gl];
+
+    #[must_use]
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Self::Gp => "GP",
+            Self::Pl => "PL",
+            Self::Gl => "GL",
+        }
+    }
+
+    /// Convert a single raw FORMAT value into an unnormalized probability.
+    fn to_prob(self, value: f64) -> f64 {
+        match self {
+            Self::Gp => value,
+            Self::Pl => f64::powf(10.0, -value / 10.0),
+            Self::Gl => f64::powf(10.0, value),
+        }
+    }
+}
+
+impl Display for LikelihoodField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tag())
+    }
+}
+
+/// Normalized genotype posterior probabilities of a single individual, assuming a flat prior. Haploid genotypes are
+/// stored with a null heterozygous probability.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenotypePosteriors {
+    pub hom_ref: f64,
+    pub het    : f64,
+    pub hom_alt: f64,
+}
+
+impl GenotypePosteriors {
+    /// Build normalized posteriors from a set of raw diploid (`[RR, RA, AA]`) or haploid (`[R, A]`) FORMAT values.
+    /// Returns `None` if the values are uninformative (i.e. all equal), or cannot be normalized.
+    #[must_use]
+    pub fn from_values(values: &[f64], field: LikelihoodField) -> Option<Self> {
+        let probs = values.iter().map(|value| field.to_prob(*value)).collect::<Vec<f64>>();
+        let [hom_ref, het, hom_alt] = match probs[..] {
+            [hom_ref, het, hom_alt] => [hom_ref, het, hom_alt],
+            [hom_ref, hom_alt]      => [hom_ref, 0.0, hom_alt],
+            _ => return None
+        };
+        let sum = hom_ref + het + hom_alt;
+        let uninformative = probs.windows(2).all(|w| (w[0] - w[1]).abs() < f64::EPSILON);
+        if uninformative || !sum.is_normal() || probs.iter().any(|p| p.is_sign_negative()) {
+            return None
+        }
+        Some(Self{hom_ref: hom_ref / sum, het: het / sum, hom_alt: hom_alt / sum})
+    }
+
+    /// Probability of sampling the alternate allele, when drawing a single allele from this genotype.
+    #[must_use]
+    pub fn alt_allele_prob(&self) -> f64 {
+        self.het / 2.0 + self.hom_alt
+    }
+
+    /// Phred-scaled confidence of the most likely genotype, capped to `MAX_POSTERIOR_PHRED`.
+    #[must_use]
+    pub fn phred(&self) -> f64 {
+        let max = self.hom_ref.max(self.het).max(self.hom_alt);
+        f64::min(-10.0 * f64::log10(1.0 - max), MAX_POSTERIOR_PHRED)
+    }
+}
+
+/// Genotype posteriors of a set of samples, at a given bi-allelic SNP coordinate.
+/// # Fields:
+/// - `coordinate`: SNP coordinate, along with its REF and ALT alleles.
+/// - `posteriors`: genotype posteriors of each sample. `None` if the genotype is missing or uninformative.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LikelihoodRecord {
+    pub coordinate: SNPCoord,
+    pub posteriors: Vec<Option<GenotypePosteriors>>,
+}
+
+/// Sequential reader of per-sample genotype likelihoods (or posteriors), as found within a `.vcf[.gz]` file.
+///
+/// Only bi-allelic SNPs are considered: records carrying indels, multiple or missing ALT alleles, or located on
+/// chromosomes that cannot be parsed are skipped.
+/// # Fields:
+/// - `source` : decompressed data stream, positionned right after the VCF header.
+/// - `samples`: sample names, as defined within the VCF header.
+/// - `fields` : FORMAT fields to search for, in order of priority.
+/// - `line`   : buffer containing the last read VCF record.
+pub struct GenotypeLikelihoodReader {
+    source : Box<dyn BufRead>,
+    samples: Vec<String>,
+    fields : Vec<LikelihoodField>,
+    line   : String,
+}
+
+impl GenotypeLikelihoodReader {
+    /// Open a `.vcf[.gz]` file and parse its header.
+    /// # Arguments:
+    /// - `path`   : path leading to the VCF file.
+    /// - `field`  : FORMAT field to use. If `None`, the first available field among `GP`, `PL` and `GL` is used.
+    /// - `threads`: number of additional decompression threads (BGZF compressed files only).
+    ///
+    /// # Errors
+    /// - if `path` targets an invalid location or cannot be decompressed.
+    /// - `MissingHeader` if the file does not contain a '#CHROM' header line.
+    pub fn new(path: &Path, field: Option<LikelihoodField>, threads: usize) -> Result<Self> {
+        use LikelihoodError::{MissingHeader, ReadVcf};
+        let loc_msg = || format!("While attempting to open genotype likelihoods file {}", path.display());
+        let mut source = compression::open(path, threads).with_loc(loc_msg)?;
+
+        let mut line = String::new();
+        let samples = loop {
+            line.clear();
+            if source.read_line(&mut line).map_err(ReadVcf).with_loc(loc_msg)? == 0 {
+                return Err(MissingHeader).with_loc(loc_msg)
+            }
+            if line.starts_with(VCF_HEADER_PREFIX) {
+                break line.trim_end().split('\t').skip(VCF_SAMPLES_START).map(ToString::to_string).collect::<Vec<String>>()
+            }
+        };
+        info!("Found {} samples within {}", samples.len(), path.display());
+
+        let fields = field.map_or_else(|| LikelihoodField::PRIORITY.to_vec(), |field| vec![field]);
+        debug!("Genotype likelihood fields: {fields:?}");
+        Ok(Self{source, samples, fields, line})
+    }
+
+    /// Sample names, ordered as within the VCF header.
+    #[must_use]
+    pub fn samples(&self) -> &[String] {
+        &self.samples
+    }
+
+    /// Raw contents of the last read VCF record.
+    #[must_use]
+    pub fn line(&self) -> &str {
+        self.line.trim_end()
+    }
+
+    /// Read and decode the next valid record. Returns `None` once the end of the file is reached.
+    /// # Errors
+    /// - `ReadVcf` if the file cannot be read.
+    /// - `InvalidRecord` if a record lacks mandatory columns, or carries an invalid position.
+    /// - `InvalidValue` if any of the genotype likelihood values cannot be parsed.
+    pub fn read_record(&mut self) -> Result<Option<LikelihoodRecord>> {
+        use LikelihoodError::{InvalidRecord, ReadVcf};
+        let loc_msg = "While reading genotype likelihoods";
+        loop {
+            self.line.clear();
+            if self.source.read_line(&mut self.line).map_err(ReadVcf).loc(loc_msg)? == 0 {
+                return Ok(None)
+            }
+            let line = self.line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+
+            let fields = line.split('\t').collect::<Vec<&str>>();
+            if fields.len() < VCF_SAMPLES_START {
+                return Err(InvalidRecord(line.to_string())).loc(loc_msg)
+            }
+            let position: u32 = fields[1].parse().map_err(|_| InvalidRecord(line.to_string())).loc(loc_msg)?;
+
+            // ---- Skip non-SNPs and unparsable chromosomes.
+            let (Ok(chromosome), Ok(reference), Ok(alternate)) = (fields[0].parse::<ChrIdx>(), fields[3].parse::<Allele>(), fields[4].parse::<Allele>()) else {
+                continue
+            };
+            if [reference, alternate].iter().any(|allele| matches!(allele, Allele::N | Allele::D)) || reference == alternate {
+                continue
+            }
+
+            // ---- Search for the first available FORMAT field.
+            let format = fields[8].split(':').collect::<Vec<&str>>();
+            let Some((field, index)) = self.fields.iter().find_map(|field| {
+                format.iter().position(|tag| *tag == field.tag()).map(|index| (*field, index))
+            }) else {
+                continue
+            };
+
+            let posteriors = fields[VCF_SAMPLES_START..].iter().enumerate()
+                .map(|(sample, values)| Self::parse_posteriors(values, index, field, sample))
+                .collect::<Result<Vec<Option<GenotypePosteriors>>, LikelihoodError>>()
+                .loc(loc_msg)?;
+
+            let coordinate = SNPCoord::new(chromosome, position, reference, alternate);
+            return Ok(Some(LikelihoodRecord{coordinate, posteriors}))
+        }
+    }
+
+    /// Extract and normalize the genotype probabilities of a single sample.
+    fn parse_posteriors(sample_values: &str, index: usize, field: LikelihoodField, sample: usize) -> Result<Option<GenotypePosteriors>, LikelihoodError> {
+        let Some(raw) = sample_values.split(':').nth(index) else {
+            return Ok(None) // Trailing FORMAT fields may be dropped.
+        };
+        if raw.split(',').any(|value| value == ".") {
+            return Ok(None)
+        }
+        let values = raw.split(',')
+            .map(str::parse::<f64>)
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| LikelihoodError::InvalidValue{field: field.to_string(), sample, value: raw.to_string()})?;
+        Ok(GenotypePosteriors::from_values(&values, field))
+    }
+}
+
+impl Iterator for GenotypeLikelihoodReader {
+    type Item = Result<LikelihoodRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use std::{fs::File, io::Write, path::PathBuf};
+
+    const VCF: &str = "\
+##fileformat=VCFv4.2
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tInd0\tInd1\tInd2
+22\t100\t.\tA\tG\t.\tPASS\t.\tGT:PL:GP\t0/1:20,0,20:0.1,0.8,0.1\t0/0:0,30,60:.\t./.:.:.
+22\t150\t.\tA\tG,T\t.\tPASS\t.\tGT:PL\t0/1:20,0,20\t0/0:0,30,60\t0/0:0,0,0
+22\t175\t.\tAT\tA\t.\tPASS\t.\tGT:PL\t0/1:20,0,20\t0/0:0,30,60\t0/0:0,0,0
+unplaced_scaffold\t200\t.\tA\tG\t.\tPASS\t.\tGT:PL\t0/1:20,0,20\t0/0:0,30,60\t0/0:0,0,0
+X\t300\t.\tC\tT\t.\tPASS\t.\tGT:GL\t1:-2,0\t0/0:0,0,0\t0/1:-1,0,-1
+";
+
+    fn write_vcf(dir: &Path) -> Result<PathBuf> {
+        let path = dir.join("test.vcf");
+        File::create(&path)?.write_all(VCF.as_bytes())?;
+        Ok(path)
+    }
+
+    #[test]
+    fn posteriors_from_values() {
+        let gp = GenotypePosteriors::from_values(&[0.2, 0.6, 0.2], LikelihoodField::Gp).expect("Valid GP values");
+        assert_eq!(gp.alt_allele_prob(), 0.5);
+
+        let pl = GenotypePosteriors::from_values(&[0.0, 10.0, 20.0], LikelihoodField::Pl).expect("Valid PL values");
+        assert!((pl.hom_ref - 1.0 / 1.11).abs() < 1e-9);
+        assert!((pl.het     - 0.1 / 1.11).abs() < 1e-9);
+
+        let haploid = GenotypePosteriors::from_values(&[-1.0, 0.0], LikelihoodField::Gl).expect("Valid GL values");
+        assert_eq!(haploid.het, 0.0);
+        assert!((haploid.alt_allele_prob() - 1.0 / 1.1).abs() < 1e-9);
+
+        assert!(GenotypePosteriors::from_values(&[0.0, 0.0, 0.0], LikelihoodField::Pl).is_none());
+        assert!(GenotypePosteriors::from_values(&[0.0, 10.0, 20.0, 30.0], LikelihoodField::Pl).is_none());
+    }
+
+    #[test]
+    fn posterior_phred() {
+        let certain = GenotypePosteriors{hom_ref: 1.0, het: 0.0, hom_alt: 0.0};
+        assert_eq!(certain.phred(), MAX_POSTERIOR_PHRED);
+        let uncertain = GenotypePosteriors{hom_ref: 0.9, het: 0.1, hom_alt: 0.0};
+        assert!((uncertain.phred() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn read_auto_fields() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let reader = GenotypeLikelihoodReader::new(&write_vcf(tmpdir.path())?, None, 0)?;
+        assert_eq!(reader.samples(), ["Ind0", "Ind1", "Ind2"]);
+
+        let records = reader.collect::<Result<Vec<LikelihoodRecord>>>()?;
+        assert_eq!(records.len(), 2);
+
+        // ---- GP is favored over PL, even when missing for some samples.
+        assert_eq!(records[0].coordinate, SNPCoord::new(22, 100, Allele::A, Allele::G));
+        assert_eq!(records[0].posteriors[0], Some(GenotypePosteriors{hom_ref: 0.1, het: 0.8, hom_alt: 0.1}));
+        assert_eq!(records[0].posteriors[1..], [None, None]);
+
+        // ---- Haploid and uninformative genotype likelihoods.
+        assert_eq!(records[1].coordinate, SNPCoord::new(ChrIdx::X, 300, Allele::C, Allele::T));
+        assert!(records[1].posteriors[0].is_some_and(|gp| gp.het == 0.0 && gp.hom_alt > 0.99));
+        assert_eq!(records[1].posteriors[1], None);
+        assert!(records[1].posteriors[2].is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn read_requested_field() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let mut reader = GenotypeLikelihoodReader::new(&write_vcf(tmpdir.path())?, Some(LikelihoodField::Pl), 0)?;
+        let record = reader.read_record()?.expect("First record");
+        assert!(record.posteriors[0].is_some_and(|gp| gp.het > 0.98));
+        assert!(record.posteriors[1].is_some_and(|gp| gp.hom_ref > 0.99));
+        assert_eq!(reader.line().split('\t').nth(1), Some("100"));
+
+        // ---- Second valid record does not carry any PL field.
+        assert!(reader.read_record()?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn missing_header() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let path = tmpdir.path().join("headerless.vcf");
+        File::create(&path)?.write_all(b"22\t100\t.\tA\tG\t.\tPASS\t.\tGT\t0/1\n")?;
+        assert!(GenotypeLikelihoodReader::new(&path, None, 0).is_err());
+        Ok(())
+    }
+}
@@ -1,5 +1,15 @@
 pub mod genotype_reader;
 
+pub mod bam;
+
+pub mod compression;
+
+pub mod tabix;
+
+pub mod pseudo_haploid;
+
+pub mod likelihoods;
+
 mod snpreader;
 pub use snpreader::{SNPReader, SNPReaderError};
 
@@ -7,4 +17,7 @@ mod panel_reader;
 pub use panel_reader::PanelReader;
 
 mod sampletag;
-pub use sampletag::SampleTag;
\ No newline at end of file
+pub use sampletag::SampleTag;
+
+mod sample_sheet;
+pub use sample_sheet::{SampleSheet, SampleEntry, SampleSheetError};
\ No newline at end of file
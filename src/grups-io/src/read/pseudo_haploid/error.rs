@@ -0,0 +1,28 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PseudoHaploidError {
+    #[error("Failed to read the contents of {0}")]
+    ReadFile(String, #[source] std::io::Error),
+
+    #[error("Invalid PLINK .bed magic number. Only SNP-major PLINK 1 binary files are supported")]
+    InvalidBedMagic,
+
+    #[error("Transposed EIGENSTRAT genotype files (TGENO) are not supported. Please convert them using 'convertf' beforehand")]
+    TransposedGeno,
+
+    #[error("Invalid packed EIGENSTRAT header: {0}")]
+    InvalidGenoHeader(String),
+
+    #[error("Number of individuals found in the genotype file ({found}) does not match that of the individual file ({expected})")]
+    IndividualCountMismatch{expected: usize, found: usize},
+
+    #[error("Genotype file ended prematurely, at SNP n°{0}")]
+    TruncatedGenotypes(usize),
+
+    #[error("Invalid EIGENSTRAT genotype character: '{0}'. Expected either '0', '1', '2' or '9'")]
+    InvalidGenotype(char),
+
+    #[error("Failed to parse the following SNP definition line: '{0}'")]
+    InvalidSnpLine(String),
+}
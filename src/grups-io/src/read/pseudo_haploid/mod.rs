@@ -0,0 +1,408 @@
+use std::{fmt::{self, Display, Formatter}, io::{BufRead, Read}, path::{Path, PathBuf}};
+
+use genome::{coordinate::ChrIdx, snp::Allele, SNPCoord};
+use located_error::LocatedError;
+use anyhow::Result;
+use log::{debug, info};
+
+use super::compression;
+
+mod error;
+pub use error::PseudoHaploidError;
+
+const PACKED_GENO_MAGIC  : &[u8; 4] = b"GENO";
+const TRANSPOSED_MAGIC   : &[u8; 5] = b"TGENO";
+const PACKED_GENO_MIN_LEN: usize    = 48;                 // Minimal record length of packed EIGENSTRAT files.
+const PLINK_BED_MAGIC    : [u8; 3]  = [0x6c, 0x1b, 0x01]; // SNP-major PLINK 1 binary file.
+
+/// Supported pseudo-haploid genotype dataset formats.
+/// - `Eigenstrat`: `.geno`, `.snp`, `.ind` (either plain text or packed `.geno` files)
+/// - `Plink`     : `.bed`, `.bim`, `.fam` (SNP-major binary files)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenotypeFormat {Eigenstrat, Plink}
+
+impl GenotypeFormat {
+    /// File extensions of the genotype, SNP and individual files, respectively.
+    #[must_use]
+    pub fn extensions(&self) -> [&'static str; 3] {
+        match self {
+            Self::Eigenstrat => ["geno", "snp", "ind"],
+            Self::Plink      => ["bed", "bim", "fam"],
+        }
+    }
+
+    /// Column indices of the chromosome, position, first and second alleles within SNP definition files.
+    fn snp_columns(&self) -> [usize; 4] {
+        match self {
+            Self::Eigenstrat => [1, 3, 4, 5],
+            Self::Plink      => [0, 3, 4, 5],
+        }
+    }
+
+    /// Column index of the individual identifier within individual definition files.
+    fn individual_column(&self) -> usize {
+        match self {
+            Self::Eigenstrat => 0,
+            Self::Plink      => 1,
+        }
+    }
+}
+
+impl Display for GenotypeFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Eigenstrat => write!(f, "EIGENSTRAT"),
+            Self::Plink      => write!(f, "PLINK"),
+        }
+    }
+}
+
+/// Encoding of the genotype file.
+/// - `Text`             : plain text EIGENSTRAT `.geno` file: one line per SNP, one character per individual.
+/// - `PackedAncestryMap`: packed EIGENSTRAT `.geno` file: 2 bits per individual, most significant bits first.
+/// - `PlinkBed`         : SNP-major PLINK `.bed` file: 2 bits per individual, least significant bits first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GenotypeEncoding {Text, PackedAncestryMap, PlinkBed}
+
+/// Pseudo-haploid genotype calls of a set of individuals, at a given SNP coordinate.
+/// # Fields:
+/// - `coordinate`: SNP coordinate, along with the two alleles defined within the SNP file.
+/// - `calls`     : called allele of each individual. `None` if the genotype is missing or heterozygous.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HaploidCalls {
+    pub coordinate: SNPCoord,
+    pub calls     : Vec<Option<Allele>>,
+}
+
+/// Sequential reader of pseudo-haploid genotype datasets (EIGENSTRAT or PLINK).
+///
+/// Genotypes are expected to be pseudo-haploid, i.e. homozygous. Heterozygous genotypes are thus considered as missing.
+/// Numeric sex-chromosome and mitochondrial codes are translated using the conventions of each format (see
+/// `ChrIdx::from_eigenstrat()` and `ChrIdx::from_plink()`). SNPs located on unplaced (`0`) or unknown contigs, or
+/// carrying invalid alleles are skipped.
+/// # Fields:
+/// - `format`     : format of the dataset.
+/// - `encoding`   : encoding of the genotype file.
+/// - `genotypes`  : genotype file reader, positionned right after its header.
+/// - `snps`       : SNP definition file reader.
+/// - `individuals`: individual identifiers, as defined within the individual file.
+/// - `nsnp`       : number of SNP definitions read so far.
+/// - `line`       : buffer containing the last read SNP definition line.
+/// - `record`     : buffer containing the last read raw genotype record.
+pub struct PseudoHaploidReader {
+    format     : GenotypeFormat,
+    encoding   : GenotypeEncoding,
+    genotypes  : Box<dyn BufRead>,
+    snps       : Box<dyn BufRead>,
+    individuals: Vec<String>,
+    nsnp       : usize,
+    line       : String,
+    record     : Vec<u8>,
+}
+
+impl PseudoHaploidReader {
+    /// Open a genotype dataset, using its file prefix (i.e. `<prefix>.geno`, `<prefix>.snp` and `<prefix>.ind` for
+    /// EIGENSTRAT datasets).
+    /// # Errors
+    /// - if any of the three files cannot be opened or read.
+    /// - `InvalidBedMagic` if the `.bed` file is not a SNP-major PLINK binary file.
+    /// - `TransposedGeno` if the `.geno` file is a transposed, packed EIGENSTRAT file.
+    /// - `InvalidGenoHeader` or `IndividualCountMismatch` if the header of a packed `.geno` file is invalid.
+    pub fn new(prefix: &Path, format: GenotypeFormat) -> Result<Self> {
+        let loc_msg = || format!("While attempting to open {format} dataset {}", prefix.display());
+        let [geno_path, snp_path, ind_path] = format.extensions().map(|ext| with_extension(prefix, ext));
+
+        let individuals = Self::parse_individuals(&ind_path, format).with_loc(loc_msg)?;
+        info!("Found {} individuals within {}", individuals.len(), ind_path.display());
+        let snps = compression::open(&snp_path, 0).with_loc(loc_msg)?;
+        let mut genotypes = compression::open(&geno_path, 0).with_loc(loc_msg)?;
+        let encoding = Self::parse_genotype_header(&mut genotypes, &geno_path, format, individuals.len()).with_loc(loc_msg)?;
+        debug!("{} genotype file encoding: {encoding:?}", geno_path.display());
+
+        Ok(Self{format, encoding, genotypes, snps, individuals, nsnp: 0, line: String::new(), record: Vec::new()})
+    }
+
+    /// Individual identifiers, ordered as within the dataset.
+    #[must_use]
+    pub fn individuals(&self) -> &[String] {
+        &self.individuals
+    }
+
+    fn parse_individuals(path: &Path, format: GenotypeFormat) -> Result<Vec<String>> {
+        let column = format.individual_column();
+        let mut individuals = Vec::new();
+        for line in compression::open(path, 0)?.lines() {
+            let line = line.map_err(|e| PseudoHaploidError::ReadFile(path.display().to_string(), e))?;
+            if let Some(id) = line.split_whitespace().nth(column) {
+                individuals.push(id.to_string());
+            }
+        }
+        Ok(individuals)
+    }
+
+    /// Detect the encoding of the genotype file, and skip its header.
+    fn parse_genotype_header(source: &mut Box<dyn BufRead>, path: &Path, format: GenotypeFormat, nind: usize) -> Result<GenotypeEncoding, PseudoHaploidError> {
+        use PseudoHaploidError::{IndividualCountMismatch, InvalidBedMagic, InvalidGenoHeader, ReadFile, TransposedGeno};
+        let read_err = |e| ReadFile(path.display().to_string(), e);
+        if format == GenotypeFormat::Plink {
+            let mut magic = [0u8; 3];
+            source.read_exact(&mut magic).map_err(read_err)?;
+            return match magic == PLINK_BED_MAGIC {
+                true  => Ok(GenotypeEncoding::PlinkBed),
+                false => Err(InvalidBedMagic),
+            }
+        }
+
+        let magic = source.fill_buf().map_err(read_err)?;
+        if magic.starts_with(TRANSPOSED_MAGIC) {
+            return Err(TransposedGeno)
+        }
+        if !magic.starts_with(PACKED_GENO_MAGIC) {
+            return Ok(GenotypeEncoding::Text)
+        }
+
+        // ---- Packed ancestry map: 'GENO <nind> <nsnp> <hashind> <hashsnp>', padded up to the record length.
+        let mut header = vec![0u8; packed_record_len(nind).max(PACKED_GENO_MIN_LEN)];
+        source.read_exact(&mut header).map_err(read_err)?;
+        let header = String::from_utf8_lossy(&header).trim_end_matches('\0').to_string();
+        let found = header.split_whitespace().nth(1)
+            .and_then(|field| field.parse::<usize>().ok())
+            .ok_or_else(|| InvalidGenoHeader(header.clone()))?;
+        if found != nind {
+            return Err(IndividualCountMismatch{expected: nind, found})
+        }
+        Ok(GenotypeEncoding::PackedAncestryMap)
+    }
+
+    /// Read and decode the next valid record. Returns `None` once the end of the SNP file is reached.
+    /// # Errors
+    /// - `ReadFile` if either the SNP or genotype file cannot be read.
+    /// - `InvalidSnpLine` if a SNP definition line lacks some columns, or carries an invalid position.
+    /// - `TruncatedGenotypes` if the genotype file contains less SNPs than the SNP file.
+    /// - `InvalidGenotype` or `IndividualCountMismatch` if a text genotype line is invalid.
+    pub fn read_record(&mut self) -> Result<Option<HaploidCalls>> {
+        use PseudoHaploidError::{InvalidSnpLine, ReadFile};
+        let format  = self.format;
+        let loc_msg = |nsnp| format!("While reading SNP n°{nsnp} of the {format} dataset");
+        loop {
+            self.line.clear();
+            let read = self.snps.read_line(&mut self.line).map_err(|e| ReadFile(String::from("SNP file"), e));
+            if read.with_loc(|| loc_msg(self.nsnp + 1))? == 0 {
+                return Ok(None)
+            }
+            if self.line.trim().is_empty() {
+                continue
+            }
+            self.nsnp += 1;
+
+            // ---- Always consume the matching genotype record, to keep both files in sync.
+            self.read_genotypes().with_loc(|| loc_msg(self.nsnp))?;
+
+            let fields = self.line.split_whitespace().collect::<Vec<&str>>();
+            let [chr, pos, a1, a2] = format.snp_columns().map(|i| fields.get(i).copied());
+            let (Some(chr), Some(pos), Some(a1), Some(a2)) = (chr, pos, a1, a2) else {
+                return Err(InvalidSnpLine(self.line.trim().to_string())).with_loc(|| loc_msg(self.nsnp))
+            };
+            let position: u32 = pos.parse()
+                .map_err(|_| InvalidSnpLine(self.line.trim().to_string()))
+                .with_loc(|| loc_msg(self.nsnp))?;
+
+            let chromosome = match format {
+                GenotypeFormat::Eigenstrat => ChrIdx::from_eigenstrat(chr),
+                GenotypeFormat::Plink      => ChrIdx::from_plink(chr),
+            };
+            let Ok(chromosome) = chromosome else {
+                continue // e.g. unplaced ('0') or unregistered contigs
+            };
+            let (Ok(a1), Ok(a2)) = (a1.parse::<Allele>(), a2.parse::<Allele>()) else {
+                continue // e.g. indels or missing alleles ('0')
+            };
+
+            // ---- EIGENSTRAT counts the number of copies of the first allele. Thus, treat it as the reference allele.
+            //      PLINK usually stores the major (or reference) allele as A2.
+            let (reference, alternate) = match format {
+                GenotypeFormat::Eigenstrat => (a1, a2),
+                GenotypeFormat::Plink      => (a2, a1),
+            };
+            let calls = self.decode_calls(a1, a2).with_loc(|| loc_msg(self.nsnp))?;
+            return Ok(Some(HaploidCalls{coordinate: SNPCoord::new(chromosome, position, reference, alternate), calls}))
+        }
+    }
+
+    /// Read the raw genotype record matching the current SNP into `self.record`.
+    fn read_genotypes(&mut self) -> Result<(), PseudoHaploidError> {
+        use PseudoHaploidError::{IndividualCountMismatch, ReadFile, TruncatedGenotypes};
+        let read_err = |e| ReadFile(String::from("genotype file"), e);
+        match self.encoding {
+            GenotypeEncoding::Text => {
+                self.record.clear();
+                if self.genotypes.read_until(b'\n', &mut self.record).map_err(read_err)? == 0 {
+                    return Err(TruncatedGenotypes(self.nsnp))
+                }
+                while self.record.last().is_some_and(u8::is_ascii_whitespace) {
+                    self.record.pop();
+                }
+                if self.record.len() != self.individuals.len() {
+                    return Err(IndividualCountMismatch{expected: self.individuals.len(), found: self.record.len()})
+                }
+            },
+            GenotypeEncoding::PackedAncestryMap | GenotypeEncoding::PlinkBed => {
+                let record_len = match self.encoding {
+                    GenotypeEncoding::PackedAncestryMap => packed_record_len(self.individuals.len()).max(PACKED_GENO_MIN_LEN),
+                    _                                   => packed_record_len(self.individuals.len()),
+                };
+                self.record.resize(record_len, 0);
+                self.genotypes.read_exact(&mut self.record).map_err(|_| TruncatedGenotypes(self.nsnp))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode the genotype record of the current SNP into haploid calls. `a1` and `a2` are the first and second alleles,
+    /// as defined within the SNP file.
+    fn decode_calls(&self, a1: Allele, a2: Allele) -> Result<Vec<Option<Allele>>, PseudoHaploidError> {
+        (0..self.individuals.len()).map(|i| {
+            Ok(match self.encoding {
+                GenotypeEncoding::Text => match self.record[i] {
+                    b'2'        => Some(a1),
+                    b'0'        => Some(a2),
+                    b'1' | b'9' => None,
+                    other       => return Err(PseudoHaploidError::InvalidGenotype(char::from(other)))
+                },
+                GenotypeEncoding::PackedAncestryMap => match (self.record[i / 4] >> (6 - 2 * (i % 4))) & 0b11 {
+                    0b10 => Some(a1),
+                    0b00 => Some(a2),
+                    _    => None,
+                },
+                GenotypeEncoding::PlinkBed => match (self.record[i / 4] >> (2 * (i % 4))) & 0b11 {
+                    0b00 => Some(a1),
+                    0b11 => Some(a2),
+                    _    => None,
+                },
+            })
+        }).collect()
+    }
+}
+
+impl Iterator for PseudoHaploidReader {
+    type Item = Result<HaploidCalls>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Number of bytes required to store the 2-bit encoded genotypes of `nind` individuals.
+// `usize::is_multiple_of()` is only stable since Rust 1.87, which is above our MSRV.
+#[allow(clippy::manual_is_multiple_of)]
+fn packed_record_len(nind: usize) -> usize {
+    nind / 4 + usize::from(nind % 4 != 0)
+}
+
+/// Append an extension to a file prefix, without replacing any existing extension (e.g. `v54.1_1240K` -> `v54.1_1240K.geno`)
+fn with_extension(prefix: &Path, ext: &str) -> PathBuf {
+    let mut path = prefix.as_os_str().to_owned();
+    path.push(format!(".{ext}"));
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs::File, io::Write};
+    use Allele::{A, C, G, T};
+
+    const SNP: &str = "rs1 22 0.0 100 A C\nrs2 22 0.0 200 G T\nrs3 90 0.0 300 A G\nrs4 23 0.0 400 C T\nrs5 0 0.0 500 A G\n";
+    const IND: &str = "Ind0 M Pop\nInd1 F Pop\nInd2 U Pop\n";
+
+    fn write_files(prefix: &Path, files: &[(&str, &[u8])]) -> Result<()> {
+        for (ext, contents) in files {
+            File::create(with_extension(prefix, ext))?.write_all(contents)?;
+        }
+        Ok(())
+    }
+
+    fn expected_calls() -> Vec<HaploidCalls> {
+        vec![
+            HaploidCalls{coordinate: SNPCoord::new(22, 100, A, C), calls: vec![Some(A), Some(C), None]},
+            HaploidCalls{coordinate: SNPCoord::new(22, 200, G, T), calls: vec![None, Some(G), Some(T)]},
+            HaploidCalls{coordinate: SNPCoord::new(ChrIdx::MT, 300, A, G), calls: vec![Some(A), Some(A), Some(A)]},
+            HaploidCalls{coordinate: SNPCoord::new(ChrIdx::X, 400, C, T), calls: vec![Some(T), None, Some(C)]},
+        ]
+    }
+
+    #[test]
+    fn read_eigenstrat_text() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let prefix = tmpdir.path().join("v54.1_1240K");
+        write_files(&prefix, &[("geno", b"209\n920\n222\n012\n999\n"), ("snp", SNP.as_bytes()), ("ind", IND.as_bytes())])?;
+
+        let reader = PseudoHaploidReader::new(&prefix, GenotypeFormat::Eigenstrat)?;
+        assert_eq!(reader.individuals(), ["Ind0", "Ind1", "Ind2"]);
+        assert_eq!(reader.collect::<Result<Vec<HaploidCalls>>>()?, expected_calls());
+        Ok(())
+    }
+
+    #[test]
+    fn read_eigenstrat_packed() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let prefix = tmpdir.path().join("packed");
+        let mut geno = b"GENO 3 5 0 0".to_vec();
+        geno.resize(PACKED_GENO_MIN_LEN, 0);
+        for record in [0b1000_1100, 0b1110_0000, 0b1010_1000, 0b0001_1000, 0b0101_0100] {
+            let mut record = vec![record];
+            record.resize(PACKED_GENO_MIN_LEN, 0);
+            geno.extend(record);
+        }
+        write_files(&prefix, &[("geno", &geno), ("snp", SNP.as_bytes()), ("ind", IND.as_bytes())])?;
+
+        let reader = PseudoHaploidReader::new(&prefix, GenotypeFormat::Eigenstrat)?;
+        assert_eq!(reader.collect::<Result<Vec<HaploidCalls>>>()?, expected_calls());
+        Ok(())
+    }
+
+    #[test]
+    fn read_plink() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let prefix = tmpdir.path().join("plink");
+        let bim = "22 rs1 0 100 C A\n22 rs2 0 200 T G\n26 rs3 0 300 G A\n23 rs4 0 400 T C\n0 rs5 0 500 G A\n";
+        let fam = "Fam0 Ind0 0 0 1 -9\nFam1 Ind1 0 0 2 -9\nFam2 Ind2 0 0 0 -9\n";
+        let mut bed = PLINK_BED_MAGIC.to_vec();
+        bed.extend([0b0001_0011, 0b0000_1101, 0b0011_1111, 0b0011_1000, 0b0001_0101]);
+        write_files(&prefix, &[("bed", &bed), ("bim", bim.as_bytes()), ("fam", fam.as_bytes())])?;
+
+        let reader = PseudoHaploidReader::new(&prefix, GenotypeFormat::Plink)?;
+        assert_eq!(reader.individuals(), ["Ind0", "Ind1", "Ind2"]);
+        assert_eq!(reader.collect::<Result<Vec<HaploidCalls>>>()?, expected_calls());
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_genotypes() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let prefix = tmpdir.path().join("truncated");
+        write_files(&prefix, &[("geno", b"209\n"), ("snp", SNP.as_bytes()), ("ind", IND.as_bytes())])?;
+
+        let mut reader = PseudoHaploidReader::new(&prefix, GenotypeFormat::Eigenstrat)?;
+        assert!(reader.read_record()?.is_some());
+        assert!(reader.read_record().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_headers() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let prefix = tmpdir.path().join("invalid");
+        write_files(&prefix, &[("bed", &[0x6c, 0x1b, 0x00]), ("bim", b""), ("fam", b"")])?;
+        assert!(PseudoHaploidReader::new(&prefix, GenotypeFormat::Plink).is_err());
+
+        write_files(&prefix, &[("geno", b"TGENO 3 4 0 0"), ("snp", SNP.as_bytes()), ("ind", IND.as_bytes())])?;
+        assert!(PseudoHaploidReader::new(&prefix, GenotypeFormat::Eigenstrat).is_err());
+
+        let mut geno = b"GENO 2 4 0 0".to_vec();
+        geno.resize(PACKED_GENO_MIN_LEN, 0);
+        write_files(&prefix, &[("geno", &geno)])?;
+        assert!(PseudoHaploidReader::new(&prefix, GenotypeFormat::Eigenstrat).is_err());
+        Ok(())
+    }
+}
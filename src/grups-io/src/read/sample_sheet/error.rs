@@ -0,0 +1,24 @@
+use thiserror::Error;
+
+const COMMON_MSG: &str = "Each line of the sample sheet should contain 2 to 6 whitespace-separated fields: <name (required)> <pileup column (required)> <min depth> <contamination rate> <sequencing error rate> <sex> (e.g. 'ART04 0 2 0.0-5.0 . male'). Use '.' to leave an optional field undefined";
+
+#[derive(Error, Debug)]
+pub enum SampleSheetError {
+    #[error("Failed to open or read the provided sample sheet")]
+    Read(#[source] std::io::Error),
+
+    #[error("Line {0} of the provided sample sheet does not contain the appropriate number of fields. {COMMON_MSG}")]
+    InvalidNumberOfFields(usize),
+
+    #[error("Line {line} - Invalid {field} '{value}' in the provided sample sheet. {COMMON_MSG}")]
+    InvalidField{line: usize, field: &'static str, value: String},
+
+    #[error("Line {0} - Sample name '{1}' is defined multiple times within the provided sample sheet")]
+    DuplicateName(usize, String),
+
+    #[error("Line {0} - Pileup column {1} is assigned to multiple samples within the provided sample sheet")]
+    DuplicateColumn(usize, usize),
+
+    #[error("The provided sample sheet does not define any sample")]
+    Empty,
+}
@@ -0,0 +1,213 @@
+use std::{fs::File, io::{BufRead, BufReader}, path::Path, str::FromStr};
+
+use genome::Sex;
+
+use anyhow::Result;
+use located_error::LocatedError;
+
+mod error;
+pub use error::SampleSheetError;
+
+/// Placeholder of an undefined optional field.
+const UNDEFINED_FIELD: &str = ".";
+
+/// A single sample of a sample sheet.
+/// - `name`          : name of the sample.
+/// - `column`        : 0-based column index of the sample within the input pileup.
+/// - `min_depth`     : minimal sequencing depth required to perform comparison.
+/// - `contam_rate`   : contamination rate (or rate range) applied during pedigree simulations, as a ratio.
+/// - `seq_error_rate`: sequencing error rate (or rate range) applied during pedigree simulations, as a ratio.
+/// - `sex`           : chromosomal sex of the sample.
+///
+/// Undefined optional fields fall back to the values provided through the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleEntry {
+    pub name          : String,
+    pub column        : usize,
+    pub min_depth     : Option<u16>,
+    pub contam_rate   : Option<Vec<f64>>,
+    pub seq_error_rate: Option<Vec<f64>>,
+    pub sex           : Option<Sex>,
+}
+
+/// Input sample sheet reader.
+/// ### File characteristics:
+/// - Whitespace-separated fields
+/// - Columns: <name>  <pileup-column>  <min-depth (optional)>  <contam-rate (optional)>  <seq-error-rate (optional)>  <sex (optional)>
+/// - Lines starting with '#', along with an optional header line starting with 'name', are ignored.
+/// - Rates are specified as percentages, either as a constant value (e.g. '2.0') or as a range (e.g. '0.0-5.0').
+/// ### Fields:
+/// - samples: sample entries, in order of appearance.
+#[derive(Debug, Clone)]
+pub struct SampleSheet {
+    samples: Vec<SampleEntry>,
+}
+
+impl SampleSheet {
+    /// Instantiate a sample sheet from a file.
+    ///
+    /// # Errors
+    /// - if `path` targets an invalid location or cannot be read.
+    /// - if any of the lines is invalid (see `SampleSheet::from_reader()`).
+    pub fn new(path: &Path) -> Result<Self> {
+        let loc_msg = || format!("While parsing sample sheet {}", path.display());
+        let source = File::open(path).map(BufReader::new).map_err(SampleSheetError::Read).with_loc(loc_msg)?;
+        Self::from_reader(source).with_loc(loc_msg)
+    }
+
+    /// Parse a sample sheet from a buffered reader.
+    ///
+    /// # Errors
+    /// - `InvalidNumberOfFields` if a line contains less than 2, or more than 6 fields.
+    /// - `InvalidField` if any of the fields cannot be parsed.
+    /// - `DuplicateName` or `DuplicateColumn` if two samples share the same name or pileup column.
+    /// - `Empty` if the sheet does not define any sample.
+    pub fn from_reader(source: impl BufRead) -> Result<Self, SampleSheetError> {
+        use SampleSheetError::{DuplicateColumn, DuplicateName, Empty, InvalidNumberOfFields, Read};
+        let mut samples: Vec<SampleEntry> = Vec::new();
+        for (i, line) in source.lines().enumerate() {
+            let line = line.map_err(Read)?;
+            let fields = line.split_whitespace().collect::<Vec<&str>>();
+            let is_header = samples.is_empty() && fields.first().is_some_and(|field| field.eq_ignore_ascii_case("name"));
+            if fields.is_empty() || fields[0].starts_with('#') || is_header {
+                continue
+            }
+            if !(2..=6).contains(&fields.len()) {
+                return Err(InvalidNumberOfFields(i + 1))
+            }
+
+            let entry = Self::parse_entry(i + 1, &fields)?;
+            if samples.iter().any(|sample| sample.name == entry.name) {
+                return Err(DuplicateName(i + 1, entry.name))
+            }
+            if samples.iter().any(|sample| sample.column == entry.column) {
+                return Err(DuplicateColumn(i + 1, entry.column))
+            }
+            samples.push(entry);
+        }
+
+        if samples.is_empty() {
+            return Err(Empty)
+        }
+        Ok(Self{samples})
+    }
+
+    fn parse_entry(line: usize, fields: &[&str]) -> Result<SampleEntry, SampleSheetError> {
+        let invalid = |field, value: &str| SampleSheetError::InvalidField{line, field, value: value.to_string()};
+        let optional = |index: usize| fields.get(index).copied().filter(|value| *value != UNDEFINED_FIELD);
+        let rate = |index: usize, field| optional(index)
+            .map(|value| parse_rate(value).ok_or_else(|| invalid(field, value)))
+            .transpose();
+
+        Ok(SampleEntry {
+            name          : fields[0].to_string(),
+            column        : fields[1].parse().map_err(|_| invalid("pileup column", fields[1]))?,
+            min_depth     : optional(2).map(|value| value.parse().ok().filter(|depth| *depth > 0).ok_or_else(|| invalid("min depth", value))).transpose()?,
+            contam_rate   : rate(3, "contamination rate")?,
+            seq_error_rate: rate(4, "sequencing error rate")?,
+            sex           : optional(5).map(|value| match Sex::from_str(value) {
+                Ok(Sex::Unknown) | Err(_) => Err(invalid("sex", value)),
+                Ok(sex)                   => Ok(sex),
+            }).transpose()?,
+        })
+    }
+
+    /// Return the pileup column of every sample, in order of appearance.
+    #[must_use]
+    pub fn columns(&self) -> Vec<usize> {
+        self.samples.iter().map(|sample| sample.column).collect()
+    }
+
+    /// Return the name of every sample, in order of appearance.
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        self.samples.iter().map(|sample| sample.name.clone()).collect()
+    }
+
+    /// Return the minimal depth of every sample, in order of appearance. Undefined values are taken from `defaults`,
+    /// which are recycled across samples.
+    #[must_use]
+    pub fn min_depths(&self, defaults: &[u16]) -> Vec<u16> {
+        self.samples.iter().enumerate()
+            .map(|(i, sample)| sample.min_depth.unwrap_or(defaults[i % defaults.len()]))
+            .collect()
+    }
+
+    /// Retrieve a sample from its pileup column.
+    #[must_use]
+    pub fn get(&self, column: usize) -> Option<&SampleEntry> {
+        self.samples.iter().find(|sample| sample.column == column)
+    }
+
+    /// Iterate over all samples, in order of appearance.
+    pub fn iter(&self) -> impl Iterator<Item = &SampleEntry> {
+        self.samples.iter()
+    }
+}
+
+/// Parse a percentage (e.g. '2.0') or a range of percentages (e.g. '0.0-5.0') into a vector of ratios.
+fn parse_rate(value: &str) -> Option<Vec<f64>> {
+    let rates = value.split('-')
+        .map(|percent| percent.parse::<f64>().ok().filter(|percent| (0.0..=100.0).contains(percent)))
+        .collect::<Option<Vec<f64>>>()?;
+    (1..=2).contains(&rates.len()).then(|| rates.into_iter().map(|percent| percent / 100.0).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(sheet: &str) -> Result<SampleSheet, SampleSheetError> {
+        SampleSheet::from_reader(sheet.as_bytes())
+    }
+
+    #[test]
+    fn parse_sheet() -> Result<()> {
+        let sheet = parse("name\tcolumn\tmin_depth\tcontam\tseq_error\tsex\n\
+                           # Comment\n\
+                           ART04\t3\t2\t0.0-5.0\t1.0\tmale\n\
+                           \n\
+                           ART16\t0\n\
+                           ART20\t5\t.\t2.0\t.\tfemale\n")?;
+
+        assert_eq!(sheet.columns(), [3, 0, 5]);
+        assert_eq!(sheet.names(), ["ART04", "ART16", "ART20"]);
+        assert_eq!(sheet.min_depths(&[1]), [2, 1, 1]);
+
+        let entry = sheet.get(3).expect("ART04 should be retrievable");
+        assert_eq!(entry.contam_rate, Some(vec![0.0, 0.05]));
+        assert_eq!(entry.seq_error_rate, Some(vec![0.01]));
+        assert_eq!(entry.sex, Some(Sex::Male));
+
+        let entry = sheet.get(5).expect("ART20 should be retrievable");
+        assert_eq!(entry.contam_rate, Some(vec![0.02]));
+        assert_eq!((entry.min_depth, &entry.seq_error_rate, entry.sex), (None, &None, Some(Sex::Female)));
+        assert!(sheet.get(1).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_sheets() {
+        assert!(matches!(parse("ART04\n"), Err(SampleSheetError::InvalidNumberOfFields(1))));
+        assert!(matches!(parse("ART04 0 1 0 0 male 1\n"), Err(SampleSheetError::InvalidNumberOfFields(1))));
+        assert!(matches!(parse("ART04 A\n"), Err(SampleSheetError::InvalidField{line: 1, ..})));
+        assert!(matches!(parse("ART04 0 0\n"), Err(SampleSheetError::InvalidField{line: 1, ..})));
+        assert!(matches!(parse("ART04 0 1 120\n"), Err(SampleSheetError::InvalidField{line: 1, ..})));
+        assert!(matches!(parse("ART04 0 1 1-2-3\n"), Err(SampleSheetError::InvalidField{line: 1, ..})));
+        assert!(matches!(parse("ART04 0 1 1 1 unknown\n"), Err(SampleSheetError::InvalidField{line: 1, ..})));
+        assert!(matches!(parse("ART04 0\nART04 1\n"), Err(SampleSheetError::DuplicateName(2, _))));
+        assert!(matches!(parse("ART04 0\nART16 0\n"), Err(SampleSheetError::DuplicateColumn(2, 0))));
+        assert!(matches!(parse("# Empty\n"), Err(SampleSheetError::Empty)));
+    }
+
+    #[test]
+    fn read_error_source() {
+        let invalid_utf8: &[u8] = b"ART04 0\n\xFF\n";
+        let err = SampleSheet::from_reader(invalid_utf8).expect_err("Invalid UTF-8 should be rejected");
+        assert!(matches!(&err, SampleSheetError::Read(e) if e.kind() == std::io::ErrorKind::InvalidData));
+
+        let err = SampleSheet::new(Path::new("missing-sample-sheet.tsv")).expect_err("Missing files should be rejected");
+        let source = err.chain().find_map(|e| e.downcast_ref::<std::io::Error>());
+        assert!(source.is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound));
+    }
+}
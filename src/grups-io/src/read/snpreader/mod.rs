@@ -135,7 +135,7 @@ impl<'a> SNPReader<'a> {
             let alternate : Allele     = split_line[self.columns[3]].parse().with_loc(context)?;
 
             if chromosome == ChrIdx(23) && self.mode == SNPReaderMode::Snp {
-                chromosome = ChrIdx(b'X');
+                chromosome = ChrIdx::X;
             }
             let coordinate: SNPCoord     = SNPCoord::try_new(chromosome, position, reference, alternate)?;
             if exclude_transitions && !coordinate.has_known_alleles() {
@@ -213,7 +213,7 @@ mod tests {
 
         assert!(positions.contains(&SNPCoord::new(14, 1_565_489, Allele::A, Allele::C)));
         assert!(positions.contains(&SNPCoord::new(15, 1_500_000, Allele::G, Allele::T)));
-        assert!(positions.contains(&SNPCoord::new(b'X',  17_000, Allele::G, Allele::A)));
+        assert!(positions.contains(&SNPCoord::new(ChrIdx::X,  17_000, Allele::G, Allele::A)));
         Ok(())
     }
 
@@ -233,7 +233,7 @@ mod tests {
 
         assert!(positions.contains(&SNPCoord::new(14, 1_565_489, Allele::A, Allele::C)));
         assert!(positions.contains(&SNPCoord::new(15, 1_500_000, Allele::G, Allele::T)));
-        assert!(positions.contains(&SNPCoord::new(b'X',  15_000, Allele::A, Allele::G)));
+        assert!(positions.contains(&SNPCoord::new(ChrIdx::X,  15_000, Allele::A, Allele::G)));
         Ok(())
     }
 
@@ -253,7 +253,7 @@ mod tests {
 
         assert!(positions.contains(&SNPCoord::new(14, 1_565_489, Allele::A, Allele::C)));
         assert!(positions.contains(&SNPCoord::new(15, 1_500_000, Allele::G, Allele::T)));
-        assert!(positions.contains(&SNPCoord::new(b'X',  18_000, Allele::A, Allele::C)));
+        assert!(positions.contains(&SNPCoord::new(ChrIdx::X,  18_000, Allele::A, Allele::C)));
         Ok(())
     }
 
@@ -275,7 +275,7 @@ mod tests {
 
         assert!(positions.contains(&SNPCoord::new(15,   60026, Allele::A, Allele::C)));
         assert!(positions.contains(&SNPCoord::new(16,   60057, Allele::G, Allele::T)));
-        assert!(positions.contains(&SNPCoord::new(b'X', 60083, Allele::G, Allele::A)));
+        assert!(positions.contains(&SNPCoord::new(ChrIdx::X, 60083, Allele::G, Allele::A)));
         Ok(())
     }
 
@@ -295,7 +295,7 @@ mod tests {
 
         assert!(positions.contains(&SNPCoord::new(1,    752_566, Allele::G, Allele::A)));
         assert!(positions.contains(&SNPCoord::new(16,   155_344, Allele::G, Allele::A)));
-        assert!(positions.contains(&SNPCoord::new(88, 2_779_345, Allele::C, Allele::T)));
+        assert!(positions.contains(&SNPCoord::new(ChrIdx::X, 2_779_345, Allele::C, Allele::T)));
         Ok(())
     }
 
@@ -0,0 +1,199 @@
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+
+use flate2::read::DeflateDecoder;
+
+use super::TabixError;
+
+const GZIP_MAGIC   : [u8; 2] = [0x1f, 0x8b];
+const HEADER_LEN   : usize   = 12; // Fixed length gzip header fields, up to XLEN.
+const FOOTER_LEN   : usize   = 8;  // CRC32 + ISIZE
+const BGZF_SUBFIELD: [u8; 2] = *b"BC";
+
+/// Single-threaded, seekable reader of BGZF-compressed files.
+///
+/// Contrarily to the parallel decompressor of `gzp`, this reader decompresses blocks one at a time, and may thus be
+/// repositionned at any virtual offset `(coffset << 16) | uoffset`, as found within tabix indices.
+/// # Fields:
+/// - `inner`       : underlying compressed data stream.
+/// - `block`       : decompressed contents of the current block.
+/// - `pos`         : position of the cursor within `block`.
+/// - `block_offset`: compressed offset of the current block.
+/// - `next_offset` : compressed offset of the following block.
+pub struct BgzfReader<R> {
+    inner       : R,
+    block       : Vec<u8>,
+    pos         : usize,
+    block_offset: u64,
+    next_offset : u64,
+}
+
+impl<R: Read + Seek> BgzfReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self{inner, block: Vec::new(), pos: 0, block_offset: 0, next_offset: 0}
+    }
+
+    /// Return the virtual offset of the cursor. When the current block has been entirely consumed, the returned
+    /// offset points to the start of the next block.
+    pub fn virtual_offset(&self) -> u64 {
+        match self.pos < self.block.len() {
+            true  => (self.block_offset << 16) | self.pos as u64,
+            false => self.next_offset << 16,
+        }
+    }
+
+    /// Reposition the cursor at the provided virtual offset.
+    /// # Errors
+    /// - `ReadBgzf` if the underlying stream cannot be repositionned, or if the target block cannot be read.
+    /// - `InvalidBgzfBlock` if the target block is not a valid BGZF block.
+    /// - `InvalidVirtualOffset` if the uncompressed offset overflows the length of the target block.
+    pub fn seek_virtual(&mut self, voffset: u64) -> Result<(), TabixError> {
+        let (coffset, uoffset) = (voffset >> 16, (voffset & 0xffff) as usize);
+        if coffset != self.block_offset || self.block.is_empty() {
+            self.inner.seek(SeekFrom::Start(coffset)).map_err(TabixError::ReadBgzf)?;
+            self.next_offset = coffset;
+            self.read_block()?;
+        }
+        if uoffset > self.block.len() {
+            return Err(TabixError::InvalidVirtualOffset(voffset))
+        }
+        self.pos = uoffset;
+        Ok(())
+    }
+
+    /// Read and decompress the block located at `self.next_offset`. Returns `false` if the end of the stream has been
+    /// reached.
+    fn read_block(&mut self) -> Result<bool, TabixError> {
+        use TabixError::{InvalidBgzfBlock, ReadBgzf};
+        self.block_offset = self.next_offset;
+        self.block.clear();
+        self.pos = 0;
+
+        // ---- Read the fixed-length header, while checking for a clean EOF.
+        let mut header = [0u8; HEADER_LEN];
+        let mut filled = 0;
+        while filled < HEADER_LEN {
+            match self.inner.read(&mut header[filled..]) {
+                Ok(0)  => break,
+                Ok(n)  => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(ReadBgzf(e))
+            }
+        }
+        match filled {
+            0          => return Ok(false),
+            HEADER_LEN => (),
+            _          => return Err(InvalidBgzfBlock(self.block_offset))
+        }
+        if header[..2] != GZIP_MAGIC {
+            return Err(InvalidBgzfBlock(self.block_offset))
+        }
+
+        // ---- Search for the 'BC' subfield within the extra field, to retrieve the total block size.
+        let xlen = usize::from(u16::from_le_bytes([header[10], header[11]]));
+        let mut extra = vec![0u8; xlen];
+        self.inner.read_exact(&mut extra).map_err(ReadBgzf)?;
+        let mut bsize = None;
+        let mut i = 0;
+        while i + 4 <= xlen {
+            let slen = usize::from(u16::from_le_bytes([extra[i+2], extra[i+3]]));
+            if extra[i..i+2] == BGZF_SUBFIELD && slen == 2 && i + 6 <= xlen {
+                bsize = Some(usize::from(u16::from_le_bytes([extra[i+4], extra[i+5]])) + 1);
+            }
+            i += 4 + slen;
+        }
+        let cdata_len = bsize
+            .and_then(|bsize| bsize.checked_sub(HEADER_LEN + xlen + FOOTER_LEN))
+            .ok_or(InvalidBgzfBlock(self.block_offset))?;
+
+        // ---- Decompress the block.
+        let mut cdata = vec![0u8; cdata_len + FOOTER_LEN];
+        self.inner.read_exact(&mut cdata).map_err(ReadBgzf)?;
+        DeflateDecoder::new(&cdata[..cdata_len]).read_to_end(&mut self.block).map_err(ReadBgzf)?;
+
+        let isize = u32::from_le_bytes([cdata[cdata_len+4], cdata[cdata_len+5], cdata[cdata_len+6], cdata[cdata_len+7]]);
+        if self.block.len() != isize as usize {
+            return Err(InvalidBgzfBlock(self.block_offset))
+        }
+        self.next_offset = self.block_offset + (HEADER_LEN + xlen + cdata.len()) as u64;
+        Ok(true)
+    }
+}
+
+impl<R: Read + Seek> Read for BgzfReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let mut available = self.fill_buf()?;
+            available.read(buf)?
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> BufRead for BgzfReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // ---- Skip empty blocks (e.g. the EOF marker block).
+        while self.pos >= self.block.len() {
+            if !self.read_block().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))? {
+                break
+            }
+        }
+        Ok(&self.block[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = usize::min(self.pos + amt, self.block.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read::compression::tests::bgzf_block;
+    use std::io::Cursor;
+    use anyhow::Result;
+
+    #[test]
+    fn read_sequential() -> Result<()> {
+        let mut data = bgzf_block(b"chr1\t10\n")?;
+        data.extend(bgzf_block(b"chr1\t20\n")?);
+        data.extend(bgzf_block(&[])?);
+
+        let mut reader = BgzfReader::new(Cursor::new(data));
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        assert_eq!(contents, "chr1\t10\nchr1\t20\n");
+        Ok(())
+    }
+
+    #[test]
+    fn seek_virtual() -> Result<()> {
+        let first = bgzf_block(b"chr1\t10\nchr1\t15\n")?;
+        let coffset = first.len() as u64;
+        let mut data = first;
+        data.extend(bgzf_block(b"chr1\t20\nchr1\t25\n")?);
+        data.extend(bgzf_block(&[])?);
+
+        let mut reader = BgzfReader::new(Cursor::new(data));
+        reader.seek_virtual((coffset << 16) | 8)?;
+        assert_eq!(reader.virtual_offset(), (coffset << 16) | 8);
+
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        assert_eq!(line, "chr1\t25\n");
+
+        // ---- Seeking within the first block.
+        reader.seek_virtual(8)?;
+        line.clear();
+        reader.read_line(&mut line)?;
+        assert_eq!(line, "chr1\t15\n");
+        assert_eq!(reader.virtual_offset(), coffset << 16);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_block() {
+        let mut reader = BgzfReader::new(Cursor::new(b"chr1\t10\n".to_vec()));
+        assert!(reader.seek_virtual(0).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TabixError {
+    #[error("Failed to open file {0}")]
+    OpenFile(String, #[source] std::io::Error),
+
+    #[error("Failed to read the contents of the index file")]
+    ReadIndex(#[source] std::io::Error),
+
+    #[error("Invalid index magic number. Expected either a tabix (.tbi) or a coordinate-sorted (.csi) index")]
+    InvalidMagic,
+
+    #[error("Index file is truncated or malformed")]
+    TruncatedIndex,
+
+    #[error("Index contains an invalid sequence name")]
+    InvalidSequenceName(#[source] std::str::Utf8Error),
+
+    #[error("Failed to read the contents of the BGZF-compressed file")]
+    ReadBgzf(#[source] std::io::Error),
+
+    #[error("Invalid BGZF block found at offset {0}. Is this file compressed with bgzip?")]
+    InvalidBgzfBlock(u64),
+
+    #[error("Invalid virtual offset: {0:#x}")]
+    InvalidVirtualOffset(u64),
+
+    #[error("Failed to retrieve the sequence name or position of the following record: {0}")]
+    InvalidRecord(String),
+}
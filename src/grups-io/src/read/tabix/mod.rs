@@ -0,0 +1,531 @@
+use std::{fmt::{self, Display, Formatter}, fs::File, io::{BufRead, BufReader, Read}, path::{Path, PathBuf}};
+
+use ahash::AHashMap;
+use located_error::LocatedError;
+use flate2::read::MultiGzDecoder;
+use anyhow::Result;
+use log::debug;
+
+mod error;
+pub use error::TabixError;
+
+mod bgzf;
+pub use bgzf::BgzfReader;
+
+const TBI_MAGIC        : &[u8; 4] = b"TBI\x01";
+const CSI_MAGIC        : &[u8; 4] = b"CSI\x01";
+const TBI_MIN_SHIFT    : u32      = 14;      // Tabix indices use fixed 16kb linear windows...
+const TBI_DEPTH        : u32      = 5;       // ... and a fixed 6-level binning scheme.
+const TBI_ZERO_BASED   : i32      = 0x10000; // Format flag, indicating 0-based, half-open coordinates.
+const INDEX_EXTENSIONS : [&str; 2] = ["tbi", "csi"];
+
+/// Contiguous span of a BGZF-compressed file, delimited by two virtual offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk {
+    pub start: u64,
+    pub end  : u64,
+}
+
+/// Genomic interval, targeted by a tabix query.
+/// # Fields:
+/// - `name` : sequence name, as found within the indexed file.
+/// - `start`: 0-based start coordinate (inclusive).
+/// - `end`  : 0-based end coordinate (exclusive).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub name : String,
+    pub start: u64,
+    pub end  : u64,
+}
+
+impl Region {
+    #[must_use]
+    pub fn new(name: &str, start: u64, end: u64) -> Self {
+        Self{name: name.to_string(), start, end}
+    }
+
+    /// Instantiate a region spanning an entire sequence.
+    #[must_use]
+    pub fn whole(name: &str) -> Self {
+        Self::new(name, 0, u64::MAX)
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.end {
+            u64::MAX => write!(f, "{}", self.name),
+            end      => write!(f, "{}:{}-{}", self.name, self.start + 1, end),
+        }
+    }
+}
+
+/// Column layout of the indexed file, as defined within the tabix header.
+/// # Fields:
+/// - `format` : tabix format flags. Only the `TBI_ZERO_BASED` flag is relevant for generic files.
+/// - `col_seq`: 1-based column index of the sequence name.
+/// - `col_beg`: 1-based column index of the start coordinate.
+/// - `col_end`: 1-based column index of the end coordinate (0 if the file only carries a single coordinate).
+/// - `meta`   : leading character of header and comment lines.
+#[derive(Debug, Clone, Copy)]
+struct TabixConfig {
+    format : i32,
+    col_seq: usize,
+    col_beg: usize,
+    col_end: usize,
+    meta   : u8,
+}
+
+impl TabixConfig {
+    /// Retrieve the sequence name, and 0-based, half-open coordinates of a record.
+    fn locate<'a>(&self, record: &'a str) -> Option<(&'a str, u64, u64)> {
+        let ncols  = self.col_seq.max(self.col_beg).max(self.col_end);
+        let fields = record.splitn(ncols + 1, '\t').collect::<Vec<&str>>();
+        let name   = *fields.get(self.col_seq.checked_sub(1)?)?;
+        let mut start: u64 = fields.get(self.col_beg.checked_sub(1)?)?.parse().ok()?;
+        if self.format & TBI_ZERO_BASED == 0 {
+            start = start.checked_sub(1)?;
+        }
+        let end = match self.col_end != 0 && self.col_end != self.col_beg {
+            true  => fields.get(self.col_end - 1)?.parse().ok()?,
+            false => start + 1,
+        };
+        Some((name, start, end))
+    }
+}
+
+/// Binning and linear index of a single sequence.
+#[derive(Debug, Default)]
+struct ReferenceIndex {
+    bins  : AHashMap<u32, Vec<Chunk>>,
+    linear: Vec<u64>,
+}
+
+/// Tabix (`.tbi`) or coordinate-sorted (`.csi`) index of a BGZF-compressed, tab-delimited file.
+/// # Fields:
+/// - `min_shift` : bit-width of the smallest bins.
+/// - `depth`     : number of levels within the binning scheme.
+/// - `config`    : column layout of the indexed file.
+/// - `names`     : sequence names, ordered as in the indexed file.
+/// - `references`: per-sequence binning index, ordered as `names`.
+#[derive(Debug)]
+pub struct TabixIndex {
+    min_shift : u32,
+    depth     : u32,
+    config    : TabixConfig,
+    names     : Vec<String>,
+    references: Vec<ReferenceIndex>,
+}
+
+impl TabixIndex {
+    /// Search for a `.tbi` or `.csi` index file, located alongside the provided file.
+    #[must_use]
+    pub fn find(path: &Path) -> Option<PathBuf> {
+        INDEX_EXTENSIONS.iter()
+            .map(|ext| {
+                let mut index_path = path.as_os_str().to_owned();
+                index_path.push(format!(".{ext}"));
+                PathBuf::from(index_path)
+            })
+            .find(|index_path| index_path.is_file())
+    }
+
+    /// Open and parse a `.tbi` or `.csi` index file.
+    /// # Errors
+    /// - `OpenFile` if `path` targets an invalid location (`FileNotFound` or `PermissionDenied`)
+    /// - `ReadIndex` if the file cannot be decompressed.
+    /// - if the contents of the index cannot be parsed.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        use TabixError::{OpenFile, ReadIndex};
+        let loc_msg = || format!("While attempting to parse index file {}", path.display());
+        let file = File::open(path).map_err(|e| OpenFile(path.display().to_string(), e)).with_loc(loc_msg)?;
+        let mut data = Vec::new();
+        MultiGzDecoder::new(BufReader::new(file)).read_to_end(&mut data).map_err(ReadIndex).with_loc(loc_msg)?;
+        Self::from_bytes(&data).with_loc(loc_msg)
+    }
+
+    /// Parse the decompressed contents of a `.tbi` or `.csi` index file.
+    /// # Errors
+    /// - `InvalidMagic` if the data does not begin with a tabix or CSI magic number.
+    /// - `TruncatedIndex` if the data ends prematurely, or contains negative counts.
+    /// - `InvalidSequenceName` if any sequence name is not valid UTF8.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, TabixError> {
+        let mut cursor = IndexCursor{data, pos: 0};
+        let magic = cursor.bytes(4)?;
+        let is_csi = match magic {
+            m if m == TBI_MAGIC => false,
+            m if m == CSI_MAGIC => true,
+            _ => return Err(TabixError::InvalidMagic)
+        };
+
+        // ---- Parse the binning scheme and tabix header.
+        let (min_shift, depth, config, names, n_ref) = if is_csi {
+            let min_shift = cursor.u32()?;
+            let depth     = cursor.u32()?;
+            let l_aux     = cursor.count()?;
+            let mut aux   = IndexCursor{data: cursor.bytes(l_aux)?, pos: 0};
+            let (config, names) = aux.tabix_header()?;
+            (min_shift, depth, config, names, cursor.count()?)
+        } else {
+            let n_ref = cursor.count()?;
+            let (config, names) = cursor.tabix_header()?;
+            (TBI_MIN_SHIFT, TBI_DEPTH, config, names, n_ref)
+        };
+
+        // ---- Parse the binning and linear index of each sequence.
+        let mut references = Vec::with_capacity(n_ref);
+        for _ in 0..n_ref {
+            let mut reference = ReferenceIndex::default();
+            for _ in 0..cursor.count()? {
+                let bin = cursor.u32()?;
+                if is_csi {
+                    cursor.u64()?; // loffset
+                }
+                let chunks = (0..cursor.count()?)
+                    .map(|_| Ok(Chunk{start: cursor.u64()?, end: cursor.u64()?}))
+                    .collect::<Result<Vec<Chunk>, TabixError>>()?;
+                reference.bins.insert(bin, chunks);
+            }
+            if !is_csi {
+                reference.linear = (0..cursor.count()?).map(|_| cursor.u64()).collect::<Result<Vec<u64>, TabixError>>()?;
+            }
+            references.push(reference);
+        }
+
+        if names.len() != references.len() {
+            return Err(TabixError::TruncatedIndex)
+        }
+        debug!("Tabix index sequences: {names:?}");
+        Ok(Self{min_shift, depth, config, names, references})
+    }
+
+    /// Sequence names, ordered as within the indexed file.
+    #[must_use]
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Retrieve the sorted, non-overlapping set of chunks which may contain records overlapping the provided region.
+    /// Returns an empty vector if the sequence name of `region` is absent from the index.
+    #[must_use]
+    pub fn query(&self, region: &Region) -> Vec<Chunk> {
+        let Some(tid) = self.names.iter().position(|name| name == &region.name) else {
+            return Vec::new()
+        };
+        let reference = &self.references[tid];
+
+        // ---- Use the linear index to skip chunks which end before the first record of the region.
+        let window     = usize::try_from(region.start >> self.min_shift).unwrap_or(usize::MAX);
+        let min_offset = reference.linear.get(window).or(reference.linear.last()).copied().unwrap_or(0);
+
+        let mut chunks = reg2bins(region.start, region.end, self.min_shift, self.depth).into_iter()
+            .filter_map(|bin| reference.bins.get(&bin))
+            .flatten()
+            .filter(|chunk| chunk.end > min_offset)
+            .copied()
+            .collect::<Vec<Chunk>>();
+        chunks.sort_unstable_by_key(|chunk| chunk.start);
+
+        // ---- Merge overlapping chunks.
+        let mut merged: Vec<Chunk> = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            match merged.last_mut() {
+                Some(last) if chunk.start <= last.end => last.end = last.end.max(chunk.end),
+                _ => merged.push(chunk),
+            }
+        }
+        merged
+    }
+}
+
+/// Compute the list of bins that may overlap with the 0-based, half-open interval `[beg, end)`
+/// (see: `reg2bins()` within the SAM/BAM format specification).
+fn reg2bins(beg: u64, end: u64, min_shift: u32, depth: u32) -> Vec<u32> {
+    let max_end = 1u64 << (min_shift + 3 * depth);
+    let end = end.min(max_end);
+    if beg >= end {
+        return Vec::new()
+    }
+    let mut bins   = Vec::new();
+    let mut offset = 0u64;
+    for level in 0..=depth {
+        let shift = min_shift + 3 * (depth - level);
+        let (first, last) = (offset + (beg >> shift), offset + ((end - 1) >> shift));
+        bins.extend((first..=last).filter_map(|bin| u32::try_from(bin).ok()));
+        offset += 1 << (3 * level);
+    }
+    bins
+}
+
+/// Little-endian reader over the decompressed contents of an index file.
+struct IndexCursor<'a> {
+    data: &'a [u8],
+    pos : usize,
+}
+
+impl<'a> IndexCursor<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], TabixError> {
+        let bytes = self.data.get(self.pos..self.pos + n).ok_or(TabixError::TruncatedIndex)?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32, TabixError> {
+        let b = self.bytes(4)?;
+        Ok(i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, TabixError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, TabixError> {
+        let b = self.bytes(8)?;
+        Ok(u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+    }
+
+    /// Read a signed 32-bit count or length, which is expected to be positive.
+    fn count(&mut self) -> Result<usize, TabixError> {
+        usize::try_from(self.i32()?).map_err(|_| TabixError::TruncatedIndex)
+    }
+
+    /// Parse the tabix header, i.e. the column layout and the concatenated, NUL-terminated sequence names.
+    fn tabix_header(&mut self) -> Result<(TabixConfig, Vec<String>), TabixError> {
+        let format  = self.i32()?;
+        let col_seq = self.count()?;
+        let col_beg = self.count()?;
+        let col_end = self.count()?;
+        let meta    = u8::try_from(self.i32()?).unwrap_or(b'#');
+        let _skip   = self.i32()?;
+        let l_nm    = self.count()?;
+        let names   = self.bytes(l_nm)?
+            .split(|byte| *byte == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| std::str::from_utf8(name).map(ToString::to_string).map_err(TabixError::InvalidSequenceName))
+            .collect::<Result<Vec<String>, TabixError>>()?;
+        Ok((TabixConfig{format, col_seq, col_beg, col_end, meta}, names))
+    }
+}
+
+/// Random-access reader of BGZF-compressed, tabix-indexed files.
+pub struct TabixReader {
+    index : TabixIndex,
+    reader: BgzfReader<BufReader<File>>,
+}
+
+impl TabixReader {
+    /// Open a BGZF-compressed file, along with its index.
+    /// # Errors
+    /// - `OpenFile` if `path` targets an invalid location (`FileNotFound` or `PermissionDenied`)
+    /// - if the index file cannot be parsed.
+    pub fn new(path: &Path, index_path: &Path) -> Result<Self> {
+        let index = TabixIndex::from_path(index_path)?;
+        let file  = File::open(path)
+            .map_err(|e| TabixError::OpenFile(path.display().to_string(), e))
+            .with_loc(|| format!("While attempting to open indexed file {}", path.display()))?;
+        Ok(Self{index, reader: BgzfReader::new(BufReader::new(file))})
+    }
+
+    #[must_use]
+    pub fn index(&self) -> &TabixIndex {
+        &self.index
+    }
+
+    /// Iterate over the records overlapping the provided region.
+    pub fn fetch(&mut self, region: &Region) -> TabixRecords<'_> {
+        let chunks = self.index.query(region);
+        TabixRecords{
+            config   : self.index.config,
+            reader   : &mut self.reader,
+            region   : region.clone(),
+            chunks,
+            current  : 0,
+            seek     : true,
+            done     : false,
+            buf      : String::new(),
+        }
+    }
+}
+
+/// Iterator over the records of a tabix-indexed file overlapping a given region.
+/// # Fields:
+/// - `chunks` : sorted, non-overlapping chunks which may contain overlapping records.
+/// - `current`: index of the chunk currently being read.
+/// - `seek`   : whether the reader should be repositionned at the start of the current chunk.
+/// - `done`   : set to `true` once a record located past the end of the region has been found.
+pub struct TabixRecords<'a> {
+    config : TabixConfig,
+    reader : &'a mut BgzfReader<BufReader<File>>,
+    region : Region,
+    chunks : Vec<Chunk>,
+    current: usize,
+    seek   : bool,
+    done   : bool,
+    buf    : String,
+}
+
+impl TabixRecords<'_> {
+    fn next_record(&mut self) -> Result<Option<String>, TabixError> {
+        while !self.done {
+            let Some(chunk) = self.chunks.get(self.current) else {
+                return Ok(None)
+            };
+            if self.seek {
+                self.reader.seek_virtual(chunk.start)?;
+                self.seek = false;
+            }
+
+            // ---- Move on to the next chunk if we've reached the end of this one.
+            self.reader.fill_buf().map_err(TabixError::ReadBgzf)?;
+            if self.reader.virtual_offset() >= chunk.end {
+                self.current += 1;
+                self.seek = true;
+                continue
+            }
+
+            self.buf.clear();
+            if self.reader.read_line(&mut self.buf).map_err(TabixError::ReadBgzf)? == 0 {
+                self.current += 1;
+                self.seek = true;
+                continue
+            }
+
+            let record = self.buf.trim_end_matches(['\n', '\r']);
+            if record.is_empty() || record.as_bytes()[0] == self.config.meta {
+                continue
+            }
+            let (name, start, end) = self.config.locate(record).ok_or_else(|| TabixError::InvalidRecord(record.to_string()))?;
+            if name != self.region.name || end <= self.region.start {
+                continue
+            }
+            if start >= self.region.end {
+                self.done = true;
+                break
+            }
+            return Ok(Some(record.to_string()))
+        }
+        Ok(None)
+    }
+}
+
+impl Iterator for TabixRecords<'_> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_record() {
+            Ok(record) => record.map(Ok),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e).with_loc(|| format!("While fetching records of region {}", self.region)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read::compression::tests::bgzf_block;
+    use std::io::Write;
+
+    const RECORDS: [(&str, u64); 6] = [("21", 100), ("21", 200), ("21", 300), ("22", 100), ("22", 16_500), ("22", 40_000)];
+
+    /// Write each record within its own BGZF block, and index the file using a single root bin per sequence.
+    fn write_indexed_pileup(dir: &Path) -> Result<(PathBuf, PathBuf)> {
+        let mut data    = Vec::new();
+        let mut offsets = Vec::new();
+        for (chr, pos) in RECORDS {
+            offsets.push((data.len() as u64) << 16);
+            data.extend(bgzf_block(format!("{chr}\t{pos}\tA\t1\t.\tJ\n").as_bytes())?);
+        }
+        offsets.push((data.len() as u64) << 16);
+        data.extend(bgzf_block(&[])?);
+
+        let mut index = TBI_MAGIC.to_vec();
+        for value in [2, 0, 1, 2, 0, i32::from(b'#'), 0, 6] {
+            index.extend(i32::to_le_bytes(value));
+        }
+        index.extend(b"21\x0022\x00");
+        for (first, last) in [(0, 3), (3, 6)] {
+            index.extend(1i32.to_le_bytes());                  // n_bin
+            index.extend(0u32.to_le_bytes());                  // bin
+            index.extend(1i32.to_le_bytes());                  // n_chunk
+            index.extend(offsets[first].to_le_bytes());
+            index.extend(offsets[last].to_le_bytes());
+            index.extend(1i32.to_le_bytes());                  // n_intv
+            index.extend(offsets[first].to_le_bytes());
+        }
+
+        let pileup = dir.join("test.pileup.gz");
+        File::create(&pileup)?.write_all(&data)?;
+        let tbi = dir.join("test.pileup.gz.tbi");
+        File::create(&tbi)?.write_all(&bgzf_block(&index)?)?;
+        Ok((pileup, tbi))
+    }
+
+    fn positions(reader: &mut TabixReader, region: &Region) -> Result<Vec<String>> {
+        reader.fetch(region)
+            .map(|record| Ok(record?.split('\t').take(2).collect::<Vec<&str>>().join(":")))
+            .collect()
+    }
+
+    #[test]
+    fn compute_bins() {
+        assert_eq!(reg2bins(0, 1, TBI_MIN_SHIFT, TBI_DEPTH), vec![0, 1, 9, 73, 585, 4681]);
+        assert_eq!(reg2bins(16_384, 16_385, TBI_MIN_SHIFT, TBI_DEPTH), vec![0, 1, 9, 73, 585, 4682]);
+        assert!(reg2bins(10, 10, TBI_MIN_SHIFT, TBI_DEPTH).is_empty());
+        assert_eq!(reg2bins(0, u64::MAX, TBI_MIN_SHIFT, TBI_DEPTH).len(), 37_449);
+    }
+
+    #[test]
+    fn find_index() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let (pileup, tbi) = write_indexed_pileup(tmpdir.path())?;
+        assert_eq!(TabixIndex::find(&pileup), Some(tbi));
+        assert_eq!(TabixIndex::find(&tmpdir.path().join("missing.pileup.gz")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_index() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let (_, tbi) = write_indexed_pileup(tmpdir.path())?;
+        let index = TabixIndex::from_path(&tbi)?;
+        assert_eq!(index.names(), ["21", "22"]);
+        assert_eq!(index.query(&Region::whole("22")).len(), 1);
+        assert!(index.query(&Region::whole("X")).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_index() {
+        assert!(matches!(TabixIndex::from_bytes(b"BAI\x01"), Err(TabixError::InvalidMagic)));
+        assert!(matches!(TabixIndex::from_bytes(b"TBI\x01\x02\x00"), Err(TabixError::TruncatedIndex)));
+    }
+
+    #[test]
+    fn fetch_regions() -> Result<()> {
+        let tmpdir = tempfile::tempdir()?;
+        let (pileup, tbi) = write_indexed_pileup(tmpdir.path())?;
+        let mut reader = TabixReader::new(&pileup, &tbi)?;
+
+        assert_eq!(positions(&mut reader, &Region::whole("22"))?, ["22:100", "22:16500", "22:40000"]);
+        assert_eq!(positions(&mut reader, &Region::new("21", 199, 300))?, ["21:200", "21:300"]);
+        assert_eq!(positions(&mut reader, &Region::new("22", 16_000, 20_000))?, ["22:16500"]);
+        assert!(positions(&mut reader, &Region::new("21", 300, 1000))?.is_empty());
+        assert!(positions(&mut reader, &Region::whole("X"))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn locate_records() {
+        let config = TabixConfig{format: 0, col_seq: 1, col_beg: 2, col_end: 0, meta: b'#'};
+        assert_eq!(config.locate("22\t100\tA\t1\t.\tJ"), Some(("22", 99, 100)));
+        assert_eq!(config.locate("22\t0\tA"), None);
+        assert_eq!(config.locate("22"), None);
+
+        let config = TabixConfig{format: TBI_ZERO_BASED, col_seq: 1, col_beg: 2, col_end: 3, meta: b'#'};
+        assert_eq!(config.locate("22\t99\t150\tfoo"), Some(("22", 99, 150)));
+    }
+}
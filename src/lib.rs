@@ -4,7 +4,8 @@ extern crate logger;
 use std::fs::File;
 
 use parser::{Cli, Commands::{Cite, FST, FromYaml, PedigreeSims, PwdFromStdin}};
-use genome::Genome;
+use genome::{Genome, SpeciesProfile, species::SexChromosome};
+use grups_io::read::SampleSheet;
 
 #[macro_use]
 extern crate log;
@@ -54,25 +55,35 @@ pub fn cite() {
 
 pub fn run(cli: &Cli) -> Result<()> {
     // ----------------------------- Set seed (randomly assigned by parser-rs if none was provided.)
-    if let PedigreeSims{ref ped, common: _, pwd:_ } = cli.commands {
-        fastrand::seed(ped.seed);
+    if let PedigreeSims{ref pwd, common: _, ped: _} | PwdFromStdin{ref pwd, common: _} = cli.commands {
+        fastrand::seed(pwd.seed);
         trace!("Seed: {}", fastrand::get_seed());
     }
     // ----------------------------- Initialize genome.
     let genome = match cli.commands {
         PedigreeSims { ref common, pwd: _, ped: _} | PwdFromStdin { ref common, pwd: _ } => {
+            let species = match common.genome_build {
+                Some(build) => SpeciesProfile::from_build(&build.to_string())?,
+                None        => SpeciesProfile::new(common.species.as_deref().unwrap_or(SpeciesProfile::DEFAULT))?,
+            };
             info!("Indexing reference genome...");
-            let mut genome = match &common.genome {
-                Some(file) => Genome::from_fasta_index(file)?,
-                None       => Genome::default(),
+            let genome = match &common.genome {
+                Some(file) => {
+                    let genome = Genome::from_fasta_index(file)?;
+                    if common.genome_build.is_some() {
+                        species.check_lengths(&genome);
+                    }
+                    genome
+                },
+                None => species.genome(),
             };
-            // ---- Split genome into autosome and X-chromosome chunks.            
-            let x_chromosome = genome.pop_xchr();
-
-            if common.x_chromosome_mode {
-                genome = x_chromosome.ok_or_else(||anyhow!("Missing Xchromosome"))?;
-            }
-            Some(genome)
+            // ---- Split genome into autosome and sex-chromosome chunks.
+            let (autosomes, sex_chromosome) = species.split(genome);
+            let genome = match common.x_chromosome_mode {
+                true  => sex_chromosome.ok_or_else(|| anyhow!("Missing sex-chromosome ({})", species.sex_chromosome().chromosome))?,
+                false => autosomes,
+            };
+            Some((genome, species.sex_chromosome()))
         },
         _ => None
     };
@@ -82,30 +93,44 @@ pub fn run(cli: &Cli) -> Result<()> {
 
     Ok(())
 }
-pub fn _run(cli: &Cli, genome: Option<Genome>) -> Result<()> {
+pub fn _run(cli: &Cli, genome: Option<(Genome, SexChromosome)>) -> Result<()> {
     match &cli.commands {
         PedigreeSims {common, pwd, ped} => {
-            // ----------------------------- Parse Requested_samples
-            let requested_samples: Vec<usize> = parser::parse_user_ranges(&pwd.samples, "samples")?;
-            let genome = genome.ok_or_else(|| anyhow!("Error: Missing genome"))?; // @TODO better handling
+            // ----------------------------- Parse Requested_samples (either from the command line or a sample sheet)
+            let sample_sheet = pwd.sample_sheet.as_deref().map(SampleSheet::new).transpose()?;
+            let requested_samples: Vec<usize> = match &sample_sheet {
+                Some(sheet) => sheet.columns(),
+                None        => pwd.requested_samples()?,
+            };
+            let (genome, sex_chromosome) = genome.ok_or_else(|| anyhow!("Error: Missing genome"))?; // @TODO better handling
 
-            // ----------------------------- Run PWD_from_stdin.
-            let (mut comparisons, output_files) = pwd_from_stdin::run(common, pwd, &requested_samples, &genome)?;
+            // ----------------------------- Run PWD_from_stdin (or restore previous observations)
+            let (mut comparisons, output_files) = match &common.observations {
+                Some(observations) => pwd_from_stdin::load(common, pwd, observations)?,
+                None               => pwd_from_stdin::run(common, pwd, &requested_samples, sample_sheet.as_ref(), &genome, &sex_chromosome)?,
+            };
             comparisons.write_pwd_results(!pwd.no_print_blocks, &output_files)?;
 
             // ----------------------------- Run Pedigree-sims
-            pedigree_sims::run(common, ped, &requested_samples, &mut comparisons)?;
+            pedigree_sims::run(common, ped, &requested_samples, &mut comparisons, sample_sheet.as_ref(), &genome, &sex_chromosome)?;
 
         },
         
         PwdFromStdin {common, pwd} => {
-            // ----------------------------- Parse Requested_samples
-            let requested_samples: Vec<usize> = parser::parse_user_ranges(&pwd.samples, "samples")?;
+            // ----------------------------- Parse Requested_samples (either from the command line or a sample sheet)
+            let sample_sheet = pwd.sample_sheet.as_deref().map(SampleSheet::new).transpose()?;
+            let requested_samples: Vec<usize> = match &sample_sheet {
+                Some(sheet) => sheet.columns(),
+                None        => pwd.requested_samples()?,
+            };
             // ----------------------------- Initialize genome.
-            let genome = genome.ok_or_else(|| anyhow!("Error: Missing genome"))?; // @TODO better handling
+            let (genome, sex_chromosome) = genome.ok_or_else(|| anyhow!("Error: Missing genome"))?; // @TODO better handling
 
-            // ----------------------------- Run PWD_from_stdin.
-            let (comparisons, output_files) = pwd_from_stdin::run(common, pwd, &requested_samples, &genome)?;
+            // ----------------------------- Run PWD_from_stdin (or restore previous observations)
+            let (comparisons, output_files) = match &common.observations {
+                Some(observations) => pwd_from_stdin::load(common, pwd, observations)?,
+                None               => pwd_from_stdin::run(common, pwd, &requested_samples, sample_sheet.as_ref(), &genome, &sex_chromosome)?,
+            };
             if ! pwd.filter_sites {
                 comparisons.write_pwd_results(!pwd.no_print_blocks, &output_files)?;
             }
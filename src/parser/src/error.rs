@@ -9,10 +9,19 @@ pub enum ParserError{
     #[error("{0}")]
     ParseRange(&'static str),
 
-    #[error("--min-depth must be greater than 0")]
+    #[error("--min-depth and --max-depth must be greater than 0")]
     InsufficientDepthError,
 
-    #[error("Neither --pileup, nor the stdin buffer are being sollicited.")]
+    #[error("--sampled-reads must be greater than 0")]
+    InvalidSampledReads,
+
+    #[error("--snps-per-block must be greater than 0")]
+    InvalidSnpsPerBlock,
+
+    #[error("Individual {0} cannot be requested through both --samples and --versus")]
+    OverlappingGroups(usize),
+
+    #[error("Neither --pileup, --merge-pileups, --bam, --bam-list, --eigenstrat, --plink, --gl-vcf, nor the stdin buffer are being sollicited.")]
     MissingPileupInput,
 
     #[error("{0} {1} does not exist")]
@@ -94,7 +94,32 @@ impl Cli{
     ///   or does not carry read permissions
     /// - Sends an unrecoverable error if: `serde_yaml` fails to parse the provided file to `Self`.
     pub fn deserialize(yaml: PathBuf) -> Result<Self, Box<dyn Error>> {
-        Ok(serde_yaml::from_reader(File::open(yaml)?)?)
+        let mut config: serde_yaml::Value = serde_yaml::from_reader(File::open(yaml)?)?;
+        Self::migrate_relocated_args(&mut config);
+        Ok(serde_yaml::from_value(config)?)
+    }
+
+    /// Move arguments that were relocated to another argument group back to their current location, so that `.yaml`
+    /// files generated by previous versions of grups-rs keep their meaning. Arguments found at their current location
+    /// take precedence.
+    /// - `ped.seed`    -> `pwd.seed`
+    /// - `ped.threads` -> `common.threads`
+    fn migrate_relocated_args(config: &mut serde_yaml::Value) {
+        const RELOCATED: [(&str, &str); 2] = [("seed", "pwd"), ("threads", "common")];
+        let Some(args) = config.get_mut("commands").and_then(|cmd| cmd.get_mut("PedigreeSims")).and_then(serde_yaml::Value::as_mapping_mut) else {
+            return
+        };
+        for (arg, group) in RELOCATED {
+            let arg = serde_yaml::Value::from(arg);
+            let Some(value) = args.get_mut(&"ped".into()).and_then(serde_yaml::Value::as_mapping_mut).and_then(|ped| ped.remove(&arg)) else {
+                continue
+            };
+            if let Some(group) = args.get_mut(&group.into()).and_then(serde_yaml::Value::as_mapping_mut) {
+                if !group.contains_key(&arg) {
+                    group.insert(arg, value);
+                }
+            }
+        }
     }
 }
 
@@ -146,11 +171,30 @@ pub struct Common {
 
     /// Fasta indexed reference genome.
     /// 
-    /// By default, grups-rs will use GRCh37 as a reference genome. Use this argument if you wish to specify an alternative reference.
+    /// By default, grups-rs will use the default chromosome lengths of the --species profile (i.e. GRCh37 for humans, or
+    /// the build selected through --genome-build). Use this argument if you wish to specify an alternative reference.
     /// Note that a '.fasta.fai' genome index file must be present at the same directory.
     #[clap(short, long, required(false))]
     pub genome: Option<String>,
 
+    /// Human reference genome build.
+    /// 
+    /// Use the built-in chromosome lengths of the selected build when no --genome is provided. Pileup positions and
+    /// recombination maps are checked against these lengths. When a --genome is provided, grups-rs warns the user if its
+    /// chromosome lengths do not match the selected build. Implies '--species human'. Defaults to grch37.
+    #[clap(long, arg_enum, required(false), conflicts_with("species"))]
+    #[serde(default)]
+    pub genome_build: Option<GenomeBuild>,
+
+    /// Species profile, declaring the set of autosomes, the sex-determination system (XY or ZW) and the default
+    /// chromosome lengths of the studied species.
+    /// 
+    /// Either the name of a built-in preset (human, cattle, dog, horse), or a path leading to a TOML species profile.
+    /// Contigs which are not declared within the profile are excluded from the analysis. Defaults to 'human'.
+    #[clap(long, required(false))]
+    #[serde(default)]
+    pub species: Option<String>,
+
     /// Provide with a list of SNP coordinates to target within the pileup.
     /// 
     /// Pileup positions which are not found within the provided --targets file will be exluded from comparison.  
@@ -174,16 +218,111 @@ pub struct Common {
     #[clap(long, required(false))]
     pub pileup: Option<String>,
 
+    /// Input BAM alignment file(s) containing samples to investigate.
+    /// 
+    /// When specified, grups-rs will directly build a pileup from the provided alignment files, instead of reading
+    /// one through '--pileup' or the standard input. Each alignment file is treated as a single individual, following
+    /// the order in which files were provided (i.e. '--samples 0' targets the first file). Files must be sorted by
+    /// coordinate, and share the same reference sequence dictionary.
+    /// 
+    /// Reads are filtered according to '--min-mapq' and '--keep-duplicates', while bases are filtered according to 
+    /// '--min-qual'. Unmapped, secondary and QC-failed alignments are always discarded.
+    #[clap(long, multiple_values(true), conflicts_with("pileup"), parse(try_from_os_str=valid_input_file))]
+    #[serde(default)]
+    pub bam: Vec<PathBuf>,
+
+    /// Text file listing input BAM alignment files, one per line.
+    /// 
+    /// Empty lines and lines starting with '#' are ignored. Listed files are appended after those provided through
+    /// '--bam'. See the '--bam' argument for additional information.
+    #[clap(long, conflicts_with("pileup"), parse(try_from_os_str=valid_input_file))]
+    pub bam_list: Option<PathBuf>,
+
+    /// Input single-sample pileup files, to be merged on the fly.
+    /// 
+    /// When specified, grups-rs will stream-merge the provided pileup files into a single multi-sample pileup,
+    /// instead of reading one through '--pileup' or the standard input. Each file must contain a single individual,
+    /// and is treated as such, following the order in which files were provided (i.e. '--samples 0' targets the first
+    /// file). Individuals lacking an entry at a given position are considered as having a depth of zero.
+    /// 
+    /// Files must be sorted by coordinate, following the chromosome order of the reference genome (i.e. the order of
+    /// the '--genome' index, or the autosomes, then the sex-chromosome, of the species profile when no reference is
+    /// provided). Files may lack some of the chromosomes. Both plain and (b)gzip-compressed files are accepted.
+    #[clap(long, multiple_values(true), conflicts_with_all(&["pileup", "bam", "bam-list", "eigenstrat", "plink", "gl-vcf"]), parse(try_from_os_str=valid_input_file))]
+    #[serde(default)]
+    pub merge_pileups: Vec<PathBuf>,
+
+    /// Input EIGENSTRAT pseudo-haploid genotype dataset, provided as a file prefix.
+    /// 
+    /// When specified, grups-rs will directly compare the genotype calls of '<prefix>.geno', using SNP definitions
+    /// found within '<prefix>.snp', and individuals found within '<prefix>.ind'. Both plain text and packed '.geno'
+    /// files are accepted. Individuals follow the order of the '.ind' file (i.e. '--samples 0' targets the first
+    /// individual), and are named after their identifier, unless '--sample-names' is provided.
+    /// 
+    /// Each called SNP is treated as a single observation, with a base quality of 40. Heterozygous calls are
+    /// considered as missing. Note that self-comparisons are thus not possible with this kind of input.
+    #[clap(long, conflicts_with_all(&["pileup", "bam", "bam-list", "plink"]))]
+    pub eigenstrat: Option<PathBuf>,
+
+    /// Input PLINK pseudo-haploid genotype dataset, provided as a file prefix.
+    /// 
+    /// Similar to '--eigenstrat', using the SNP-major binary '<prefix>.bed', '<prefix>.bim' and '<prefix>.fam' files.
+    /// The A2 allele of the '.bim' file is treated as the reference allele.
+    #[clap(long, conflicts_with_all(&["pileup", "bam", "bam-list"]))]
+    pub plink: Option<PathBuf>,
+
+    /// Input VCF file containing per-sample genotype likelihoods or posteriors.
+    /// 
+    /// When specified, grups-rs will compute the expected pairwise mismatch rate between individuals from their
+    /// genotype probabilities, instead of sampling observed alleles. Probabilities are extracted from the 'GP', 'PL'
+    /// or 'GL' FORMAT field (see '--gl-field'), and normalized assuming a flat genotype prior. Individuals follow the
+    /// order of the VCF header (i.e. '--samples 0' targets the first sample), and are named after their identifier,
+    /// unless '--sample-names' is provided.
+    /// 
+    /// Only bi-allelic SNPs are considered. Missing or uninformative genotypes are treated as missing data.
+    /// Note that '--min-depth' and '--min-qual' are not applied when using this kind of input. Both plain and
+    /// (b)gzip-compressed files are accepted.
+    #[clap(long, conflicts_with_all(&["pileup", "bam", "bam-list", "eigenstrat", "plink"]), parse(try_from_os_str=valid_input_file))]
+    pub gl_vcf: Option<PathBuf>,
+
+    /// Load previously observed comparisons from a binary snapshot, instead of parsing a pileup.
+    /// 
+    /// Snapshots are written when using '--write-observations', and contain the overlapping SNPs, pairwise
+    /// differences, phred scores and jackknife blocks of each pair of individuals. This allows re-running pedigree
+    /// simulations with different parameters, without re-reading the input pileup. Note that every site filtration
+    /// and comparison parameter (e.g. '--samples', '--min-depth', '--targets', '--blocksize') is then inherited from
+    /// the run which generated the snapshot.
+    #[clap(long, conflicts_with_all(&["pileup", "merge-pileups", "bam", "bam-list", "eigenstrat", "plink", "gl-vcf"]), parse(try_from_os_str=valid_input_file))]
+    pub observations: Option<PathBuf>,
+
     /// Restrict comparison to a given set of chromosomes.
     /// 
     /// Argument may accept slices (inclusive) such as '--chr 9-11' and/or discrete integers such as '--chr 1 4 13'.{n}
     /// Example:{n}
     ///   specifying          : '--chr 9-11 13 19-22 '{n}
     ///   ...will be parsed as: [9, 10, 11, 13, 19, 20, 21, 22]
+    /// Non-numbered contigs may be requested using their name (e.g. '--chr 1-22 X'), provided they are listed within the
+    /// '--genome' fasta index.
     /// 
     #[clap(short, long, multiple_values(true))]
     pub chr: Option<Vec<String>>,
 
+    /// Exclude positions overlapping the intervals of a BED file.
+    /// 
+    /// Useful to discard low-complexity regions, segmental duplications or the HLA, both from the observed pairwise
+    /// differences and from pedigree simulations. Intervals are expected in the standard BED format (0-based,
+    /// half-open). May be combined with '--include', in which case exclusion takes precedence.
+    #[clap(long, parse(try_from_os_str=valid_input_file))]
+    pub mask: Option<PathBuf>,
+
+    /// Restrict comparison to positions overlapping the intervals of a BED file.
+    /// 
+    /// Positions that are not found within any of the provided intervals are excluded, both from the observed
+    /// pairwise differences and from pedigree simulations. Intervals are expected in the standard BED format (0-based,
+    /// half-open).
+    #[clap(long, parse(try_from_os_str=valid_input_file))]
+    pub include: Option<PathBuf>,
+
     /// Provide with a list of sample names for printing.{n}
     /// 
     /// By default, individuals will be referred to using their pileup index. e.g. "Ind0, Ind1, Ind2, etc."
@@ -215,9 +354,30 @@ pub struct Common {
     /// Run GRUPS-rs in X-chromosome comparison mode
     /// 
     /// This mode will trigger specific rules of allele transmission and recombination during pedigree 
-    /// simulations, reflective of X-chromosomal inheritance rules.
-    #[clap(short='X', long)]
+    /// simulations, reflective of X-chromosomal inheritance rules. For ZW species (see --species), the
+    /// Z-chromosome is analysed instead, and inheritance rules are mirrored accordingly. Pseudo-autosomal regions
+    /// declared within the species profile (e.g. PAR1 and PAR2) are excluded from comparisons.
+    #[clap(short='X', long, alias("sex-chromosome-mode"))]
     pub x_chromosome_mode: bool,
+
+    /// Number of additional parallel decompression threads.
+    /// 
+    /// Can increase performance when working with BGZF compressed input pileups, BAM files or .vcf.gz files. Note
+    /// that this parameter has no effect when working with uncompressed .pileup, .vcf or .fst[.frq] files.
+    /// 
+    #[clap(long, default_value("0"))]
+    #[serde(default)]
+    pub decompression_threads: usize,
+
+    /// Number of parallel CPU threads.
+    /// 
+    /// Parallelization is dispatched according to the number of pairwise comparisons, both when comparing
+    /// individuals and when performing pedigree simulations. Thus, there is no point in invoking more threads than
+    /// (n*n-1)/2 samples (or n^2/2, when allowing --self-comparisons). Pileup lines are additionally parsed and
+    /// filtered in parallel. Results do not depend on the number of threads.
+    #[clap(short='@', long, default_value("1"))]
+    #[serde(default = "default_threads")]
+    pub threads: usize,
 }
 
 /// Estimate the raw average genetic PairWise Differences between individuals
@@ -262,6 +422,43 @@ pub struct PwdFromStdin {
     #[clap(long)]
     pub consider_dels: bool,
 
+    /// Discard nucleotides that may stem from post-mortem deamination.
+    /// 
+    /// When specified, C->T substitutions found on forward reads, and G->A substitutions found on reverse reads are
+    /// excluded from comparisons. Strands are inferred from the case of each base within the pileup. Note that this
+    /// filter requires a known reference allele: when building the pileup from alignment files ('--bam'), reference
+    /// alleles are retrieved from the '--targets' file (which is then mandatory), and unknown positions are left
+    /// unfiltered.
+    #[clap(long)]
+    #[serde(default)]
+    pub filter_deamination: bool,
+
+    /// Discard nucleotides located within the first or last N positions of their read.
+    /// 
+    /// When working with pileups, this argument requires 5'-relative read positions ('samtools mpileup --output-BP-5',
+    /// see '--read-positions'). Since read lengths are not reported by samtools, only the first N positions from the
+    /// 5' end of each read can then be identified. Both ends of the reads are trimmed when building the pileup from
+    /// alignment files ('--bam').
+    #[clap(long, default_value("0"))]
+    #[serde(default)]
+    pub trim_reads: u32,
+
+    /// Input pileup contains mapping qualities, as generated by 'samtools mpileup --output-MQ'.
+    /// 
+    /// Mapping qualities are expected as an additional column, following the base qualities of each individual.
+    #[clap(long)]
+    #[serde(default)]
+    pub mapping_qualities: bool,
+
+    /// Input pileup contains 5'-relative read positions, as generated by 'samtools mpileup --output-BP-5'.
+    /// 
+    /// Read positions are expected as an additional column, following the base qualities (and mapping qualities, if
+    /// any) of each individual. Positions must be counted from the 5' end of each read: left-to-right positions
+    /// ('--output-BP') cannot be distinguished from these, and would trim the 3' end of reverse reads instead.
+    #[clap(long)]
+    #[serde(default)]
+    pub read_positions: bool,
+
     /// Do not print jackknife blocks between each pair of individuals
     /// 
     /// By default, grups-rs will keep track of the pairwise mismatch rate within windows of size '--blocksize'.
@@ -279,6 +476,38 @@ pub struct PwdFromStdin {
     /// Note that lower block values may drastically increase the memory footprint of grups-rs.
     #[clap(short='b', long, required(false), default_value("1000000"))]
     pub blocksize: u32,
+
+    /// Define jackknife blocks from the intervals of a BED file, instead of using fixed-size windows.
+    /// 
+    /// Intervals (e.g. LD blocks, chromosome arms) are expected in the standard BED format (0-based, half-open) and
+    /// must not overlap. Regions that are not covered by any interval are assigned to additional blocks, spanning each
+    /// gap. Overrides '--blocksize'.
+    #[clap(long, conflicts_with("snps-per-block"))]
+    pub block_bed: Option<PathBuf>,
+
+    /// Define jackknife blocks containing an equal number of overlapping SNPs, instead of using fixed-size windows.
+    /// 
+    /// Blocks are defined separately for each pair of individuals, once all of their overlapping SNPs are known, and
+    /// never span across chromosomes: the incomplete trailing block of each chromosome is merged with the preceding
+    /// one. This prevents samples with patchy coverage from generating badly unbalanced blocks. Overrides '--blocksize'.
+    #[clap(long, parse(try_from_str=parse_snps_per_block))]
+    pub snps_per_block: Option<u32>,
+
+    /// Spill the overlapping SNPs of each pair to memory-mapped temporary files, located within the provided directory.
+    /// 
+    /// Observations are stored per chromosome, and only the chromosome currently being compared is kept in memory. This
+    /// bounds memory usage when comparing many individuals (e.g. 50 samples amount to 1,225 pairs). Temporary files are
+    /// deleted once grups-rs exits.
+    #[clap(long, parse(try_from_os_str=valid_input_directory))]
+    pub spill_dir: Option<PathBuf>,
+
+    /// Write a binary snapshot of the observed comparisons, within a '.obs' file.
+    /// 
+    /// Snapshots contain the overlapping SNPs, pairwise differences, phred scores and jackknife blocks of each pair
+    /// of individuals, and may then be provided to '--observations', in place of the input pileup.
+    #[clap(long, conflicts_with("filter-sites"))]
+    #[serde(default)]
+    pub write_observations: bool,
     
     /// Provide with the minimal sequencing depth required to perform comparison.
     /// 
@@ -292,6 +521,20 @@ pub struct PwdFromStdin {
     #[clap(short='x', long, multiple_values(true), required(false), default_values(&["1","1"]))]
     pub min_depth: Vec <u16>,
 
+    /// Provide with the maximal sequencing depth allowed to perform comparison.
+    /// 
+    /// Positions where an individual exceeds its maximal depth (after quality filtration) are excluded from the
+    /// comparisons involving said individual. This is mainly useful to discard collapsed repeats, which tend to
+    /// display an excess of coverage. Values are recycled across individuals, in the same manner as '--min-depth'.
+    /// By default, no maximal depth is applied.
+    /// 
+    /// Example:  '--samples 0-2 --max-depth 20 35' will result in:{n}
+    ///   - Ind  : 0  1  2{n}
+    ///   - Depth: 20 35 20{n}
+    #[clap(long, multiple_values(true), required(false))]
+    #[serde(default)]
+    pub max_depth: Vec<u16>,
+
     /// 0-based column index of the individuals that should be compared within the input pileup file (specified with --pileup)
     /// 
     /// Argument may accept slices (inclusive), such as --samples 0-3 and/or discrete integer values
@@ -306,6 +549,48 @@ pub struct PwdFromStdin {
     #[clap(short='s', long, multiple_values(true), default_values(&["0", "1"]))]
     pub samples: Vec<String>,
 
+    /// 0-based column index of a second group of individuals, which should be compared against those of --samples.
+    /// 
+    /// When specified, only the pairs spanning both groups are compared, instead of every pair of individuals.
+    /// Individuals of --versus are appended to those of --samples, e.g. when providing --sample-names or --min-depth.
+    /// Accepts slices and discrete values, in the same manner as --samples. Self-comparisons, if requested, are still
+    /// performed for every individual.
+    /// 
+    /// Example:{n}
+    /// '--samples 0 --versus 1-200' will compare the first individual against the 200 others, i.e. 200 pairs instead
+    /// of 20,100.
+    #[clap(long, multiple_values(true), conflicts_with("pairs"))]
+    #[serde(default)]
+    pub versus: Vec<String>,
+
+    /// Only compare the pairs of individuals listed within the provided file.
+    /// 
+    /// Pairs are expected one per line, as two whitespace-separated individuals, referred to either by name (see
+    /// --sample-names) or by 0-based pileup index. Listed individuals must be requested through --samples. Empty lines
+    /// and lines starting with a '#' are ignored.
+    /// 
+    /// Example:{n}
+    /// ART04 ART16{n}
+    /// ART04 5{n}
+    #[clap(long, parse(try_from_os_str=valid_input_file))]
+    pub pairs: Option<PathBuf>,
+
+    /// Define the compared individuals and their per-sample parameters from a sample sheet, instead of using --samples.
+    /// 
+    /// The sample sheet is a whitespace-separated file, where each line defines a single individual, using the
+    /// following columns: <name> <pileup column> <min depth> <contamination rate> <sequencing error rate> <sex>.
+    /// Only the first two columns are required. Undefined values (either missing, or set to '.') fall back to those of
+    /// --min-depth, --contam-rate and --seq-error-rate, while sexes are only used along --sex-specific-mode. Rates
+    /// are specified as percentages, using constant values or ranges. Lines starting with a '#' and a header line
+    /// starting with 'name' are ignored.
+    /// 
+    /// Example:{n}
+    /// name   column  min_depth  contam_rate  seq_error  sex{n}
+    /// ART04  0       2          0.0-5.0      .          male{n}
+    /// ART16  3       .          2.0          1.0        female{n}
+    #[clap(long, parse(try_from_os_str=valid_input_file), conflicts_with_all(&["versus", "sample-names"]))]
+    pub sample_sheet: Option<PathBuf>,
+
     /// Exclude transitions from the input targets file.
     /// 
     /// Note that this argument requires the use of --targets to provide the program with a list of coordinates.
@@ -313,6 +598,56 @@ pub struct PwdFromStdin {
     /// argument for additional information regarding valid file formats.
     #[clap(long)]
     pub exclude_transitions: bool,
+
+    /// Minimal required mapping quality (MAPQ) of a read to perform comparison.
+    /// 
+    /// Applied when building the pileup from alignment files (using '--bam' or '--bam-list'), or when working with
+    /// pileups carrying mapping qualities (see '--mapping-qualities').
+    #[clap(long, default_value("0"))]
+    #[serde(default)]
+    pub min_mapq: u8,
+
+    /// Keep reads flagged as PCR or optical duplicates.
+    /// 
+    /// By default, duplicate reads are discarded when building the pileup from alignment files. Only relevant
+    /// when using '--bam' or '--bam-list'.
+    #[clap(long)]
+    #[serde(default)]
+    pub keep_duplicates: bool,
+
+    /// FORMAT field from which genotype probabilities should be extracted when using '--gl-vcf'.
+    /// 
+    /// 'gp': genotype posteriors ; 'pl': phred-scaled likelihoods ; 'gl': log10-scaled likelihoods.{n}
+    /// By default, the first available field is used for each record, in order of priority: GP > PL > GL.
+    #[clap(long, arg_enum, requires("gl-vcf"))]
+    pub gl_field: Option<LikelihoodField>,
+
+    /// Select the method used to estimate pairwise differences at each overlapping site.
+    /// 
+    /// deterministic: compute the probability of observing a mismatch, using every nucleotide of each individual.{n}
+    /// random-read: randomly sample a single nucleotide per individual (two nucleotides, without replacement, when
+    /// performing self-comparisons), and check whether these mismatch. Sampling is seeded using '--seed'.
+    #[clap(long, arg_enum, default_value("deterministic"))]
+    #[serde(default)]
+    pub pwd_mode: PwdMode,
+
+    /// Number of random draws performed at each site, when using '--pwd-mode random-read'.
+    /// 
+    /// Local pairwise differences are then averaged across all draws.
+    #[clap(long, default_value("1"), parse(try_from_str=parse_sampled_reads))]
+    #[serde(default = "default_sampled_reads")]
+    pub sampled_reads: u16,
+
+    /// Provide the RNG with a set seed.
+    #[clap(long, required(false), default_value_t=fastrand::u64(u64::MIN..=u64::MAX))]
+    #[serde(default = "random_seed")]
+    pub seed: u64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ArgEnum, Serialize, Deserialize, Default)]
+pub enum PwdMode {
+    #[default] Deterministic,
+    RandomRead
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Serialize, Deserialize, Default)]
@@ -322,6 +657,13 @@ pub enum Mode {
     FstMmap
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ArgEnum, Serialize, Deserialize)]
+pub enum LikelihoodField {
+    Gp,
+    Pl,
+    Gl
+}
+
 #[derive(Debug, Copy, Clone, ArgEnum, Serialize, Deserialize, Default)]
 pub enum RelAssignMethod {
     Zscore,
@@ -338,6 +680,23 @@ impl Display for RelAssignMethod {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ArgEnum, Serialize, Deserialize)]
+pub enum GenomeBuild {
+    Grch37,
+    Grch38,
+    Chm13,
+}
+
+impl Display for GenomeBuild {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Grch37 => write!(f, "GRCh37"),
+            Self::Grch38 => write!(f, "GRCh38"),
+            Self::Chm13  => write!(f, "CHM13"),
+        }
+    }
+}
+
 /// Estimate genetic relatedness through pedigree simulations.
 /// 
 /// Perform genetic relatedness estimation between pileup individuals, by first running the pwd-from-stdin 
@@ -442,19 +801,33 @@ pub struct PedigreeSims {
 
     /// Path to a directory containing a set of chromosome-specific genetic recombination maps, such as the HapMap-phaseII dataset.
     /// 
-    /// Note that GRUPS-rs will search for, and attempt to load any file ending with the '.txt' file extension within that directory.
-    /// It is therefore highly recommended that this directory ONLY contains the required recombination map files, and nothing else.
-    /// 
-    /// The expected input is a headed, tab separated file with columns '<chr> <pos(bp)> <rate(cM/Mb)> <Map(cM)>'{n}
-    /// Example:
+    /// Note that GRUPS-rs will search for, and attempt to load any file ending with the '.txt', '.map' or '.tsv' file extension
+    /// (optionally followed by '.gz') within that directory. It is therefore highly recommended that this directory ONLY contains
+    /// the required recombination map files, and nothing else. Use --recomb-maps to provide an explicit list of files instead.
+    /// 
+    /// The format of each map is detected from its header. Accepted formats are:{n}
+    ///   HapMap        : '<chr> <pos(bp)> <rate(cM/Mb)> <Map(cM)>'{n}
+    ///   SHAPEIT/Eagle : 'chr position COMBINED_rate(cM/Mb) Genetic_Map(cM)'{n}
+    ///   PLINK (.map)  : '<chr> <id> <Map(cM)> <pos(bp)>' (headerless){n}
+    ///   deCODE        : 'Chr Begin End cMperMb cM'{n}
+    ///   Bhérer et al. : 'pos chr cM'{n}
+    /// Example (HapMap):
     /// Chromosome  Position(bp)    Rate(cM/Mb)     Map(cM)
     /// chr22       16051347        8.096992        0.000000
     /// chr22       16052618        8.131520        0.010291
     /// ...         ...             ...             ...
     /// 
     /// 
-    #[clap(short='G', long, required(false), parse(try_from_os_str=valid_input_directory))] // default_value("./data/recombination/GRCh37"),
-    pub recomb_dir: PathBuf,
+    #[clap(short='G', long, required_unless_present("recomb-maps"), parse(try_from_os_str=valid_input_directory))] // default_value("./data/recombination/GRCh37"),
+    pub recomb_dir: Option<PathBuf>,
+
+    /// Explicit list of genetic recombination maps.
+    /// 
+    /// Use this argument instead of --recomb-dir if your recombination maps are located next to other files. See
+    /// --recomb-dir for a list of accepted formats. Both plain and gzip-compressed files are accepted.
+    #[clap(long, multiple_values(true), conflicts_with("recomb-dir"), parse(try_from_os_str=valid_input_file))]
+    #[serde(default)]
+    pub recomb_maps: Vec<PathBuf>,
 
     /// Number of pedigree simulation replicates to perform for each pairwise comparisons.
     /// 
@@ -511,18 +884,6 @@ pub struct PedigreeSims {
     #[clap(short='p', long, parse(try_from_os_str=valid_input_file))]
     pub panel: Option<PathBuf>,
 
-    /// Number of additional parallel decompression threads.
-    /// 
-    /// Can increase performance when working with BGZF compressed .vcf.gz files. Note that this parameter has no effect when working with
-    /// uncompressed .vcf or .fst[.frq] files.
-    /// 
-    #[clap(long, default_value("0"))]
-    pub decompression_threads: usize,
-
-    /// Provide the RNG with a set seed.
-    #[clap(long, required(false), default_value_t=fastrand::u64(u64::MIN..=u64::MAX))]
-    pub seed: u64,
-
     /// Select the method for most likely relationship assignment
     /// 
     /// svm: Compute treshold using Ordinally Partitionned Support Vector Machines. Binary SVMs are instantiated sequentially, from the lowest relatedness order 
@@ -535,6 +896,23 @@ pub struct PedigreeSims {
     #[clap(long, arg_enum, default_value("svm"))]
     pub assign_method: RelAssignMethod,
 
+    /// Account for the block-jackknife standard error of the observed avg. PWD when computing z-scores.
+    /// 
+    /// By default, z-scores are computed using the standard deviation of each simulated distribution. When this flag is
+    /// set, the jackknife standard error of the observed avg. PWD is combined with this standard deviation, i.e.
+    /// z = (sim_avg - obs_avg) / sqrt(sim_sd² + obs_se²)
+    #[clap(long)]
+    #[serde(default)]
+    pub jackknife_zscore: bool,
+
+    /// Minimum number of overlapping SNPs required to perform pedigree simulations on a given pair.
+    ///
+    /// Pairs whose observed overlap is lower than this value are excluded from pedigree simulations, and are reported
+    /// with an 'Insufficient_overlap' status within the .result file. The default value of 0 disables this filter.
+    #[clap(long, default_value("0"))]
+    #[serde(default)]
+    pub min_overlap: usize,
+
     /// Run GRUPS-rs in sex-specific mode
     /// 
     /// By default, grups-rs will randomly pick reference samples as founder individuals, without consideration of their 
@@ -543,14 +921,6 @@ pub struct PedigreeSims {
     /// samples are selected in accordance with the sex of the considered founder individual.
     #[clap(long)]
     pub sex_specific_mode: bool,
-
-    /// Number of parallel CPU processes when performing Pedigree simulations
-    /// 
-    /// Parallelization is dispatched according to the number of pairwise comparisons. Thus, there is no point in 
-    /// invoking more threads than (n*n-1)/2 samples (or n^2/2, when allowing --self-comparisons)
-    /// 
-    #[clap(short='@', long, default_value("1"))]
-    pub threads: usize,
 }
 
 /// Convert VCF files into sets of FSA-encoded indexes.
@@ -617,25 +987,50 @@ pub struct VCFFst {
     /// When unspecified, the program will instead look for <POP>_AF tags within the VCF's INFO field. These tags can be generated
     /// using the bcftools '+fill-tags' plugin.
     #[clap(short='F', long)]
-    pub compute_pop_afs: bool
+    pub compute_pop_afs: bool,
 
+    /// Fasta indexed reference genome.
+    /// 
+    /// Contigs which are neither numbered (1, 2, ..., 22), nor X, Y or MT (e.g. unplaced scaffolds, or contigs of
+    /// non-human assemblies) must be defined within a reference genome to get encoded. Note that the same reference
+    /// genome must then be provided to the 'pedigree-sims' module, when using the generated files.
+    /// Note that a '.fasta.fai' genome index file must be present at the same directory.
+    #[clap(short, long, required(false))]
+    #[serde(default)]
+    pub genome: Option<String>,
 }
 
 impl PwdFromStdin {
     /// Sanity check : depth must indeed be > 2 when performing self-comparison.
     ///
     /// # Errors
-    ///  if any of the values provided through `--min-depth` is lower than 2 while `--self-comparison` is on.
+    ///  if any of the values provided through `--min-depth` or `--max-depth` is lower than 1.
     /// 
     /// # @TODO:
     /// - put this in `Comparison::new()` ?? -> This would allow mix-matching batch mode and self-comparison,
     ///   but could be a bit confusing for users..
     pub fn check_depth(&self) -> Result<(), ParserError> {
-        if self.min_depth.iter().any(|&x| x < 1) {        
+        if self.min_depth.iter().chain(&self.max_depth).any(|&x| x < 1) {        
             return Err(ParserError::InsufficientDepthError)
         }
         Ok(())
     }
+
+    /// Parse the pileup indices of every requested individual, i.e. those of `--samples`, followed by those of
+    /// `--versus`.
+    /// 
+    /// # Errors
+    /// - if any of the values provided through `--samples` or `--versus` is invalid.
+    /// - `OverlappingGroups` if an individual is found within both `--samples` and `--versus`.
+    pub fn requested_samples(&self) -> Result<Vec<usize>, ParserError> {
+        let mut samples: Vec<usize> = parse_user_ranges(&self.samples, "samples")?;
+        let versus: Vec<usize> = parse_user_ranges(&self.versus, "versus")?;
+        if let Some(index) = versus.iter().find(|index| samples.contains(index)) {
+            return Err(ParserError::OverlappingGroups(*index))
+        }
+        samples.extend(versus);
+        Ok(samples)
+    }
 }
 
 /// Command line interface argument parser.
@@ -645,19 +1040,35 @@ impl PwdFromStdin {
 ///        - add deserialization method. Users could thus fully reproduce a previous run with ease. keep it FAIR. 
 impl Common {
     /// Sanity Check: The program should leave if the user did not provide any pileup input, either through
-    /// `--pileup` or through stdinput. Without this, our program would wait indefinitely for the stdin buffer.
+    /// `--pileup`, `--merge-pileups`, `--bam`, `--bam-list` or through stdinput. Without this, our program would wait indefinitely
+    /// for the stdin buffer.
     /// 
     /// # Errors
-    /// - if the user did not provide an input file, neither from stdin, nor through the `--pileup` argument.
+    /// - if the user did not provide an input file, neither from stdin, nor through the `--pileup`,
+    ///   `--merge-pileups`, `--bam`, `--bam-list`, `--eigenstrat`, `--plink` or `--gl-vcf` arguments.
     pub fn check_input(&self) -> Result<(), ParserError> {
-        if self.pileup.is_none() && io::stdin().is_terminal() {
+        if self.pileup.is_none() && self.merge_pileups.is_empty() && !self.has_bam_input() && !self.has_genotype_input() && self.observations.is_none() && io::stdin().is_terminal() {
             return Err(ParserError::MissingPileupInput)
         }
         Ok(())
     }
 
-    /// Get a generic filename for our output files. If the user used `--pileup`, this will become its file stem.
-    /// If the user used stdin, this will become a generic name -> "pwd_from_stdin-output" 
+    /// Return `true` if the user requested to build the pileup from alignment files (`--bam` or `--bam-list`)
+    #[must_use]
+    pub fn has_bam_input(&self) -> bool {
+        !self.bam.is_empty() || self.bam_list.is_some()
+    }
+
+    /// Return `true` if the user requested to use a genotype dataset (`--eigenstrat`, `--plink` or `--gl-vcf`)
+    #[must_use]
+    pub fn has_genotype_input(&self) -> bool {
+        self.eigenstrat.is_some() || self.plink.is_some() || self.gl_vcf.is_some()
+    }
+
+    /// Get a generic filename for our output files. If the user used `--pileup` (or `--bam-list`, `--gl-vcf`,
+    /// `--observations`), this will become its file stem. If the user used `--eigenstrat` or `--plink`, this will
+    /// become the file name of the dataset prefix. If the user used stdin, `--merge-pileups` or `--bam`, this will become a generic name ->
+    /// "pwd_from_stdin-output" 
     ///
     /// # @TODO: This function should be the one responsible of defining the default filename. Stay dry.
     /// 
@@ -665,9 +1076,16 @@ impl Common {
     /// - if a default file-prefix cannot be created from the input pileup filestem.
     pub fn get_file_prefix(&self, subdir: Option<&str>) -> Result<PathBuf> {
         let default_prefix = String::from("pwd_from_stdin-output");
-        let file_prefix = Path::new(self.pileup.as_ref()
-            .unwrap_or(&default_prefix))
-            .file_stem()
+        let file_prefix = match self.eigenstrat.as_deref().or(self.plink.as_deref()) {
+            Some(dataset_prefix) => dataset_prefix.file_name(), // Dataset prefixes do not carry any file extension.
+            None => self.pileup.as_deref().map(Path::new)
+                .or(self.bam_list.as_deref())
+                .or(self.gl_vcf.as_deref())
+                .or(self.observations.as_deref())
+                .unwrap_or(Path::new(&default_prefix))
+                .file_stem()
+        };
+        let file_prefix = file_prefix
             .ok_or_else(||anyhow!(ParserError::ParseOutputPrefix))
             .loc( "While parsing command line arguments" )?;
 
@@ -777,6 +1195,32 @@ where   T       : FromStr + Add<Output = T> + Ord + One,
     }
 }
 
+fn default_threads() -> usize {
+    1
+}
+
+fn default_sampled_reads() -> u16 {
+    1
+}
+
+fn parse_sampled_reads(s: &str) -> Result<u16> {
+    match s.parse::<u16>()? {
+        0     => Err(anyhow!(ParserError::InvalidSampledReads)).with_loc(|| format!("While parsing {s}")),
+        reads => Ok(reads),
+    }
+}
+
+fn parse_snps_per_block(s: &str) -> Result<u32> {
+    match s.parse::<u32>()? {
+        0     => Err(anyhow!(ParserError::InvalidSnpsPerBlock)).with_loc(|| format!("While parsing {s}")),
+        snps  => Ok(snps),
+    }
+}
+
+fn random_seed() -> u64 {
+    fastrand::u64(u64::MIN..=u64::MAX)
+}
+
 fn percent_str_to_ratio(s: &str) -> Result<f64>{
     use ParserError::ParseRatio;
 
@@ -847,4 +1291,26 @@ where   T                   : FromStr + Add<Output = T> + Ord + One,
     parsed_ranges.sort();
     parsed_ranges.dedup();
     Ok(parsed_ranges)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_relocated_args() -> Result<(), Box<dyn Error>> {
+        let yaml = "commands:\n  PedigreeSims:\n    common: {}\n    pwd: {}\n    ped:\n      seed: 42\n      threads: 8\n";
+        let mut config: serde_yaml::Value = serde_yaml::from_str(yaml)?;
+        Cli::migrate_relocated_args(&mut config);
+        let args = &config["commands"]["PedigreeSims"];
+        assert_eq!(args["pwd"]["seed"].as_u64(), Some(42));
+        assert_eq!(args["common"]["threads"].as_u64(), Some(8));
+        assert!(args["ped"].get("seed").is_none() && args["ped"].get("threads").is_none());
+
+        // ---- Arguments found at their current location take precedence.
+        let yaml = "commands:\n  PedigreeSims:\n    common: {}\n    pwd:\n      seed: 1\n    ped:\n      seed: 42\n";
+        let mut config: serde_yaml::Value = serde_yaml::from_str(yaml)?;
+        Cli::migrate_relocated_args(&mut config);
+        assert_eq!(config["commands"]["PedigreeSims"]["pwd"]["seed"].as_u64(), Some(1));
+        Ok(())
+    }
+}
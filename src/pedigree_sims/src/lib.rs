@@ -3,7 +3,7 @@ use located_error::prelude::*;
 //pub mod io;
 use grups_io::{
     parse::{self, FileKey},
-    read::PanelReader,
+    read::{PanelReader, SampleEntry, SampleSheet},
     read::genotype_reader::{GenotypeReader, VCFReader, FSTReader},
 };
 
@@ -11,6 +11,7 @@ mod svm;
 
 pub mod pedigrees;
 
+use genome::{GeneticMap, Genome, GenomeMask, species::SexChromosome};
 use pwd_from_stdin::comparisons::Comparisons;
 
 
@@ -18,8 +19,6 @@ use pwd_from_stdin::comparisons::Comparisons;
 //   + [FEATURE] Add ability for multiple contaminating pop
 //   + [FEATURE] Add weighted averaged contamination rates ? (see grups_module.pyx:585):
 // -------------------------------------------------------------------------------------------------------------------
-//   + [  QoL  ] Add multiple candidate file extensions to GeneticMap.
-// -------------------------------------------------------------------------------------------------------------------
 // @ TODO! CLEANUP + BUGFIX
 // -------------------------------------------------------------------------------------------------------------------
 // @ TODO! META
@@ -30,24 +29,29 @@ pub fn run(
     ped_cli           : &parser::PedigreeSims,
     requested_samples : &[usize],
     comparisons       : &mut Comparisons,
+    sample_sheet      : Option<&SampleSheet>,
+    genome            : &Genome,
+    sex_chromosome    : &SexChromosome,
 ) -> Result<()>
 {
     info!("Running 'pedigree-sims' module...");
     // ----------------------------- Sanity checks 
-    if ped_cli.contamination_rate.len() < requested_samples.len() {
+    let sheet_defines = |rate: fn(&SampleEntry) -> bool| sample_sheet.is_some_and(|sheet| sheet.iter().all(rate));
+    if ped_cli.contamination_rate.len() < requested_samples.len() && !sheet_defines(|sample| sample.contam_rate.is_some()) {
         warn!("Number of provided contamination rates is lower than that of --samples. \
             Values will be recycled."
         );
     }
 
     match &ped_cli.seq_error_rate {
+        None if sample_sheet.is_some_and(|sheet| sheet.iter().any(|sample| sample.seq_error_rate.is_some())) => (),
         None => {
             // Explicitely warn the user that contamination error rates will be taken from the pileup file
             // if --seq_error_rate was unspecified
             warn!("--seq_error_rate was unspecified. Error probabilities will be sampled directly from the pileup file" );
         },
         Some(error_rates_vec) => {
-            if error_rates_vec.len() < requested_samples.len() {
+            if error_rates_vec.len() < requested_samples.len() && !sheet_defines(|sample| sample.seq_error_rate.is_some()) {
                 warn!("Number of provided sequencing error rates is lower than that of --samples. \
                     Values will be recycled."
                 );
@@ -55,6 +59,14 @@ pub fn run(
         }
     }
 
+    // ----------------------------- Exclude pairs with an insufficient overlap from simulations.
+    let insufficient = comparisons.split_off_insufficient(ped_cli.min_overlap);
+    for comparison in insufficient.iter() {
+        warn!("{} only has {} overlapping SNPs (--min-overlap: {}). Skipping pedigree simulations for this pair.",
+            comparison.get_pair(), comparison.get_overlap(), ped_cli.min_overlap
+        );
+    }
+
     // ----------------------------- Prepare output files
     // ---- Add final_results files.
     let mut output_files = parse::get_output_files(
@@ -77,6 +89,12 @@ pub fn run(
 
     debug!("Output files: {output_files:#?}");
 
+    // ---- Nothing left to simulate: only report pairs having an insufficient overlap.
+    if comparisons.is_empty() {
+        warn!("None of the requested pairs reach the provided --min-overlap threshold. Skipping pedigree simulations.");
+        return pedigrees::Pedigrees::write_insufficient_results(&insufficient, &output_files["result"]);
+    }
+
     // --------------------- Fetch the input panel.
     let mut panel = match ped_cli.panel.as_ref() {
         Some(path) => PanelReader::new(path),
@@ -95,12 +113,20 @@ pub fn run(
         parser::Mode::FstMmap => FSTReader::<memmap2::Mmap>::fetch_input_files(&ped_cli.data_dir)?
     };
 
+    // --------------------- Parse input recombination maps.
+    let genetic_map = match &ped_cli.recomb_dir {
+        Some(dir) => GeneticMap::from_dir(dir),
+        None      => GeneticMap::from_files(&ped_cli.recomb_maps),
+    }.loc("While parsing genetic recombination maps")?;
+
     // --------------------- Generate empty pedigrees for each Comparison & each requested replicate.
     info!("Initializing pedigree replicates...");
     let mut pedigrees = pedigrees::Pedigrees::initialize(
         &ped_cli.pedigree_pop,
         comparisons,
-        &ped_cli.recomb_dir
+        genetic_map,
+        genome,
+        sex_chromosome,
     )?;
 
     // -------------------- Populate all pedigree replicates.
@@ -125,6 +151,9 @@ pub fn run(
 
     // --------------------- Randomly assign chromosomal sex of samples if requested
     if ped_cli.sex_specific_mode {
+        if let Some(sample_sheet) = sample_sheet {
+            pedigrees.assign_sample_sexes(comparisons, sample_sheet)?;
+        }
         pedigrees.assign_random_sex().loc("While attempting to randomly assign sexes of all pedigrees")?;
     }
 
@@ -141,28 +170,33 @@ pub fn run(
         ped_cli.snp_downsampling_rate,
         ped_cli.af_downsampling_rate,
         &ped_cli.seq_error_rate, 
-        &ped_cli.contamination_rate
+        &ped_cli.contamination_rate,
+        sample_sheet,
     )?;
 
     
+    // --------------------- Parse genomic inclusion/exclusion masks.
+    let mut mask = GenomeMask::new(com_cli.include.as_deref(), com_cli.mask.as_deref())?;
+    sex_chromosome.mask_pseudo_autosomal_regions(&mut mask);
+
     // --------------------- Perform pedigree simulations for each pedigree, using all chromosomes.
     match ped_cli.mode {
         parser::Mode::Vcf => {
             info!("Starting VCF pedigree comparisons.");
             for vcf in &input_paths {
-                pedigrees.pedigree_simulations_vcf(comparisons,  vcf, ped_cli.maf, ped_cli.decompression_threads)?;
+                pedigrees.pedigree_simulations_vcf(comparisons,  vcf, ped_cli.maf, &mask, com_cli.decompression_threads)?;
             }
         },
         parser::Mode::Fst => {
             info!("Starting FST pedigree comparisons (in RAM).");
             for fst in &input_paths{
-                pedigrees.pedigree_simulations_fst::<Vec<u8>>(comparisons, fst, ped_cli.maf, ped_cli.threads)?;
+                pedigrees.pedigree_simulations_fst::<Vec<u8>>(comparisons, fst, ped_cli.maf, &mask, com_cli.threads)?;
             }
         },
         parser::Mode::FstMmap => {
             info!("Starting FST pedigree comparisons (Memmap).");
             for fst in &input_paths{
-                pedigrees.pedigree_simulations_fst::<memmap2::Mmap>(comparisons, fst, ped_cli.maf, ped_cli.threads)?;
+                pedigrees.pedigree_simulations_fst::<memmap2::Mmap>(comparisons, fst, ped_cli.maf, &mask, com_cli.threads)?;
             }
         }
     }
@@ -173,7 +207,7 @@ pub fn run(
 
     // --------------------- Compute most likely relationship for each Comparison
     info!("Assigning most likely relationships using {}...", ped_cli.assign_method);
-    pedigrees.compute_results(comparisons, &output_files["result"], ped_cli.assign_method, ped_cli.threads)?;
+    pedigrees.compute_results(comparisons, &insufficient, &output_files["result"], ped_cli.assign_method, ped_cli.jackknife_zscore, com_cli.threads)?;
 
     Ok(())
 }
\ No newline at end of file
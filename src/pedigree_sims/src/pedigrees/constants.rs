@@ -6,4 +6,5 @@ pub (crate) const PWD_FORMAT_LEN: usize              = 9;
 pub (crate) const OVERLAP_FORMAT_LEN: usize          = 9;
 pub (crate) const AVG_PWD_FORMAT_LEN: usize          = 12;
 pub (crate) const SEX_FORMAT_LEN: usize              = 11;
-pub (crate) const FLOAT_FORMAT_PRECISION: usize      = 6;
\ No newline at end of file
+pub (crate) const FLOAT_FORMAT_PRECISION: usize      = 6;
+pub (crate) const INSUFFICIENT_OVERLAP_LABEL: &str  = "Insufficient_overlap";
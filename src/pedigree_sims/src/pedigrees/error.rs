@@ -8,6 +8,9 @@ pub enum PedigreeError {
     #[error("Attempting to mutably access a missing pedigree vector using the comparison label '{0}' as a key.")]
     MissingPedVec(String),
 
+    #[error("Sequencing error rates are only defined for one of the two individuals of pair '{0}'. When using a sample sheet without '--seq-error-rate', sequencing error rates must either be defined for every sample, or for none of them")]
+    MissingSeqErrorRate(String),
+
     #[error("Pedigree Vector does not contain any contaminant")]
     MissingContaminant,
 
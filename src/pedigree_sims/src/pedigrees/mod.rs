@@ -2,19 +2,20 @@ use std::{collections::{BTreeMap, HashMap}, mem::ManuallyDrop, path::Path, sync:
 
 use grups_io::{
     read::genotype_reader::{fst::SetRead, FSTReader, GenotypeReader, VCFReader},
-    read::PanelReader,
+    read::{PanelReader, SampleSheet},
     read::SampleTag,
     write::GenericWriter,
 };
 
 use genome::{
     coordinate::{Coordinate, Position},
-    GeneticMap,
+    species::SexChromosome,
+    GeneticMap, Genome, GenomeMask,
 };
 
 use located_error::prelude::*;
 use parser::RelAssignMethod;
-use pwd_from_stdin::comparisons::{Comparison, Comparisons as PileupComparisons};
+use pwd_from_stdin::comparisons::{Comparison, Comparisons as PileupComparisons, HeterozygosityRatio};
 
 use ahash::AHashMap;
 use fastrand;
@@ -41,7 +42,7 @@ mod pedigree_sim_builder;
 pub mod constants;
 use constants::{
     AVG_PWD_FORMAT_LEN, COMPARISON_LABEL_FORMAT_LEN, FLOAT_FORMAT_PRECISION, IND_LABEL_FORMAT_LEN,
-    IND_TAG_FORMAT_LEN, OVERLAP_FORMAT_LEN, PWD_FORMAT_LEN, REPLICATE_ID_FORMAT_LEN,
+    IND_TAG_FORMAT_LEN, INSUFFICIENT_OVERLAP_LABEL, OVERLAP_FORMAT_LEN, PWD_FORMAT_LEN, REPLICATE_ID_FORMAT_LEN,
     SEX_FORMAT_LEN,
 };
 
@@ -66,6 +67,7 @@ pub struct Pedigrees {
     genetic_map: GeneticMap,
     previous_positions: HashMap<String, Arc<RwLock<Position>>>,
     rng : fastrand::Rng,
+    sex_chromosome: SexChromosome,
 }
 
 /// @TODO! : Right now we're reading the pedigree definition file for each replicate. Which isn't very efficient.
@@ -76,22 +78,25 @@ impl Pedigrees {
     /// # Arguments:
     /// - `pedigree_pop`: (super-)population id used for the pedigree simulation replicates.
     /// - `comparisons` : pileup Comparisons of our real samples.
-    /// - `recomb_dir`  : path leading to the directory containing recombination maps.
+    /// - `genetic_map` : genetic recombination maps.
+    /// - `genome`      : reference genome, against which recombination maps are checked.
+    /// - `sex_chromosome`: sex-chromosome of the species (i.e. `X` or `Z`), to which sex-chromosomal inheritance rules
+    ///   are applied.
     ///
     /// # Errors:
-    /// - returns an error upon failing to parse `self.genetic_map`
+    /// - returns an error if any recombination map extends beyond the end of its chromosome.
     pub fn initialize(
         pedigree_pop: &str,
         comparisons: &PileupComparisons,
-        recomb_dir: impl AsRef<Path>,
+        genetic_map: GeneticMap,
+        genome: &Genome,
+        sex_chromosome: &SexChromosome,
     ) -> Result<Self> {
         // Generate pedigree replicates for each pwd_from_stdin::Comparison.
         let pedigrees = HashMap::new();
 
-        // --------------------- Parse input recombination maps.
-        //info!("Parsing genetic maps in {}", recomb_dir);
-        let genetic_map =
-            GeneticMap::from_dir(recomb_dir).loc("While attempting to initialize Pedigrees")?;
+        // --------------------- Ensure recombination maps match the reference genome.
+        genetic_map.check_bounds(genome).loc("While attempting to initialize Pedigrees")?;
 
         // --------------------- For each comparison, keep a record of the previously typed SNP's position.
         let mut previous_positions = HashMap::new();
@@ -107,7 +112,8 @@ impl Pedigrees {
             pedigree_pop,
             genetic_map,
             previous_positions,
-            rng
+            rng,
+            sex_chromosome: sex_chromosome.clone(),
         })
     }
 
@@ -193,6 +199,32 @@ impl Pedigrees {
             .all(|ped_rep| ped_rep.read().all_sex_assigned())
     }
 
+    /// Fix the sex of the compared individuals of each pedigree simulation replicate, according to the sexes defined
+    /// within the user-provided sample sheet (see `Pedigree::assign_compared_sexes()`).
+    /// Arguments:
+    /// - `comparisons` : pileup Comparisons of our real samples.
+    /// - `sample_sheet`: user-defined sample sheet.
+    ///
+    /// # Errors
+    /// - if `self.pedigrees` does contain a given pileup comparison label.
+    pub fn assign_sample_sexes(&mut self, comparisons: &PileupComparisons, sample_sheet: &SampleSheet) -> Result<()> {
+        for comparison in comparisons.iter() {
+            let pair_label = comparison.get_pair();
+            let sexes = comparison.get_pair_indices().map(|index| sample_sheet.get(index).and_then(|sample| sample.sex));
+            let satisfied = self.inner.get(pair_label)
+                .ok_or_else(|| PedigreeError::MissingPedVec(pair_label.to_string()))
+                .loc("While assigning the sex of compared pedigree individuals")?
+                .write()
+                .assign_compared_sexes(sexes);
+            if !satisfied {
+                warn!("Failed to assign the sample sheet sexes of {pair_label} to some of the compared pedigree individuals, \
+                    either because their sex is set within the pedigree definition file, or because it conflicts with that of their spouse."
+                );
+            }
+        }
+        Ok(())
+    }
+
     pub fn assign_random_sex(&mut self) -> Result<()> {
         self.inner.iter_mut().try_for_each(|(label, ped_rep)| {
             ped_rep.write().assign_random_sex().with_loc(|| {
@@ -211,9 +243,12 @@ impl Pedigrees {
     ///   - `seq_error_rate[i]` is tied to pileup `sample[i]`
     /// - `seq_error_rates`       : user-defined probabilities of simulating a human contamination (constant values and/or ranges)
     ///    -  `contam_rate[i]` is tied to pileup `sample[i]`
+    /// - `sample_sheet`          : optional user-defined sample sheet. Per-sample rates defined within the sheet take
+    ///   precedence over `seq_error_rates` and `contam_rate`.
     ///
     /// # Errors
     /// - if `self.pedigrees` does contain a given pileup comparison label.
+    /// - `MissingSeqErrorRate` if sequencing error rates are only defined for one of the two individuals of a pair.
     pub fn set_params(
         &mut self,
         comparisons: &PileupComparisons,
@@ -221,11 +256,12 @@ impl Pedigrees {
         af_downsampling_rate: f64,
         seq_error_rate: &Option<Vec<Vec<f64>>>,
         contam_rate: &[Vec<f64>],
+        sample_sheet: Option<&SampleSheet>,
     ) -> Result<()> {
         let loc_msg = "While attempting to set user input parameters";
         // ---- Iterate upon pileup comparisons and assign parameters for each pedigree simulation replicate.
         for comparison in comparisons.iter() {
-            use PedigreeError::MissingPedVec;
+            use PedigreeError::{MissingPedVec, MissingSeqErrorRate};
             let pair_indices = comparison.get_pair_indices();
             let pair_label = comparison.get_pair();
             let mut pedigree_reps = self.inner
@@ -233,15 +269,28 @@ impl Pedigrees {
                 .ok_or_else(|| MissingPedVec(pair_label.to_string()))
                 .loc(loc_msg)?.write();
 
+            // ---- Fetch the rates of each individual. Values of the sample sheet take precedence over the command line,
+            //      where values wrap around if the user did not provide enough ranges!
+            let samples = pair_indices.map(|index| sample_sheet.and_then(|sheet| sheet.get(index)));
+            let seq_error_rates = [0, 1].map(|i| samples[i]
+                .and_then(|sample| sample.seq_error_rate.as_deref())
+                .or_else(|| seq_error_rate.as_ref().map(|rates| rates[pair_indices[i] % rates.len()].as_slice()))
+            );
+            let contam_rates = [0, 1].map(|i| samples[i]
+                .and_then(|sample| sample.contam_rate.as_deref())
+                .unwrap_or(&contam_rate[pair_indices[i] % contam_rate.len()])
+            );
+
             // ---- Instantiate a sequencing error `ParamRateGenerator` if the user specified sequencing error rates.
             //      If the user did not provide any, assign `None` -> the phred-scores of the pileup will then be used to compute the seq-error probability
-            let mut seq_error_rate_gen = seq_error_rate.as_ref().map(|seq_errors_vec| {
-                ParamRateGenerator::from_user_input(seq_errors_vec, pair_indices)
-            });
+            let mut seq_error_rate_gen = match seq_error_rates {
+                [Some(first), Some(second)] => Some(ParamRateGenerator::from_rates([first, second])),
+                [None, None]                => None,
+                _                           => return Err(MissingSeqErrorRate(pair_label.to_string())).loc(loc_msg),
+            };
 
             // ---- Instantiate a contamination `ParamRateGenerator`
-            let mut contam_rate_gen =
-                ParamRateGenerator::from_user_input(contam_rate, pair_indices);
+            let mut contam_rate_gen = ParamRateGenerator::from_rates(contam_rates);
 
             match seq_error_rate_gen {
                 Some(ref seq_err) => debug!(
@@ -319,7 +368,7 @@ impl Pedigrees {
             .with_loc(|| MissingContaminant)?
             .compute_local_cont_af(reader)?;
 
-        let xchr_mode = coordinate.chromosome.0 == b'X';
+        let hemizygous = (coordinate.chromosome == self.sex_chromosome.chromosome).then_some(self.sex_chromosome.hemizygous);
         'pedigree: for (i, pedigree) in pedigree_vec.inner.iter_mut().enumerate() {
             // --------------------- Perform SNP downsampling if necessary
             if rng.f64() < pedigree.get_params()?.snp_downsampling_rate {
@@ -333,7 +382,7 @@ impl Pedigrees {
             pedigree.compute_offspring_alleles(
                 interval_prob_recomb,
                 i,
-                xchr_mode,
+                hemizygous,
                 rng
             )?;
 
@@ -352,6 +401,7 @@ impl Pedigrees {
     /// - `comparisons`   : pileup Comparisons of our real samples.
     /// - `input_fst_path`: path leading to the target FST-index file, containing genotype information for founder individuals. (`.fst`).
     /// - `maf`           : user-defined minor-allele-frequency treshold.
+    /// - `mask`          : genomic inclusion/exclusion mask. Masked positions are filtered out of our comparisons.
     ///
     /// # Panics:
     /// - when failing to convert `input_fst_path` to a string slice.
@@ -361,6 +411,7 @@ impl Pedigrees {
         comparisons: &mut PileupComparisons,
         input_fst_path: &Path,
         maf: f32,
+        mask: &GenomeMask,
         threads: usize,
     ) -> Result<()> {
         let loc_msg = "While performing pedigree simulations";
@@ -425,6 +476,15 @@ impl Pedigrees {
                         // --------------------- Print progress bar
                         progress_bar.set_position(i as u64);
 
+                        // --------------------- Skip masked positions, and keep them in memory for filtration.
+                        if !mask.allows(&coordinate) {
+                            trace!("[{pair_name}] skip masked position at {coordinate}");
+                            positions_to_delete.write()
+                                .entry(key.to_owned())
+                                .or_default()
+                                .push(coordinate);
+                            continue 'coordinate;
+                        }
 
                         // --------------------- Search through the FST index for the genotypes and pop frequencies at this coordinate.
                         fst_reader.clear_buffers();
@@ -483,7 +543,7 @@ impl Pedigrees {
         }
 
         // --------------------- Filter out unwanted alleles.
-        Self::filter_pileup_positions(&mut positions_to_delete.write(), comparisons);
+        Self::filter_pileup_positions(&mut positions_to_delete.write(), comparisons)?;
         multiprogress.clear()?;
         Ok(())
     }
@@ -494,22 +554,26 @@ impl Pedigrees {
     /// # Arguments:
     /// - `positions_to_delete`: HashMap of coordinates. Key = comparison label | Value = Vec<Coordinate> to delete.
     /// - `comparisons`        : pileup Comparisons of our real samples.
+    /// 
+    /// # Errors
+    /// - if the filtered positions of any comparison cannot be spilled back to disk.
     fn filter_pileup_positions(
         positions_to_delete: &mut AHashMap<String, Vec<Coordinate>>,
         comparisons: &mut PileupComparisons,
-    ) {
+    ) -> Result<()> {
         info!("Filtering out unwanted alleles from comparisons.");
         for comparison in comparisons.iter_mut() {
             let key = comparison.get_pair().to_string();
             let pre_filtered_n = comparison.positions.len();
-            comparison.positions.retain(|pwd| {
+            comparison.retain_positions(|pwd| {
                 !positions_to_delete
                     .entry(key.clone())
                     .or_default()
                     .contains(&pwd.coordinate)
-            });
+            })?;
             info!("- {key: <20} filtered-out SNPs: {}", pre_filtered_n - comparison.positions.len());
         }
+        Ok(())
     }
 
     /// Perform pedigree simulations using a `VCFReader`.
@@ -517,6 +581,7 @@ impl Pedigrees {
     /// - `comparisons`   : pileup Comparisons of our real samples.
     /// - `input_vcf_path`: path leading to the target VCF file, containing genotype information for founder individuals. (`.vcf` or `.vcf.gz`).
     /// - `maf`           : user-defined minor-allele-frequency treshold.
+    /// - `mask`          : genomic inclusion/exclusion mask. Masked positions are filtered out of our comparisons.
     /// - `threads`       : user-requested number of decompression threads for our `VCFReader` (this is only relevant when reading BGZF-compressed `.vcf.gz` files)
     ///
     /// # Panics:
@@ -526,6 +591,7 @@ impl Pedigrees {
         comparisons: &mut PileupComparisons,
         input_vcf_path: &Path,
         maf: f32,
+        mask: &GenomeMask,
         threads: usize,
     ) -> Result<()> {
         let loc_file = || {
@@ -552,6 +618,16 @@ impl Pedigrees {
             }
             i += 1;
 
+            // --------------------- Skip masked positions, and keep them in memory for filtration.
+            if !mask.allows(&coordinate) {
+                for comparison in comparisons.iter().filter(|comparison| comparison.positions.contains(&coordinate)) {
+                    trace!("[{}] skip masked position at {coordinate}", comparison.get_pair());
+                    positions_to_delete.entry(comparison.get_pair().to_owned()).or_default().push(coordinate);
+                }
+                vcf_reader.next_line().with_loc(|| loc_coord(&coordinate))?;
+                continue 'line;
+            }
+
             // --------------------- Loop across comparisons.
             let mut rng = fastrand::Rng::with_seed(self.rng.get_seed()); // TEMP WORKAROUND (just to check that changes in test files is only due to seeding)
             for comparison in comparisons.iter() {
@@ -614,7 +690,7 @@ impl Pedigrees {
         }
 
         // --------------------- Filter out unwanted alleles.
-        Self::filter_pileup_positions(&mut positions_to_delete, comparisons);
+        Self::filter_pileup_positions(&mut positions_to_delete, comparisons)?;
         Ok(())
     }
 
@@ -688,8 +764,12 @@ impl Pedigrees {
         Ok(())
     }
 
+    /// Select the relationship scenario whose simulated avg. PWD distribution lies closest to our observed avg. PWD.
+    /// When an `observed_se` is provided, the observed block-jackknife standard error is combined with the simulated
+    /// standard deviation of each scenario when computing z-scores.
     fn get_most_likely_relationship_zscore(
         observed_avg_pwd: f64,
+        observed_se: Option<f64>,
         stats: &[(String, (f64, f64))],
     ) -> (Option<String>, f64, Vec<f64>) {
         let mut min_z_score: f64 = f64::MAX;
@@ -699,7 +779,7 @@ impl Pedigrees {
         let mut z_scores = Vec::new();
 
         for (scenario, (avg_avg_pwd, std_dev)) in stats.iter().rev() {
-            let scenario_z_score = (avg_avg_pwd - observed_avg_pwd) / std_dev;
+            let scenario_z_score = (avg_avg_pwd - observed_avg_pwd) / observed_se.map_or(*std_dev, |se| std_dev.hypot(se));
             z_scores.push(scenario_z_score);
             let is_more_likely = scenario_z_score.abs() < min_z_score.abs();
             if is_more_likely {
@@ -776,16 +856,62 @@ impl Pedigrees {
         Ok((most_likely_rel, per_class_svm_prob))
     }
 
+    /// Header of the `.result` output file.
+    fn results_header() -> String {
+        format!("{: <20} - {: <20} - {: <10} - {: <10} - {: <10} - {: <12} - {: <14} - {: <10} - {: <10} - {: <14} - {}",
+            "Pair_name", "Most_Likely_rel", "Corr.Overlap", "Corr.Sum.PWD", "Corr.Avg.PWD", "Corr.CI.95", "Corr.Avg.Phred", "Sim.Avg.PWD", "Min.Z_Score", "Corr.Het.Ratio", "Corr.JK.SE"
+        )
+    }
+
+    /// Format a `.result` row for a pair that was excluded from pedigree simulations because of an insufficient
+    /// overlap. Observed statistics are reported as-is, while simulation-derived columns are left undefined.
+    fn insufficient_result_row(comparison: &Comparison) -> String {
+        format!(
+            "{: <20} - {: <20} - {: <12.6} - {: <12.6} - {: <12.6} - {: <12.6} - {: <14.6} - {: <11.6} - {: >11.6} - {: >14.6} - {: >12.6}",
+            comparison.get_pair(),
+            INSUFFICIENT_OVERLAP_LABEL,
+            comparison.get_overlap(),
+            comparison.get_sum_pwd(),
+            comparison.get_avg_pwd(),
+            comparison.get_confidence_interval(),
+            comparison.get_avg_phred(),
+            f64::NAN,
+            f64::NAN,
+            HeterozygosityRatio(comparison.get_heterozygosity_ratio()),
+            comparison.get_jackknife_standard_error(),
+        )
+    }
+
+    /// Write a `.result` file solely containing pairs excluded from pedigree simulations. This is used when none of
+    /// the requested pairs reach the user-defined `--min-overlap` threshold.
+    /// # Arguments
+    /// - `insufficient`: pileup Comparisons excluded from simulations, because of an insufficient overlap.
+    /// - `output_file` : target output file where results are written.
+    pub fn write_insufficient_results(insufficient: &PileupComparisons, output_file: &str) -> Result<()> {
+        let loc_msg = "While attempting to write the results of pairs having an insufficient overlap";
+        let mut writer = GenericWriter::new(Some(output_file)).loc(loc_msg)?;
+        let rows = insufficient.iter().map(Self::insufficient_result_row);
+        writer.write_iter([Self::results_header()].into_iter().chain(rows)).loc(loc_msg)?;
+        Ok(())
+    }
+
     /// Recompute a corrected average-PWD of our real samples, and estimate the most likely relationship
     /// using our simulation results
     /// # Arguments
-    /// - `comparisons`   : pileup Comparisons of our real samples.
-    /// - `output_files`  : target output file where results are written.
+    /// - `comparisons`     : pileup Comparisons of our real samples.
+    /// - `output_files`    : target output file where results are written.
+    /// - `assign_method`   : method used to assign a most likely relationship.
+    /// - `jackknife_zscore`: account for the jackknife standard error of the observed avg. PWD when computing z-scores.
+    /// - `threads`         : number of worker threads.
+    /// - `insufficient`    : pileup Comparisons excluded from simulations, because of an insufficient overlap.
+    ///   These are reported with an `Insufficient_overlap` status.
     pub fn compute_results(
         &self,
         comparisons: &mut PileupComparisons,
+        insufficient: &PileupComparisons,
         output_file: &str,
         assign_method: RelAssignMethod,
+        jackknife_zscore: bool,
         threads: usize
     ) -> Result<()> {
         let loc_msg = "While attempting to compute corrected summary statistics";
@@ -795,11 +921,12 @@ impl Pedigrees {
         let mut writer = GenericWriter::new(Some(output_file)).loc(loc_msg)?;
 
         // ---- Print header and write to output_file
-        let simulation_header = format!("{: <20} - {: <20} - {: <10} - {: <10} - {: <10} - {: <12} - {: <14} - {: <10} - {: <10}",
-            "Pair_name", "Most_Likely_rel", "Corr.Overlap", "Corr.Sum.PWD", "Corr.Avg.PWD", "Corr.CI.95", "Corr.Avg.Phred", "Sim.Avg.PWD", "Min.Z_Score"
-        );
+        simulations_results.write().insert((0 as char).to_string(), Self::results_header());
 
-        simulations_results.write().insert((0 as char).to_string(), simulation_header);
+        // ---- Pairs which were excluded from simulations are reported as-is, without any relationship assignment.
+        for comparison in insufficient.iter() {
+            simulations_results.write().insert(comparison.get_pair().to_owned(), Self::insufficient_result_row(comparison));
+        }
 
         // ---- We might have removed some PWDs during simulations. We need to recompute the variance before printing out
         //      "corrected" 95% Confidence intervals.
@@ -832,6 +959,7 @@ impl Pedigrees {
                 scope.spawn_fifo(|_| {
                     let tx = tx; 
                     let observed_avg_pwd = comparison.get_avg_pwd();
+                    let observed_jk_se   = comparison.get_jackknife_standard_error();
                     let comparison_label = comparison.get_pair();
                     // ---- Set progress bar prefix
                     progress_bar.set_prefix(comparison_label.to_string());
@@ -857,7 +985,7 @@ impl Pedigrees {
 
                     // ---- Select the scenario having the least amount of Z-score with our observed avg.PWD
                     let (mut most_likely_rel, most_likely_avg_pwd, z_scores) =
-                        Self::get_most_likely_relationship_zscore(observed_avg_pwd, &stats);
+                        Self::get_most_likely_relationship_zscore(observed_avg_pwd, jackknife_zscore.then_some(observed_jk_se), &stats);
 
                     // ---- Get svm probabilities if this was assigned.
                     if matches!(assign_method, RelAssignMethod::SVM) {
@@ -911,9 +1039,10 @@ impl Pedigrees {
                     let corrected_overlap = comparison.get_overlap();
                     let corrected_ci = comparison.get_confidence_interval();
                     let corrected_phred = comparison.get_avg_phred();
+                    let corrected_het_ratio = HeterozygosityRatio(comparison.get_heterozygosity_ratio());
 
                     // ---- Preformat and log result to console.
-                    //"Pair_name", "Most_Likely_rel", "Corr.Overlap", "Corr.Sum.PWD", "Corr.Avg.PWD", "Corr.CI.95", "Corr.Avg.Phred", "Sim.Avg.PWD", "Min.Z_Score",
+                    //"Pair_name", "Most_Likely_rel", "Corr.Overlap", "Corr.Sum.PWD", "Corr.Avg.PWD", "Corr.CI.95", "Corr.Avg.Phred", "Sim.Avg.PWD", "Min.Z_Score", "Corr.Het.Ratio", "Corr.JK.SE"
                     let simulation_result = format!(
                         "{comparison_label: <20} - \
                         {assigned_rel: <20} - \
@@ -923,7 +1052,9 @@ impl Pedigrees {
                         {corrected_ci: <12.6} - \
                         {corrected_phred: <14.6} - \
                         {most_likely_avg_pwd: <11.6} - \
-                        {min_z_score: >11.6}"
+                        {min_z_score: >11.6} - \
+                        {corrected_het_ratio: >14.6} - \
+                        {observed_jk_se: >12.6}"
                     );
                     
                     let mut svm_row = format!("{comparison_label:<20} - {observed_avg_pwd:<12.6}");
@@ -224,7 +224,7 @@ mod tests {
             let parent_id = pedigree.edges.get(parent_rels[i]).expect("Parent should be retrievable").to;
             pedigree.individuals.get_ind_mut(parent_id).expect("Individual should be retrievable").alleles =  Some(parents_alleles[i]);
         }
-        pedigree.assign_alleles(iid, recombination_prob, 0, false, &mut rng)
+        pedigree.assign_alleles(iid, recombination_prob, 0, None, &mut rng)
     }
 
     fn run_all_allele_assignment_cases(recombination_prob: f64) -> Result<()> {
@@ -429,7 +429,7 @@ mod tests {
     fn alleles_assignment_founder() {
         let mut pedigree = common::mock_founder_pedigree("parent");
         let iid = pedigree.individuals.get_ind_from_label_mut("parent").expect("Individual should be retrievable").id;
-        let result = pedigree.assign_alleles(iid, 0.0, 0, false, &mut Rng::new());
+        let result = pedigree.assign_alleles(iid, 0.0, 0, None, &mut Rng::new());
         assert!(result.is_err());
     }
 
@@ -437,7 +437,7 @@ mod tests {
     fn alleles_assignments_unnassigned_parent_alleles(){
         let mut pedigree = common::mock_offspring_pedigree("offspring", None);
         let iid = pedigree.individuals.get_ind_from_label_mut("offspring").expect("Individual should be retrievable").id;
-        let result = pedigree.assign_alleles(iid, 0.0, 0, false, &mut Rng::new());
+        let result = pedigree.assign_alleles(iid, 0.0, 0, None, &mut Rng::new());
         assert!(result.is_err());
     }
 
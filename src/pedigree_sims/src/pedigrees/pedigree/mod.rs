@@ -233,9 +233,9 @@ impl Pedigree {
     }
 
     #[inline]
-    pub fn compute_offspring_alleles(&mut self, interval_prob_recomb: f64, pedigree_index: usize, xchr_mode: bool, rng: &mut fastrand::Rng) -> Result<()> {
+    pub fn compute_offspring_alleles(&mut self, interval_prob_recomb: f64, pedigree_index: usize, hemizygous: Option<Sex>, rng: &mut fastrand::Rng) -> Result<()> {
         for offspring_id in self.individuals.offsprings_ids_sorted() {
-            self.assign_alleles(offspring_id, interval_prob_recomb, pedigree_index, xchr_mode, rng)
+            self.assign_alleles(offspring_id, interval_prob_recomb, pedigree_index, hemizygous, rng)
                 .with_loc(|| format!("While attempting to assign the alleles of {}", 
                     self.individuals.get_ind(offspring_id).expect("Individual should be retrievable").label())
                 )?;
@@ -370,8 +370,11 @@ impl Pedigree {
     }
 
 
+    /// Assign the alleles of an offspring, by simulating meiosis for each of its parents.
+    /// - `hemizygous`: sex carrying a single copy of the simulated chromosome (e.g. males, for the X-chromosome of XY
+    ///   species), or `None` when simulating autosomes.
     #[inline]
-    pub fn assign_alleles (&mut self, iid: IndividualId, recombination_prob: f64, ped_idx: usize, xchr_mode: bool, rng: &mut Rng) -> Result<bool> {
+    pub fn assign_alleles (&mut self, iid: IndividualId, recombination_prob: f64, ped_idx: usize, hemizygous: Option<Sex>, rng: &mut Rng) -> Result<bool> {
         use IndividualError::{InvalidAlleleAssignment, MissingParents, MissingStrands};
         // ---- Ensure this method call is non-redundant.
         if self.individuals.get_ind(iid).expect("Individual should be retrievable").alleles.is_some() {
@@ -388,12 +391,16 @@ impl Pedigree {
             // ---- Assign parent genome if not previously generated
             let parent = self.individuals.get_ind(*parent_id).expect("Individual should be retrievable");
             if parent.alleles.is_none() {
-                self.assign_alleles(*parent_id, recombination_prob, ped_idx, xchr_mode, rng)?;
+                self.assign_alleles(*parent_id, recombination_prob, ped_idx, hemizygous, rng)?;
             }
 
             // ---- Check if recombination occured for each parent and update recombination tracker if so
             let parent = self.individuals.get_ind(*parent_id).expect("Individual should be retrievable");
-            if (!xchr_mode || parent.sex == Some(Sex::Female)) && rng.f64() < recombination_prob {
+            let can_recombine = match hemizygous {
+                None      => true,
+                Some(sex) => parent.sex.is_some_and(|parent_sex| parent_sex != sex && !parent_sex.is_unknown()),
+            };
+            if can_recombine && rng.f64() < recombination_prob {
                 let ind = self.individuals.get_ind(iid).expect("Individual should be retrievable");
                 trace!("- Cross-over occured in ped: {:<5} - ind: {} ({} {:?})", ped_idx, ind.label(), parent.label(), parent.sex);
                 let ind = self.individuals.get_ind_mut(iid).expect("Individual should be retrievable"); // TODO: find a way to appease the borrow checker and remove this redundant borrow
@@ -407,35 +414,35 @@ impl Pedigree {
             return Err(anyhow!(MissingStrands)).with_loc(||InvalidAlleleAssignment)
         };
 
-        let ind_alleles = if xchr_mode {
+        let ind_alleles = if let Some(hemizygous) = hemizygous {
             let mut alleles = [0u8; 2];
-            // ---- Find the index of both parents
-            let father_idx = parents.iter().position(|p| self.individuals.get_ind(*p).expect("Parent should be retrievable").sex == Some(Sex::Male)).expect("No parent found..");
-            let mother_idx = (father_idx + 1 ) % 2;
-
-            // -- assign maternal strand
-            let mat_strand_currently_recombining = ind.currently_recombining[mother_idx];
-            alleles[mother_idx] = self.individuals.get_ind(parents[mother_idx]).expect("Parent should be retrievable").meiosis(strands[mother_idx], mat_strand_currently_recombining);
-
-            // -- Assign paternal strand
-            let pat_strand_currently_recombining = ind.currently_recombining[father_idx];
-            alleles[father_idx] = match ind.sex {
-                Some(Sex::Male)           => Ok(alleles[mother_idx]), // If the descendant is a male, alleles are exclusively from the mother
-                Some(Sex::Female)         => Ok(self.individuals.get_ind(parents[father_idx]).expect("Parent should be retrievable").meiosis(strands[father_idx], pat_strand_currently_recombining)),
+            // ---- Find the index of both parents. (i.e. father and mother for XY species, mother and father for ZW)
+            let hemi_idx = parents.iter().position(|p| self.individuals.get_ind(*p).expect("Parent should be retrievable").sex == Some(hemizygous)).expect("No parent found..");
+            let homo_idx = (hemi_idx + 1 ) % 2;
+
+            // -- assign the strand of the homogametic parent (i.e. mother for XY species)
+            let homo_strand_currently_recombining = ind.currently_recombining[homo_idx];
+            alleles[homo_idx] = self.individuals.get_ind(parents[homo_idx]).expect("Parent should be retrievable").meiosis(strands[homo_idx], homo_strand_currently_recombining);
+
+            // -- Assign the strand of the hemizygous parent (i.e. father for XY species)
+            let hemi_strand_currently_recombining = ind.currently_recombining[hemi_idx];
+            alleles[hemi_idx] = match ind.sex {
                 Some(Sex::Unknown) | None => Err(IndividualError::UnknownOrMissingSex).loc("While attempting to assign alleles during X-chromosome-mode"),
+                Some(sex) if sex == hemizygous => Ok(alleles[homo_idx]), // If the descendant is hemizygous, alleles are exclusively from the homogametic parent
+                Some(_)                   => Ok(self.individuals.get_ind(parents[hemi_idx]).expect("Parent should be retrievable").meiosis(strands[hemi_idx], hemi_strand_currently_recombining)),
             }?;
 
             // ---- Sanity checks
-            if ind.sex == Some(Sex::Male) && alleles[0] != alleles[1] {
-                // Male individuals are not expected to be heterozygous during X-chromosome simulations.
+            if ind.sex == Some(hemizygous) && alleles[0] != alleles[1] {
+                // Hemizygous individuals are not expected to be heterozygous during X-chromosome simulations.
                 return Err(IndividualError::SpuriousAlleleAssignment{alleles})
-                    .loc("Male Individual is heterozygous while in X-chromosome mode")
+                    .with_loc(|| format!("Hemizygous ({hemizygous}) Individual is heterozygous while in X-chromosome mode"))
             }
 
-            if ind.currently_recombining[father_idx] {
-                // Fathers are not expected to recombine during X-chromosome simulations.
+            if ind.currently_recombining[hemi_idx] {
+                // Hemizygous parents are not expected to recombine during X-chromosome simulations.
                 return Err(IndividualError::InvalidOrSpuriousRecombinationEvent)
-                    .loc("Father is recombining while in X-chromosome-mode")
+                    .with_loc(|| format!("Hemizygous parent ({hemizygous}) is recombining while in X-chromosome-mode"))
             }
 
             Some(alleles)
@@ -452,8 +459,11 @@ impl Pedigree {
     #[inline]
     pub fn assign_random_sex(&mut self, id: IndividualId) -> Result<bool> {
         use IndividualError::InvalidSexAssignment;
-        // ---- Ensure this method call is non-redundant
-        if self.individuals.get_ind(id).expect("Individual should be retrievable").sex.is_some() {
+        // ---- Ensure this method call is non-redundant. Individuals whose sex was fixed beforehand (see
+        //      `assign_compared_sexes()`) may still have parents of unknown sex.
+        let sex_of = |id| self.individuals.get_ind(id).expect("Individual should be retrievable").sex;
+        let parents_assigned = self.get_parents_ids(id).into_iter().flatten().all(|parent| sex_of(parent).is_some());
+        if sex_of(id).is_some() && parents_assigned {
             return Ok(false)
         }
 
@@ -481,9 +491,11 @@ impl Pedigree {
             }
         }
 
-        // ---- Randomly assign sex of the considered individual
+        // ---- Randomly assign sex of the considered individual, if it was not fixed beforehand.
         let ind = self.individuals.get_ind_mut(id).expect("Individual should be retrievable");
-        ind.sex = Some(Sex::random());
+        if ind.sex.is_none() {
+            ind.sex = Some(Sex::random());
+        }
 
         Ok(true)
     }
@@ -491,6 +503,42 @@ impl Pedigree {
     pub fn all_sex_assigned(&self) -> bool {
         self.individuals.inner.values().all(|ind| ind.is_sex_assigned())
     }
+
+    /// Fix the sex of the individuals targeted by each pedigree comparison, according to the known sex of the two
+    /// samples being compared (e.g. `sexes[0]` is assigned to the first individual of every comparison). Individuals
+    /// whose sex is already defined are left untouched, and no sex is assigned if it matches that of the individual's
+    /// spouse. Returns `false` if any of the requested sexes could not be assigned.
+    pub fn assign_compared_sexes(&mut self, sexes: [Option<Sex>; 2]) -> bool {
+        let mut satisfied = true;
+        for pair in self.comparisons.iter().map(|comparison| comparison.pair).collect::<Vec<_>>() {
+            for (id, sex) in pair.into_iter().zip(sexes) {
+                if let Some(sex) = sex {
+                    satisfied &= self.assign_sex(id, sex);
+                }
+            }
+        }
+        satisfied
+    }
+
+    /// Assign the sex of an individual, unless it is already defined, or conflicts with that of its spouse(s).
+    fn assign_sex(&mut self, id: IndividualId, sex: Sex) -> bool {
+        let sex_of = |id| self.individuals.get_ind(id).expect("Individual should be retrievable").sex;
+        if let Some(current) = sex_of(id) {
+            return current == sex
+        }
+
+        let conflicting_spouse = self.individuals.offsprings_ids().into_iter()
+            .filter_map(|offspring_id| self.get_parents_ids(offspring_id))
+            .filter(|parents| parents.contains(&id))
+            .flatten()
+            .any(|parent_id| parent_id != id && sex_of(parent_id) == Some(sex));
+        if conflicting_spouse {
+            return false
+        }
+
+        self.individuals.get_ind_mut(id).expect("Individual should be retrievable").sex = Some(sex);
+        true
+    }
     
     pub fn _display_comparison(&self, f: &mut Formatter<'_>, comp: &PedComparison) -> fmt::Result {
             let default_tag = SampleTag::new("None", None, None);
@@ -584,7 +632,7 @@ mod test {
     fn meiosis_assign_alleles_empty_strands(){
         let mut pedigree = test_pedigree_set();
         let offspr = pedigree.individuals.get_ind_id("offspr").expect("Cannot extract offspr");
-        pedigree.assign_alleles(offspr, 0.0, 0, false, &mut fastrand::Rng::new()).expect("Failed to assign alleles");
+        pedigree.assign_alleles(offspr, 0.0, 0, None, &mut fastrand::Rng::new()).expect("Failed to assign alleles");
     }
 
     #[test]
@@ -595,9 +643,9 @@ mod test {
         offspr.strands   = Some([0, 0]);
         
         let offspr_id   = pedigree.individuals.get_ind_id("offspr").expect("Cannot extract offspr");
-        let output = pedigree.assign_alleles(offspr_id, 0.0, 0, false, &mut rng).expect("Failed to assign alleles");
+        let output = pedigree.assign_alleles(offspr_id, 0.0, 0, None, &mut rng).expect("Failed to assign alleles");
         assert!(output);
-        let output = pedigree.assign_alleles(offspr_id, 0.0, 0, false, &mut rng).expect("Failed to assign alleles");
+        let output = pedigree.assign_alleles(offspr_id, 0.0, 0, None, &mut rng).expect("Failed to assign alleles");
         assert!(!output);
     }
 
@@ -607,7 +655,7 @@ mod test {
         let offspr_id    = pedigree.individuals.get_ind_id("offspr").expect("Cannot extract offspr");
         let offspr   = pedigree.individuals.get_ind_mut(offspr_id).expect("Cannot extract offspr");
         offspr.strands   = Some([0, 0]);
-        pedigree.assign_alleles(offspr_id, 0.0, 0, false, &mut fastrand::Rng::new()).expect("Failed to assign alleles");
+        pedigree.assign_alleles(offspr_id, 0.0, 0, None, &mut fastrand::Rng::new()).expect("Failed to assign alleles");
         assert_eq!(pedigree.individuals.get_ind(offspr_id).expect("Individual should be retrievable").alleles, Some([0, 1]));
     }
 
@@ -618,19 +666,38 @@ mod test {
         let offspr       = pedigree.individuals.get_ind_mut(offspr_id).expect("Cannot extract offspr");
         offspr.strands   = Some([1, 1]);
 
-        pedigree.assign_alleles(offspr_id, 0.0, 0, false, &mut fastrand::Rng::new()).expect("Failed to assign alleles");
+        pedigree.assign_alleles(offspr_id, 0.0, 0, None, &mut fastrand::Rng::new()).expect("Failed to assign alleles");
         assert_eq!(pedigree.individuals.get_ind(offspr_id).expect("Individual should be retrievable").alleles, Some([1, 0]));
         assert_eq!(pedigree.individuals.get_ind(offspr_id).expect("Individual should be retrievable").currently_recombining, [false, false]);
 
     }
 
+    #[test]
+    fn meiosis_zw_sex_chromosome() {
+        // ---- ZW species: mothers are hemizygous, and should neither recombine, nor transmit their Z to daughters.
+        for (offspring_sex, want) in [(Sex::Female, [1, 1]), (Sex::Male, [1, 0])] {
+            let mut pedigree = test_pedigree_set();
+            for (label, sex) in [("father", Sex::Male), ("mother", Sex::Female), ("offspr", offspring_sex)] {
+                pedigree.individuals.get_ind_from_label_mut(label).expect("Cannot extract individual").sex = Some(sex);
+            }
+            pedigree.individuals.get_ind_from_label_mut("mother").expect("Cannot extract mother").set_alleles([0, 0]);
+            let offspr_id  = pedigree.individuals.get_ind_id("offspr").expect("Cannot extract offspr");
+            pedigree.individuals.get_ind_mut(offspr_id).expect("Cannot extract offspr").strands = Some([0, 0]);
+
+            pedigree.assign_alleles(offspr_id, 1.0, 0, Some(Sex::Female), &mut fastrand::Rng::new()).expect("Failed to assign alleles");
+            let offspr = pedigree.individuals.get_ind(offspr_id).expect("Individual should be retrievable");
+            assert_eq!(offspr.alleles, Some(want));
+            assert_eq!(offspr.currently_recombining, [true, false]);
+        }
+    }
+
     #[test]
     fn meiosis_check_recombination() {
         let mut pedigree = test_pedigree_set();
         let offspr_id    = pedigree.individuals.get_ind_id("offspr").expect("Cannot extract offspr");
         let offspr       = pedigree.individuals.get_ind_mut(offspr_id).expect("Cannot extract offspr");
         offspr.strands   = Some([0, 1]);
-        pedigree.assign_alleles(offspr_id, 1.0, 0, false, &mut fastrand::Rng::new()).expect("Failed to assign alleles");
+        pedigree.assign_alleles(offspr_id, 1.0, 0, None, &mut fastrand::Rng::new()).expect("Failed to assign alleles");
 
         assert_eq!(pedigree.individuals.get_ind(offspr_id).expect("Individual should be retrievable").alleles, Some([1, 1]));
         assert_eq!(pedigree.individuals.get_ind(offspr_id).expect("Individual should be retrievable").currently_recombining, [true, true]);
@@ -667,6 +734,23 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn assign_compared_sexes() -> Result<()> {
+        let mut pedigree = test_pedigree_set();
+        pedigree.add_comparison("parent-offspring", ["father", "offspr"])?;
+        pedigree.add_comparison("unrelated", ["father", "mother"])?;
+
+        // ---- Both samples are male: the mother cannot be assigned the same sex as her spouse.
+        assert!(!pedigree.assign_compared_sexes([Some(Sex::Male), Some(Sex::Male)]));
+        let sex = |pedigree: &Pedigree, label| pedigree.individuals.get_ind_from_label(label).expect("Individual should be retrievable").sex;
+        assert_eq!([sex(&pedigree, "father"), sex(&pedigree, "offspr"), sex(&pedigree, "mother")], [Some(Sex::Male), Some(Sex::Male), None]);
+
+        // ---- Remaining individuals are then assigned at random, while preserving fixed sexes.
+        pedigree.assign_random_sexes()?;
+        assert_eq!([sex(&pedigree, "father"), sex(&pedigree, "offspr"), sex(&pedigree, "mother")], [Some(Sex::Male), Some(Sex::Male), Some(Sex::Female)]);
+        Ok(())
+    }
+
     #[test]
     fn clear_alleles() {
         let mut pedigree = test_pedigree_set();
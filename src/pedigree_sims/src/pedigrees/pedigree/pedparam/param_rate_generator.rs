@@ -24,31 +24,19 @@ impl<T: Display> Display for ParamRateGenerator<T> {
 }
 
 impl<T: Display> ParamRateGenerator<T> {
-    /// Instantiate a new generartor from a given user-input.
+    /// Instantiate a new generator from the user-provided rate (or rate range) of each compared individual.
     /// # Arguments:
-    /// - `rates`   : vector of user provided rate (e.g. contamination rate, sequencing error rate, etc.)
-    ///   each inner vector must either be of len 1 or 2. any value > 2 will cause a panic.
-    /// 
-    /// - `indices` : pileup indices of the two individuals being compared. (See `pwd_from_stdin::Comparison::get_pair_indices()`)
+    /// - `rates`: user provided rate (e.g. contamination rate, sequencing error rate, etc.) of the two individuals
+    ///   being compared. each slice must either be of len 1 or 2. any value > 2 will cause a panic.
     /// 
     /// # Panics:
     /// - whenever `rates[i].len()` > 2
-    /// - if self.inner.len() > 2
-    /// 
-    pub fn from_user_input(rates: &[Vec<T>], indices: [usize; 2]) -> Self 
+    pub fn from_rates(rates: [&[T]; 2]) -> Self
     where 
         T: 'static + Copy + SampleUniform + PartialOrd + Debug
     {
-        let mut inner = Vec::new();
         // ---- Instantiate two PedParam. One for each compaired individual. 
-        for index in &indices {
-            let rate_idx = index % rates.len() ; // Wrap around values if the user did not provide enough ranges!
-            let rate_gen: Box<dyn PedParam<T>> = <dyn PedParam<T>>::from_vec(&rates[rate_idx]);
-            inner.push(rate_gen);
-        }
-        let inner: [Box<dyn PedParam<T>>; 2 ] = inner.try_into()
-            .expect("Invalid ParamRateGenerator array length.");
-            
+        let inner = rates.map(<dyn PedParam<T>>::from_vec);
         Self {inner}
     }
 
@@ -15,6 +15,8 @@ use std::{
 use grups_io::read::PanelReader;
 use grups_io::read::SampleTag;
 
+use genome::Sex;
+
 use located_error::prelude::*;
 
 /// A vector of pedigree simulation replicates. This struct is generally assigned to a given Pileup-Comparison.
@@ -82,6 +84,16 @@ impl PedigreeReps {
         })
     }
 
+    /// Fix the sex of the compared individuals of each pedigree replicate, according to the known sex of the two
+    /// samples being compared. Returns `false` if any of the requested sexes could not be assigned.
+    pub fn assign_compared_sexes(&mut self, sexes: [Option<Sex>; 2]) -> bool {
+        let mut satisfied = true;
+        for pedigree in &mut self.inner {
+            satisfied &= pedigree.assign_compared_sexes(sexes);
+        }
+        satisfied
+    }
+
     pub fn assign_random_sex(&mut self) -> Result<()> {
         self.inner.iter_mut().enumerate().try_for_each(|(i, pedigree)| {
             pedigree.assign_random_sexes().with_loc(|| format!("While attempting to randomly assign sex of pedigree n°{i}"))
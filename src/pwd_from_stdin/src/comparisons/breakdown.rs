@@ -0,0 +1,60 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{PAIRS_FORMAT_LEN, COUNT_FORMAT_LEN, AVERG_FORMAT_LEN, DISPL_SEP, FLOAT_FORMAT_PRECISION};
+
+/// Summary statistics of the pairwise differences observed between a pair of individuals, within a given subset of
+/// their overlapping SNPs (e.g. a single chromosome, or a single substitution class).
+/// - `pair`          : label of the compared pair of individuals.
+/// - `category`      : label of the considered subset (chromosome name, substitution class, etc.)
+/// - `overlap`       : number of overlapping SNPs within this subset.
+/// - `sum_pwd`       : sum of local pairwise differences within this subset.
+/// - `standard_error`: jackknife standard error of the average PWD within this subset.
+#[derive(Debug)]
+pub struct PwdBreakdown {
+    pub pair          : String,
+    pub category      : String,
+    pub overlap       : usize,
+    pub sum_pwd       : f64,
+    pub standard_error: f64,
+}
+
+impl PwdBreakdown {
+    /// Average pairwise difference within this subset.
+    #[must_use]
+    pub fn avg_pwd(&self) -> f64 {
+        self.sum_pwd / self.overlap as f64
+    }
+
+    /// Format a header line, using `category` as the name of the subset column.
+    #[must_use]
+    pub fn header(category: &str) -> String {
+        format!(
+            "{: <PAIRS_FORMAT_LEN$}{DISPL_SEP}\
+             {: <COUNT_FORMAT_LEN$}{DISPL_SEP}\
+             {: <COUNT_FORMAT_LEN$}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$}",
+            "Pair_name", category, "Overlap", "Sum.PWD", "Avg.PWD", "Jackknife.SE"
+        )
+    }
+}
+
+impl Display for PwdBreakdown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f,
+            "{: <PAIRS_FORMAT_LEN$}{DISPL_SEP}\
+             {: <COUNT_FORMAT_LEN$}{DISPL_SEP}\
+             {: <COUNT_FORMAT_LEN$}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$.1}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}",
+            self.pair,
+            self.category,
+            self.overlap,
+            self.sum_pwd + 0.0,
+            self.avg_pwd() + 0.0,
+            self.standard_error + 0.0
+        )
+    }
+}
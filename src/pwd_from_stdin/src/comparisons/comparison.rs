@@ -1,12 +1,15 @@
-use std::{fmt::{self, Formatter, Display}, collections::BTreeSet};
+use std::{fmt::{self, Formatter, Display}, collections::BTreeMap, io::{self, Read, Write}, path::Path};
 
 use genome::jackknife::{JackknifeBlocks, JackknifeEstimates};
-use genome::Genome;
+use genome::coordinate::Coordinate;
+use genome::snp::SubstitutionClass;
+use grups_io::read::likelihoods::GenotypePosteriors;
 use located_error::LocatedError;
 
 use crate::pileup::{Pileup, Line};
 use super::ComparisonError;
-use super::{Individual, Pwd};
+use super::{Individual, Pwd, PwdBreakdown, PwdStore};
+use super::snapshot::{SnapshotReader, SnapshotWriter};
 use super::{PAIRS_FORMAT_LEN, COUNT_FORMAT_LEN, AVERG_FORMAT_LEN, DISPL_SEP, FLOAT_FORMAT_PRECISION};
 
 use anyhow::Result;
@@ -28,13 +31,10 @@ impl Variance {
     }
 
     /// Unbiased, two-pass variance estimation algorithm 
-    pub fn update_from_iter(&mut self, avg_pwd: f64, pwds: &BTreeSet<Pwd>) {
-        self.std_dev_sum = pwds.iter()
-            .map(|pwd| (avg_pwd - pwd.pwd).powf(2.0))
-            .sum::<f64>();
-
-        self.n = pwds.len();
-
+    pub fn update_from_iter(&mut self, avg_pwd: f64, pwds: impl Iterator<Item = Pwd>) {
+        (self.std_dev_sum, self.n) = pwds
+            .map(|pwd| (avg_pwd - pwd.avg_local_pwd()).powf(2.0))
+            .fold((0.0, 0), |(sum, n), deviation| (sum + deviation, n + 1));
     }
 
     /// Single-pass variance estimation algorithm.
@@ -74,6 +74,8 @@ impl Variance {
 /// 
 /// - `blocks`          : genome blocks used for jackknife resampling.
 /// 
+/// - `positions`       : columnar store of every overlapping SNP, along with its local pairwise difference.
+/// 
 /// # Traits : `Debug`
 /// 
 /// # @TODO:
@@ -86,12 +88,12 @@ pub struct Comparison {
     self_comparison : bool,
     variance        : Variance,
     pub blocks      : JackknifeBlocks,
-    pub positions   : BTreeSet<Pwd>
+    pub positions   : PwdStore
 }
 
 impl Comparison {
     #[must_use]
-    pub fn new(mut pair: [Individual; 2], self_comparison: bool, genome: &Genome, blocksize: u32) -> Self {
+    pub fn new(mut pair: [Individual; 2], self_comparison: bool, blocks: JackknifeBlocks) -> Self {
         let label = format!("{}-{}", &pair[0].name, &pair[1].name);
         if self_comparison {
             for individual in &mut pair {
@@ -103,7 +105,7 @@ impl Comparison {
                 }
             }
         }
-        Comparison {pair, label, self_comparison, variance: Variance::new(), blocks: JackknifeBlocks::new(genome, blocksize), positions: BTreeSet::new()}
+        Comparison {pair, label, self_comparison, variance: Variance::new(), blocks, positions: PwdStore::new()}
     }
 
     #[must_use]
@@ -118,30 +120,68 @@ impl Comparison {
         Ok(left_ok && right_ok)
     }
 
+    /// Retrieve the genotype posteriors of both individuals from a given VCF record. Returns `None` if any of the two
+    /// genotypes is missing.
+    pub fn satisfiable_posteriors<'a>(&self, posteriors: &'a [Option<GenotypePosteriors>]) -> Result<Option<[&'a GenotypePosteriors; 2]>, ComparisonError> {
+        let left  = self.pair[0].genotype_posteriors(posteriors)?;
+        let right = self.pair[1].genotype_posteriors(posteriors)?;
+        Ok(left.zip(right).map(|(left, right)| [left, right]))
+    }
+
     /// Compare our two individuals at the given SNP position ; increment the appropriate counters after the comparison 
-    /// has been made.
-    pub fn compare(&mut self, line: &Line) -> Result<()> {
-        //let pwd = Pwd::one(line.coordinate, &random_nucl);
-        let loc_msg = || format!("While comparing pair {:?}", self.pair);
+    /// has been made. `class` is the substitution class of the SNP, if known.
+    pub fn compare(&mut self, line: &Line, class: Option<SubstitutionClass>) -> Result<()> {
         let pwd = match self.self_comparison {
             true  => Pwd::deterministic_self(line, &self.pair),
             false => Pwd::deterministic_pairwise(line, &self.pair)
         };
+        self.add_pwd(pwd.with_class(class))
+    }
 
+    /// Compare our two individuals at the given SNP position, by randomly sampling `draws` times (at least once) a single
+    /// nucleotide per individual (or two nucleotides without replacement, in the case of self-comparisons) ; increment
+    /// the appropriate counters after the comparison has been made.
+    /// 
+    /// # Errors
+    /// - `InsufficientNucleotides` if any of the two individuals does not carry enough nucleotides to be sampled.
+    pub fn compare_random(&mut self, line: &Line, class: Option<SubstitutionClass>, draws: u16, rng: &mut fastrand::Rng) -> Result<()> {
+        let mut sample = || match self.self_comparison {
+            true  => line.random_sample_self(self.pair[0].index, rng),
+            false => line.random_sample_pair(&self.pair, rng),
+        }.ok_or(ComparisonError::InsufficientNucleotides(line.coordinate))
+         .with_loc(|| format!("While comparing pair {:?}", self.pair));
+
+        let mut pwd = Pwd::one(line.coordinate, line.reference, &sample()?);
+        for _ in 1..draws {
+            pwd.update(line.reference, &sample()?);
+        }
+        self.add_pwd(pwd.with_class(class))
+    }
+
+    /// Compare our two individuals at the given SNP position, using their genotype posteriors instead of observed
+    /// nucleotides ; increment the appropriate counters after the comparison has been made.
+    pub fn compare_posteriors(&mut self, coordinate: Coordinate, posteriors: [&GenotypePosteriors; 2], class: Option<SubstitutionClass>) -> Result<()> {
+        let pwd = Pwd::from_posteriors(coordinate, posteriors, self.self_comparison);
+        self.add_pwd(pwd.with_class(class))
+    }
+
+    /// Record a local pairwise difference, along with its corresponding jackknife block.
+    fn add_pwd(&mut self, pwd: Pwd) -> Result<()> {
+        let loc_msg = || format!("While comparing pair {:?}", self.pair);
         let current_block = self.blocks
-            .find_block(&line.coordinate)
-            .ok_or(ComparisonError::MissingBlock(line.coordinate)).with_loc(loc_msg)?;
+            .find_block(&pwd.coordinate)
+            .ok_or(ComparisonError::MissingBlock(pwd.coordinate)).with_loc(loc_msg)?;
 
         current_block.add_count();
         current_block.add_pwd(pwd.avg_local_pwd());
 
-        self.positions.insert(pwd);
+        self.positions.insert(pwd).with_loc(loc_msg)?;
         Ok(())
     }
 
     pub fn get_sum_pwd(&self) -> f64 {
         self.positions.iter()
-            .map(Pwd::avg_local_pwd)
+            .map(|pwd| pwd.avg_local_pwd())
             .sum::<f64>()
     }
 
@@ -158,7 +198,7 @@ impl Comparison {
 
     fn get_sum_phred(&self) -> f64 {
         self.positions.iter()
-            .map(Pwd::compute_avg_phred)
+            .map(|pwd| pwd.compute_avg_phred())
             .sum::<f64>()
     }
     /// Getter for the average pairwise phred score across our overlapping snps.
@@ -180,6 +220,102 @@ impl Comparison {
         self.blocks.compute_unequal_delete_m_pseudo_values(self.get_sum_pwd(), self.positions.len() as u32)
     }
 
+    /// Block-jackknife standard error of the observed avg. PWD. Unlike `get_confidence_interval()`, this estimate
+    /// accounts for the linkage between neighbouring SNPs.
+    #[must_use]
+    pub fn get_jackknife_standard_error(&self) -> f64 {
+        self.get_jackknife_estimates().standard_error()
+    }
+
+    /// Only retain the overlapping SNPs satisfying the predicate `f`, and recount the pairwise differences of each
+    /// jackknife block accordingly.
+    /// 
+    /// # Errors
+    /// - if the filtered positions cannot be spilled back to disk.
+    pub fn retain_positions(&mut self, f: impl FnMut(&Pwd) -> bool) -> Result<()> {
+        self.positions.retain(f).with_loc(|| format!("While filtering the positions of pair {}", self.label))?;
+        self.blocks = self.blocks.tally(self.positions.iter().map(|pwd| (pwd.coordinate, pwd.avg_local_pwd())));
+        Ok(())
+    }
+
+    /// Spill the overlapping SNPs of this pair to a memory-mapped temporary file within `dir`, instead of keeping them
+    /// in memory. Only the chromosome currently being compared remains in memory (see `PwdStore`).
+    /// 
+    /// # Errors
+    /// - if the temporary file cannot be created or written.
+    pub fn spill_positions(&mut self, dir: &Path) -> Result<()> {
+        self.positions.spill_to(dir).with_loc(|| format!("While spilling the positions of pair {}", self.label))
+    }
+
+    /// Replace the jackknife blocks of this pair with blocks containing an equal number of overlapping SNPs. This
+    /// prevents patchy coverage from generating badly unbalanced blocks.
+    pub fn split_blocks_by_snp_count(&mut self, snps_per_block: u32) {
+        self.blocks = JackknifeBlocks::from_snp_counts(self.positions.iter().map(|pwd| pwd.coordinate), snps_per_block)
+            .tally(self.positions.iter().map(|pwd| (pwd.coordinate, pwd.avg_local_pwd())));
+    }
+
+    /// Write the individuals, jackknife blocks and overlapping SNPs of this pair within an observations snapshot.
+    pub(super) fn write_snapshot<W: Write>(&self, writer: &mut SnapshotWriter<W>) -> io::Result<()> {
+        writer.u8(u8::from(self.self_comparison))?;
+        self.pair.iter().try_for_each(|individual| writer.individual(individual))?;
+        writer.blocks(&self.blocks)?;
+        writer.usize(self.positions.len())?;
+        self.positions.iter().try_for_each(|pwd| writer.pwd(&pwd))
+    }
+
+    /// Restore a pair from an observations snapshot. Overlapping SNPs are spilled within `spill_dir`, if provided.
+    pub(super) fn read_snapshot<R: Read>(reader: &mut SnapshotReader<R>, spill_dir: Option<&Path>) -> Result<Self> {
+        let self_comparison = reader.u8()? != 0;
+        let pair = [reader.individual()?, reader.individual()?];
+        let mut comparison = Self::new(pair, self_comparison, reader.blocks()?);
+        if let Some(dir) = spill_dir {
+            comparison.spill_positions(dir)?;
+        }
+        for _ in 0..reader.usize()? {
+            comparison.positions.insert(reader.pwd()?)?;
+        }
+        comparison.positions.flush()?;
+        Ok(comparison)
+    }
+
+    /// Break down the observed pairwise differences of this pair per chromosome. Jackknife standard errors are computed
+    /// using the blocks of each chromosome.
+    #[must_use]
+    pub fn get_chromosome_breakdown(&self) -> Vec<PwdBreakdown> {
+        let mut chromosomes = BTreeMap::new();
+        for pwd in self.positions.iter() {
+            let (overlap, sum_pwd) = chromosomes.entry(pwd.coordinate.chromosome).or_insert((0, 0.0));
+            *overlap += 1;
+            *sum_pwd += pwd.avg_local_pwd();
+        }
+
+        chromosomes.into_iter().map(|(chromosome, (overlap, sum_pwd))| {
+            let standard_error = self.blocks.compute_chromosome_pseudo_values(chromosome)
+                .map_or(f64::NAN, |estimates| estimates.standard_error());
+            PwdBreakdown{pair: self.label.clone(), category: chromosome.to_string(), overlap, sum_pwd, standard_error}
+        }).collect()
+    }
+
+    /// Break down the observed pairwise differences of this pair into transitions and transversions. SNPs of unknown
+    /// substitution class are ignored. Classes lacking any overlapping SNP are skipped.
+    #[must_use]
+    pub fn get_class_breakdown(&self) -> Vec<PwdBreakdown> {
+        [SubstitutionClass::Transition, SubstitutionClass::Transversion].into_iter().filter_map(|class| {
+            let positions = self.positions.iter().filter(|pwd| pwd.class == Some(class));
+            let overlap   = positions.clone().count();
+            if overlap == 0 {
+                return None
+            }
+            let sum_pwd = positions.clone().map(|pwd| pwd.avg_local_pwd()).sum::<f64>();
+
+            #[allow(clippy::cast_possible_truncation)] // Sample definitly has less than 4_294_967_295 positions...
+            let standard_error = self.blocks.tally(positions.map(|pwd| (pwd.coordinate, pwd.avg_local_pwd())))
+                .compute_unequal_delete_m_pseudo_values(sum_pwd, overlap as u32)
+                .standard_error();
+            Some(PwdBreakdown{pair: self.label.clone(), category: class.to_string(), overlap, sum_pwd, standard_error})
+        }).collect()
+    }
+
     /// Optain the 95% confidence interval for the observed avg. PWD
     #[must_use]
     pub fn get_confidence_interval(&self) -> f64 {
@@ -188,15 +324,42 @@ impl Comparison {
 
     /// Compute the variance and standard deviation for the observed avg. PWD using a two-pass method.
     pub fn update_variance_unbiased(&mut self) {
-        self.variance.update_from_iter(self.get_avg_pwd(), &self.positions);
+        self.variance.update_from_iter(self.get_avg_pwd(), self.positions.iter());
+    }
+
+    fn get_sum_hom_alt(&self) -> f64 {
+        self.positions.iter()
+            .map(|pwd| pwd.avg_local_hom_alt())
+            .sum::<f64>()
+    }
+
+    /// Getter for the heterozygosity ratio of this pair, i.e. the ratio of pairwise differences over shared
+    /// non-reference observations. Low-quality pairs tend to display an excess of mismatches relative to their shared
+    /// non-reference alleles, while genuinely distant pairs do not.
+    /// 
+    /// Returns `NaN` if the pair does not share any non-reference observation (e.g. when reference alleles are unknown).
+    #[must_use]
+    pub fn get_heterozygosity_ratio(&self) -> f64 {
+        let sum_hom_alt = self.get_sum_hom_alt();
+        match sum_hom_alt > 0.0 {
+            true  => self.get_sum_pwd() / sum_hom_alt,
+            false => f64::NAN,
+        }
     }
+}
 
-    //// WIP: heterozygocity ratio
-    //pub fn get_heterozygocity_ratio(&self) -> f64 {
-    //    let sum_hom_alt = self.positions.iter().map(|pwd| pwd.hom_alt_sum).sum::<f64>();
-    //    self.get_sum_pwd() / sum_hom_alt
-    //}
+/// Display wrapper of a heterozygosity ratio, printing `NA` in place of undefined (`NaN`) values. Width, alignment
+/// and precision flags are forwarded.
+#[derive(Debug, Clone, Copy)]
+pub struct HeterozygosityRatio(pub f64);
 
+impl Display for HeterozygosityRatio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0.is_nan() {
+            true  => f.pad("NA"),
+            false => Display::fmt(&(self.0 + 0.0), f),
+        }
+    }
 }
 
 impl Display for Comparison {
@@ -207,13 +370,17 @@ impl Display for Comparison {
              {: <AVERG_FORMAT_LEN$.1}{DISPL_SEP}\
              {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
              {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
              {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}",
             self.get_pair(),
             self.positions.len(),
             self.get_sum_pwd() + 0.0,
             self.get_avg_pwd() + 0.0,
             self.get_confidence_interval() + 0.0,
-            self.get_avg_phred() +0.0
+            self.get_avg_phred() +0.0,
+            HeterozygosityRatio(self.get_heterozygosity_ratio()),
+            self.get_jackknife_standard_error() + 0.0
         )
     }
 }
@@ -265,6 +432,105 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn satisfiable_posteriors() -> Result<()> {
+        let comparison = common::mock_comparison(false);
+        let het = GenotypePosteriors{hom_ref: 0.0, het: 1.0, hom_alt: 0.0};
+        assert_eq!(comparison.satisfiable_posteriors(&[Some(het), Some(het)])?, Some([&het, &het]));
+        assert_eq!(comparison.satisfiable_posteriors(&[Some(het), None])?, None);
+        assert!(comparison.satisfiable_posteriors(&[Some(het)]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn compare_random() -> Result<()> {
+        let draws = 8;
+        let mut rng = fastrand::Rng::with_seed(42);
+
+        // Fixed nucleotides: every draw should agree with the deterministic estimate.
+        let mut comparison = common::mock_comparison(false);
+        comparison.compare_random(&Line::new("22\t10\tC\t2\tTT\tJJ\t2\t..\tJJ", true)?, None, draws, &mut rng)?;
+        comparison.compare_random(&Line::new("22\t20\tC\t2\tTT\tJJ\t2\tTT\tJJ", true)?, None, draws, &mut rng)?;
+        assert_eq!(comparison.get_sum_pwd(), 1.0);
+        assert_eq!(comparison.get_overlap(), 2);
+
+        // Self-comparisons sample two distinct nucleotides.
+        let mut comparison = common::mock_comparison(true);
+        comparison.compare_random(&Line::new("22\t10\tC\t2\tT.\tJJ", true)?, None, draws, &mut rng)?;
+        assert_eq!(comparison.get_sum_pwd(), 1.0);
+        assert!(comparison.compare_random(&Line::new("22\t20\tC\t1\tT\tJ", true)?, None, draws, &mut rng).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn heterozygosity_ratio() -> Result<()> {
+        let mut comparison = common::mock_comparison(false);
+        comparison.compare(&Line::new("22\t10\tC\t2\tTT\tJJ\t2\tTT\tJJ", true)?, None)?;
+        comparison.compare(&Line::new("22\t20\tC\t2\tTT\tJJ\t2\t..\tJJ", true)?, None)?;
+        comparison.compare(&Line::new("22\t30\tC\t2\tTT\tJJ\t2\t.T\tJJ", true)?, None)?;
+        assert_eq!(comparison.get_heterozygosity_ratio(), 1.0);
+
+        // Shared observations are not counted as non-reference when the reference is unknown.
+        comparison.compare(&Line::new("22\t40\tN\t2\tTT\tJJ\t2\tTT\tJJ", true)?, None)?;
+        assert_eq!(comparison.get_heterozygosity_ratio(), 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn heterozygosity_ratio_undefined() -> Result<()> {
+        let mut comparison = common::mock_comparison(false);
+        comparison.compare(&Line::new("22\t10\tN\t2\tTT\tJJ\t2\tTT\tJJ", true)?, None)?;
+        comparison.compare(&Line::new("22\t20\tN\t2\tTT\tJJ\t2\tCC\tJJ", true)?, None)?;
+        assert!(comparison.get_heterozygosity_ratio().is_nan());
+        assert_eq!(format!("{: <8.6}|", HeterozygosityRatio(f64::NAN)), "NA      |");
+        assert_eq!(format!("{: >8.2}|", HeterozygosityRatio(0.5)), "    0.50|");
+        Ok(())
+    }
+
+    #[test]
+    fn breakdowns() -> Result<()> {
+        use genome::snp::SubstitutionClass::{Transition, Transversion};
+        let mut comparison = common::mock_comparison(false);
+        comparison.compare(&Line::new("1\t10\tC\t2\tTT\tJJ\t2\tTT\tJJ", true)?, Some(Transition))?;
+        comparison.compare(&Line::new("1\t20\tC\t2\tTT\tJJ\t2\t..\tJJ", true)?, Some(Transversion))?;
+        comparison.compare(&Line::new("2\t10\tC\t2\tTT\tJJ\t2\t.T\tJJ", true)?, None)?;
+
+        let chromosomes = comparison.get_chromosome_breakdown();
+        let summary: Vec<(&str, usize, f64)> = chromosomes.iter().map(|b| (b.category.as_str(), b.overlap, b.sum_pwd)).collect();
+        assert_eq!(summary, vec![("1", 2, 1.0), ("2", 1, 0.5)]);
+
+        let classes = comparison.get_class_breakdown();
+        let summary: Vec<(&str, usize, f64)> = classes.iter().map(|b| (b.category.as_str(), b.overlap, b.avg_pwd())).collect();
+        assert_eq!(summary, vec![("Ts", 1, 0.0), ("Tv", 1, 1.0)]);
+        Ok(())
+    }
+
+    #[test]
+    fn retain_positions() -> Result<()> {
+        let mut comparison = common::mock_comparison(false);
+        comparison.compare(&Line::new("1\t10\tC\t2\tTT\tJJ\t2\tTT\tJJ", true)?, None)?;
+        comparison.compare(&Line::new("2\t10\tC\t2\tTT\tJJ\t2\t..\tJJ", true)?, None)?;
+        comparison.compare(&Line::new("3\t10\tC\t2\tTT\tJJ\t2\t..\tJJ", true)?, None)?;
+        assert!(comparison.get_jackknife_standard_error().is_finite());
+
+        comparison.retain_positions(|pwd| pwd.coordinate.chromosome.0 != 3)?;
+        assert_eq!(comparison.get_overlap(), 2);
+        assert_eq!(comparison.get_jackknife_estimates().estimate, comparison.get_avg_pwd());
+        Ok(())
+    }
+
+    #[test]
+    fn compare_posteriors() -> Result<()> {
+        let mut comparison = common::mock_comparison(false);
+        let hom_ref = GenotypePosteriors{hom_ref: 1.0, het: 0.0, hom_alt: 0.0};
+        let hom_alt = GenotypePosteriors{hom_ref: 0.0, het: 0.0, hom_alt: 1.0};
+        comparison.compare_posteriors(Coordinate::new(22, 10), [&hom_ref, &hom_alt], None)?;
+        comparison.compare_posteriors(Coordinate::new(22, 20), [&hom_ref, &hom_ref], None)?;
+        assert_eq!(comparison.get_sum_pwd(), 1.0);
+        assert_eq!(comparison.get_avg_pwd(), 0.5);
+        assert_eq!(comparison.get_overlap(), 2);
+        Ok(())
+    }
 
     fn test_compare(
         comparison: &mut Comparison,
@@ -281,7 +547,7 @@ mod tests {
                 nucs[1].len(), nucs[1], quals[1])
             ;
             let line = pileup::Line::new(raw_line.as_str(), true)?;
-            comparison.compare(&line)?;
+            comparison.compare(&line, None)?;
         }
         Ok(())
     }
@@ -363,7 +629,7 @@ mod tests {
         
         // Remove the last position from the comparisons.
         let x = SNPCoord::try_new(22, 40, 'N', 'N')?;
-        mock_comparison.positions.remove(&Pwd::initialize(x.coordinate));
+        mock_comparison.positions.retain(|pwd| pwd.coordinate != x.coordinate)?;
 
         // Check pwd statistics are updated and sane...
         assert_eq!(mock_comparison.get_sum_phred(), 118.5);       // (J + A)
@@ -386,8 +652,10 @@ mod tests {
              {: <AVERG_FORMAT_LEN$.1}{DISPL_SEP}\
              {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
              {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
              {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}",
-             expected_pair_name, 0, 0.0, f64::NAN, f64::NAN, f64::NAN
+             expected_pair_name, 0, 0.0, f64::NAN, f64::NAN, f64::NAN, HeterozygosityRatio(f64::NAN), f64::NAN
         );
         assert_eq!(expect_out, format!("{mock_comparison}"));
     }
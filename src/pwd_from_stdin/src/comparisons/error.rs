@@ -11,4 +11,28 @@ pub enum ComparisonError {
     Also note that indices specified with --sample are zero-based indices."
     )]
     InvalidPileupIndex(usize, String),
+
+    #[error("Not enough nucleotides to perform random sampling at coordinate {0}")]
+    InsufficientNucleotides(Coordinate),
+
+    #[error("Invalid observations file. Observations are expected to be written by grups-rs, using '--write-observations'")]
+    InvalidSnapshot,
+
+    #[error("Observations file was written using format version {found}, while this version of grups-rs expects version {expected}. Please regenerate it using '--write-observations'")]
+    SnapshotVersion{found: u32, expected: u32},
+
+    #[error("Observations file refers to contig id {0}, which is missing from its contig table")]
+    UnknownSnapshotContig(u16),
+
+    #[error("Invalid pairs file entry at line {0}: '{1}'. Each line is expected to contain exactly two whitespace-separated individuals")]
+    InvalidPairsLine(usize, String),
+
+    #[error("Listed individual '{0}' does not match the name or pileup index of any individual requested through --samples")]
+    UnknownPairIndividual(String),
+
+    #[error("Listed pair '{0} {0}' is a self-comparison. Use --self-comparison to allow comparing individuals to themselves")]
+    ListedSelfComparison(String),
+
+    #[error("Pair '{0}' is listed more than once")]
+    DuplicatePair(String),
 }
\ No newline at end of file
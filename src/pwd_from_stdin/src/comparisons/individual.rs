@@ -1,5 +1,6 @@
 
 use crate::pileup::Pileup;
+use grups_io::read::likelihoods::GenotypePosteriors;
 
 
 use super::{UNDEFINED_LABEL_PREFIX, error::ComparisonError};
@@ -13,11 +14,14 @@ use super::{UNDEFINED_LABEL_PREFIX, error::ComparisonError};
 /// 
 ///  - `min_depth` : minimum sequencing depth that is allowed before making a comparison. User-defined, or defaults to 1.
 /// 
+///  - `max_depth` : maximum sequencing depth that is allowed before making a comparison. User-defined, or unbounded.
+/// 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Individual {
     pub name     : String,
     pub index    : usize,
     pub min_depth: u16,
+    pub max_depth: Option<u16>,
 }
 
 impl Individual {
@@ -29,13 +33,27 @@ impl Individual {
             Some(name) => name.to_string(),
             None => format!("{UNDEFINED_LABEL_PREFIX}{index}"),
         };
-        Individual{name, index, min_depth}
+        Individual{name, index, min_depth, max_depth: None}
     }
 
-    /// Ensure the current pileup depth is greater than the requested minimum depth at this line.
+    /// Set the maximum requested depth of this individual.
+    #[must_use]
+    pub fn with_max_depth(self, max_depth: Option<u16>) -> Individual {
+        Individual{max_depth, ..self}
+    }
+
+    /// Ensure the current pileup depth lies within the requested minimum and maximum depth at this line.
     pub fn satisfiable_depth(&self, pileups: &[Pileup]) -> Result<bool, ComparisonError> {
         pileups.get(self.index)
-            .map(|pileup| pileup.depth >= self.min_depth)
+            .map(|pileup| pileup.depth >= self.min_depth && pileup.depth <= self.max_depth.unwrap_or(u16::MAX))
+            .ok_or(ComparisonError::InvalidPileupIndex(self.index, self.name.clone()))
+    }
+
+    /// Retrieve the genotype posteriors of this individual, among those of a given VCF record. Returns `None` if the
+    /// genotype of this individual is missing.
+    pub fn genotype_posteriors<'a>(&self, posteriors: &'a [Option<GenotypePosteriors>]) -> Result<Option<&'a GenotypePosteriors>, ComparisonError> {
+        posteriors.get(self.index)
+            .map(Option::as_ref)
             .ok_or(ComparisonError::InvalidPileupIndex(self.index, self.name.clone()))
     }
 }
@@ -80,6 +98,26 @@ mod tests {
         assert!(!ind.satisfiable_depth(&pileup)?);
         Ok(())
     }
+
+    #[test]
+    fn satisfiable_max_depth() -> Result<(), Box<dyn Error>>{
+        #![allow(clippy::cast_possible_truncation)]
+        let (bases, scores) = ("AATA", "JEEJ");
+        let pileup = [Pileup::new(Allele::N, bases.len() as u16, bases, scores, true)?];
+
+        // ind.max_depth > pileup.depth  -> true
+        let ind = Individual::new(None, 0, 1).with_max_depth(Some(8));
+        assert!(ind.satisfiable_depth(&pileup)?);
+
+        // ind.max_depth == pileup.depth -> true
+        let ind = Individual::new(None, 0, 1).with_max_depth(Some(4));
+        assert!(ind.satisfiable_depth(&pileup)?);
+
+        // ind.max_depth < pileup.depth  -> false
+        let ind = Individual::new(None, 0, 1).with_max_depth(Some(3));
+        assert!(!ind.satisfiable_depth(&pileup)?);
+        Ok(())
+    }
 }
 
 
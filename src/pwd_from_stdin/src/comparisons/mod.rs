@@ -1,9 +1,8 @@
 
-use std::{fmt::{self, Display, Formatter}, ops::{Deref, DerefMut}, collections::HashMap};
+use std::{fmt::{self, Display, Formatter}, ops::{Deref, DerefMut}, collections::HashMap, path::Path};
 
-use genome::Genome;
+use genome::jackknife::JackknifeBlocks;
 
-use itertools::Itertools;
 use log::info;
 
 use grups_io::write::GenericWriter;
@@ -16,7 +15,18 @@ mod individual;
 pub use individual::Individual;
 
 mod comparison;
-pub use comparison::Comparison;
+pub use comparison::{Comparison, HeterozygosityRatio};
+
+mod breakdown;
+pub use breakdown::PwdBreakdown;
+
+mod store;
+pub use store::PwdStore;
+
+mod snapshot;
+
+mod selection;
+pub use selection::PairSelection;
 
 mod test;
 
@@ -62,31 +72,31 @@ impl DerefMut for Comparisons {
 
 impl Comparisons {
 
-    /// Instantiate and populate a new `Comparisons` object from the user-provided parameters.
-    #[must_use]
+    /// Instantiate and populate a new `Comparisons` object from the user-provided parameters. Only the pairs of
+    /// individuals targeted by `pairs` are compared (see `PairSelection`).
+    ///
+    /// # Errors
+    /// - if any of the requested pairs cannot be resolved (see `PairSelection::select()`).
     pub fn parse(
         individuals           : &[usize],
         min_depths            : &[u16],
+        max_depths            : &[u16],
         names                 : &[String],
         allow_self_comparison : bool,
-        genome                : &Genome,
-        blocksize             : u32
-    ) -> Comparisons {
+        pairs                 : &PairSelection,
+        blocks                : &JackknifeBlocks,
+    ) -> Result<Comparisons> {
         let mut inds = vec![];
         for (i, index) in individuals.iter().enumerate() {
             let name = names.get(i);
             let min_depth = &min_depths[i % (min_depths.len())]; // wrap around min_depths if its length is lesser than the number of inds.
-            inds.push(Individual::new(name, *index, *min_depth));
-        }
-        let mut comparisons: Vec<Comparison> = vec![];
-        for pair in inds.iter().combinations_with_replacement(2) {
-            let self_comparison = pair[0] == pair[1]; 
-            if self_comparison && !allow_self_comparison {
-                continue
-            }
-        comparisons.push(Comparison::new([pair[0].clone(), pair[1].clone()], self_comparison, genome, blocksize));            
+            let max_depth = (!max_depths.is_empty()).then(|| max_depths[i % max_depths.len()]);
+            inds.push(Individual::new(name, *index, *min_depth).with_max_depth(max_depth));
         }
-    Comparisons(comparisons)
+        let comparisons = pairs.select(&inds, allow_self_comparison)?.into_iter()
+            .map(|[i, j]| Comparison::new([inds[i].clone(), inds[j].clone()], i == j, blocks.clone()))
+            .collect();
+        Ok(Comparisons(comparisons))
     }
 
     
@@ -105,8 +115,10 @@ impl Comparisons {
              {: <AVERG_FORMAT_LEN$}{DISPL_SEP}\
              {: <AVERG_FORMAT_LEN$}{DISPL_SEP}\
              {: <AVERG_FORMAT_LEN$}{DISPL_SEP}\
-             {: <AVERG_FORMAT_LEN$}",
-             "Pair_name", "Raw.Overlap", "Raw.Sum.PWD", "Raw.Avg.PWD", "Raw.CI.95", "Raw.Avg.Phred");
+             {: <AVERG_FORMAT_LEN$}{DISPL_SEP}\
+             {: <AVERG_FORMAT_LEN$}{DISPL_SEP}\
+             {}",
+             "Pair_name", "Raw.Overlap", "Raw.Sum.PWD", "Raw.Avg.PWD", "Raw.CI.95", "Raw.Avg.Phred", "Raw.Het.Ratio", "Raw.JK.SE");
         pwd_writer.write_iter(vec![&header])?; // Print PWD results to file.
         pwd_writer.write_iter(self.iter())?;   // 
         
@@ -116,6 +128,14 @@ impl Comparisons {
             println!("{self}"); // Print PWD results to console
         }
 
+        // ---- Per-chromosome and per-substitution class breakdowns.
+        if let Some(output_file) = output_files.get("chr") {
+            self.write_breakdown(output_file, "Chr", Comparison::get_chromosome_breakdown)?;
+        }
+        if let Some(output_file) = output_files.get("tstv") {
+            self.write_breakdown(output_file, "Class", Comparison::get_class_breakdown)?;
+        }
+
         if print_blocks {
             use genome::jackknife::{CHROM_FORMAT_LEN, COUNT_FORMAT_LEN, RANGE_FORMAT_LEN};
             let blk_header = format!(
@@ -136,6 +156,16 @@ impl Comparisons {
         Ok(())
     }
 
+    /// Write a breakdown of the observed PWD of each pair within a given output file.
+    /// - `category` : name of the column describing the subset of SNPs (e.g. "Chr").
+    /// - `breakdown`: method used to break down the observed PWD of a given comparison.
+    fn write_breakdown(&self, output_file: &str, category: &str, breakdown: fn(&Comparison) -> Vec<PwdBreakdown>) -> Result<()> {
+        let mut writer = GenericWriter::new(Some(output_file))?;
+        writer.write_iter(vec![PwdBreakdown::header(category)])?;
+        writer.write_iter(self.iter().flat_map(breakdown))?;
+        Ok(())
+    }
+
     /// How many pairs are we comparing?
     #[must_use]
     pub fn len(&self) -> usize {
@@ -159,15 +189,48 @@ impl Comparisons {
         self.0.iter().map(Comparison::get_pair).map(ToString::to_string).collect()
     }
 
+    /// Replace the jackknife blocks of each pair with blocks containing an equal number of overlapping SNPs.
+    pub fn split_blocks_by_snp_count(&mut self, snps_per_block: u32) {
+        self.0.iter_mut().for_each(|comparison| comparison.split_blocks_by_snp_count(snps_per_block));
+    }
+
+    /// Spill the overlapping SNPs of each pair to memory-mapped temporary files within `dir` (see `PwdStore`).
+    /// 
+    /// # Errors
+    /// - if any of the temporary files cannot be created or written.
+    pub fn spill_positions(&mut self, dir: &Path) -> Result<()> {
+        self.0.iter_mut().try_for_each(|comparison| comparison.spill_positions(dir))
+    }
+
+    /// Spill the remaining in-memory positions of each pair to disk. No-op if spilling was not requested.
+    /// 
+    /// # Errors
+    /// - if any of the temporary files cannot be written.
+    pub fn flush_positions(&mut self) -> Result<()> {
+        self.0.iter_mut().try_for_each(|comparison| comparison.positions.flush())
+    }
+
     pub fn update_variance_unbiased(&mut self) {
         self.0.iter_mut().for_each(Comparison::update_variance_unbiased);
     }
+
+    /// Remove every pair having less than `min_overlap` overlapping SNPs, and return them within a separate
+    /// `Comparisons` object. The relative order of the remaining pairs is preserved.
+    pub fn split_off_insufficient(&mut self, min_overlap: usize) -> Comparisons {
+        let (retained, insufficient) = std::mem::take(&mut self.0)
+            .into_iter()
+            .partition(|comparison| comparison.get_overlap() >= min_overlap);
+        self.0 = retained;
+        Comparisons(insufficient)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::{fmt::Write, error::Error};
+    use genome::Genome;
+    use itertools::Itertools;
     use crate::comparisons::test::common;
 
     fn factorial(n: u32 ) -> u32 {
@@ -186,8 +249,8 @@ mod tests {
     fn mock_comparisons(ind_set: &[usize], allow_self_comparison: bool) -> Comparisons {
         let min_depths = vec![2];
         let names = vec![];
-        let genome = Genome::default();
-        Comparisons::parse(ind_set, &min_depths, &names, allow_self_comparison, &genome, 50_000_000)
+        let blocks = JackknifeBlocks::new(&Genome::default(), 50_000_000);
+        Comparisons::parse(ind_set, &min_depths, &[], &names, allow_self_comparison, &PairSelection::All, &blocks).expect("Selecting every pair should never fail")
     }
 
     #[test]
@@ -237,6 +300,22 @@ mod tests {
         assert!(! empty_comp.is_empty());
     }
 
+    #[test]
+    fn split_off_insufficient() {
+        let ind_set = vec![0,1,2];
+        let mut comparisons = mock_comparisons(&ind_set, false);
+        let expected_pairs = comparisons.get_pairs();
+
+        // ---- Pairs without any overlap are never removed when the filter is disabled.
+        assert!(comparisons.split_off_insufficient(0).is_empty());
+        assert_eq!(comparisons.get_pairs(), expected_pairs);
+
+        // ---- Pairs without any overlap are all removed, in order, as soon as one SNP is required.
+        let insufficient = comparisons.split_off_insufficient(1);
+        assert!(comparisons.is_empty());
+        assert_eq!(insufficient.get_pairs(), expected_pairs);
+    }
+
     #[test]
     fn pair_string_getter_self_allowed(){
         let ind_set = vec![0,1,2];
@@ -278,8 +357,10 @@ mod tests {
                 {: <AVERG_FORMAT_LEN$.1}{DISPL_SEP}\
                 {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
                 {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
+                {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
+                {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}{DISPL_SEP}\
                 {: <AVERG_FORMAT_LEN$.FLOAT_FORMAT_PRECISION$}",
-                expected_pair_name, 0, 0.0, f64::NAN, f64::NAN, f64::NAN
+                expected_pair_name, 0, 0.0, f64::NAN, f64::NAN, f64::NAN, HeterozygosityRatio(f64::NAN), f64::NAN
             )?;
         }
 
@@ -1,16 +1,27 @@
 use crate::pileup::Line;
-use genome::Nucleotide;
+use genome::{Nucleotide, snp::{Allele, SubstitutionClass}};
+use grups_io::read::likelihoods::GenotypePosteriors;
 
 use super::Individual;
 
 use genome::coordinate::{Coordinate, derive::{Coord, CoordBorrow, CoordEq, CoordHash, CoordOrd}};
 
+/// Local pairwise difference between two individuals, at a given coordinate.
+/// - `coordinate`  : position of the overlapping SNP.
+/// - `phred_sums`  : sum of the (average) phred scores of each individual's observations.
+/// - `pwd`         : sum of the pairwise mismatch (probabilities) across all observations.
+/// - `hom_alt_sum` : sum of the probabilities of sharing the same non-reference allele, across all observations.
+///   Always zero when the reference allele is unknown.
+/// - `class`       : substitution class of the SNP (transition or transversion), if known.
+/// - `observations`: number of observations (i.e. random draws) made at this position.
 #[derive(Debug, Coord, CoordEq, CoordOrd, CoordHash, CoordBorrow)]
 pub struct Pwd {
     pub coordinate  : Coordinate,
     pub phred_sums  : [f64; 2],
     pub pwd         : f64,
-    observations    : u32,
+    pub hom_alt_sum : f64,
+    pub class       : Option<SubstitutionClass>,
+    pub(super) observations: u32,
 }
 
 impl Pwd {
@@ -20,16 +31,26 @@ impl Pwd {
             coordinate,
             phred_sums  : [0.0,0.0],
             pwd         : 0.0,
+            hom_alt_sum : 0.0,
+            class       : None,
             observations: 0
         }
     }
+
+    /// Set the substitution class of this SNP.
+    #[must_use]
+    pub fn with_class(self, class: Option<SubstitutionClass>) -> Self {
+        Self{class, ..self}
+    }
     
     #[must_use]
-    pub fn one(coordinate: Coordinate, random_nucl: &[&Nucleotide]) -> Self {
+    pub fn one(coordinate: Coordinate, reference: Allele, random_nucl: &[&Nucleotide]) -> Self {
         Self {
             coordinate,
             phred_sums  : [0, 1].map(|i| f64::from(random_nucl[i].phred.score()) ),
             pwd         : Self::check_pwd(random_nucl),
+            hom_alt_sum : Self::check_hom_alt(reference, random_nucl),
+            class       : None,
             observations: 1,
         }
     }
@@ -37,23 +58,20 @@ impl Pwd {
     #[must_use]
     pub fn deterministic_self(line: &Line, pair: &[Individual; 2]) -> Self {
         use itertools::Itertools;
-        let (mut pwd, mut counter) = (0.0, 0.0);
+        let (mut pwd, mut hom_alt_sum, mut counter) = (0.0, 0.0, 0.0);
         let mut phreds = [0.0, 0.0];
-        // let mut hom_alt_sum = 0.0; // WIP: heterozygocity ratio
         for nucs in line.individuals[pair[0].index].nucleotides.iter().combinations(2) {
-            if nucs[0].base != nucs[1].base {
-                pwd += 1.0;
-            } //else if nucs[0].base != '.' { // WIP: heterozygocity ratio
-            //    hom_alt_sum += 1.0
-            //}
+            pwd         += Self::check_pwd(&nucs);
+            hom_alt_sum += Self::check_hom_alt(line.reference, &nucs);
             [0, 1].into_iter().for_each(|i| phreds[i] += f64::from(nucs[i].phred.score()));
             counter += 1.0; 
         }
         
         let phred_sums = [phreds[0]/counter , phreds[1]/counter];
         let pwd = pwd/counter ;
+        let hom_alt_sum = hom_alt_sum/counter;
 
-        Self { coordinate: line.coordinate, phred_sums, pwd, observations: 1 }
+        Self { coordinate: line.coordinate, phred_sums, pwd, hom_alt_sum, class: None, observations: 1 }
     }
 
     #[must_use]
@@ -62,25 +80,38 @@ impl Pwd {
         let observation_sets = [0, 1].map(|i| line.individuals[pair[i].index].observation_set());
         
         let mut prob_pwd = 0.0;
-
-        //let mut hom_alt_sum = 0.0;
+        let mut hom_alt_sum = 0.0;
         for (base0, prob0) in &observation_sets[0].0 {
             for (base1, prob1) in &observation_sets[1].0 {
                 if base0 != base1 {
                     prob_pwd += prob0 * prob1;
-                    
-                } //else if *base0 != '.' { // WIP: heterozygocity ratio
-                //    hom_alt_sum += 1.0
-                //}
+                } else if Self::is_alternate(line.reference, *base0) {
+                    hom_alt_sum += prob0 * prob1;
+                }
             }
         }
         let coordinate = Coordinate{chromosome: line.coordinate.chromosome, position: line.coordinate.position};
         let phred_sums = observation_sets.map(|set| set.1);
-        Self { coordinate, phred_sums, pwd: prob_pwd, observations: 1 }
+        Self { coordinate, phred_sums, pwd: prob_pwd, hom_alt_sum, class: None, observations: 1 }
     }
 
-    pub fn update(&mut self, random_nucl: &[&Nucleotide]) {
+    /// Compute the expected pairwise mismatch probability of a pair of individuals, given their genotype posteriors.
+    /// Self-comparisons draw two alleles from the same genotype, and may thus only differ if it is heterozygous.
+    #[must_use]
+    pub fn from_posteriors(coordinate: Coordinate, posteriors: [&GenotypePosteriors; 2], self_comparison: bool) -> Self {
+        let (pwd, hom_alt_sum) = if self_comparison {
+            (posteriors[0].het / 2.0, posteriors[0].hom_alt + posteriors[0].het / 4.0)
+        } else {
+            let [alt0, alt1] = posteriors.map(GenotypePosteriors::alt_allele_prob);
+            (alt0 * (1.0 - alt1) + alt1 * (1.0 - alt0), alt0 * alt1)
+        };
+        let phred_sums = posteriors.map(GenotypePosteriors::phred);
+        Self { coordinate, phred_sums, pwd, hom_alt_sum, class: None, observations: 1 }
+    }
+
+    pub fn update(&mut self, reference: Allele, random_nucl: &[&Nucleotide]) {
         self.pwd += Self::check_pwd(random_nucl);
+        self.hom_alt_sum += Self::check_hom_alt(reference, random_nucl);
         self.update_phreds(random_nucl);
         self.observations += 1;
     }
@@ -94,11 +125,27 @@ impl Pwd {
         f64::from(u8::from(nuc[0].base != nuc[1].base))
     }
 
+    // Check if both nucleotides share the same non-reference allele.
+    fn check_hom_alt(reference: Allele, nuc: &[&Nucleotide]) -> f64 {
+        f64::from(u8::from(nuc[0].base == nuc[1].base && Self::is_alternate(reference, nuc[0].base)))
+    }
+
+    // Check if a given base is a known non-reference allele. Always false when the reference is unknown.
+    fn is_alternate(reference: Allele, base: Allele) -> bool {
+        reference != Allele::N && base != reference
+    }
+
     #[must_use]
     pub fn avg_local_pwd(&self) -> f64 {
         self.pwd / f64::from(self.observations)
     }
 
+    /// Return the average probability of sharing the same non-reference allele at this position.
+    #[must_use]
+    pub fn avg_local_hom_alt(&self) -> f64 {
+        self.hom_alt_sum / f64::from(self.observations)
+    }
+
     #[must_use]
     pub fn compute_avg_phred(&self) -> f64 {
         f64::midpoint(self.phred_sums[0], self.phred_sums[1]) / f64::from(self.observations)
@@ -147,4 +194,23 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn from_posteriors() {
+        let coordinate = Coordinate::new(22, 51_057_923);
+        let hom_ref = GenotypePosteriors{hom_ref: 1.0, het: 0.0, hom_alt: 0.0};
+        let het     = GenotypePosteriors{hom_ref: 0.0, het: 1.0, hom_alt: 0.0};
+        let unsure  = GenotypePosteriors{hom_ref: 0.5, het: 0.5, hom_alt: 0.0};
+
+        assert_eq!(Pwd::from_posteriors(coordinate, [&hom_ref, &hom_ref], false).pwd, 0.0);
+        assert_eq!(Pwd::from_posteriors(coordinate, [&hom_ref, &het], false).pwd, 0.5);
+        assert_eq!(Pwd::from_posteriors(coordinate, [&het, &het], false).pwd, 0.5);
+        assert_eq!(Pwd::from_posteriors(coordinate, [&unsure, &het], false).pwd, 0.5);
+        assert_eq!(Pwd::from_posteriors(coordinate, [&het, &het], true).pwd, 0.5);
+        assert_eq!(Pwd::from_posteriors(coordinate, [&unsure, &unsure], true).pwd, 0.25);
+
+        let pwd = Pwd::from_posteriors(coordinate, [&unsure, &hom_ref], false);
+        assert_eq!(pwd.pwd, 0.25);
+        assert!((pwd.phred_sums[0] - 10.0 * f64::log10(2.0)).abs() < 1e-9);
+    }
+
 }
\ No newline at end of file
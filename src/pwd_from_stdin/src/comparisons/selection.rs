@@ -0,0 +1,144 @@
+use std::{collections::HashSet, fs::File, io::{BufRead, BufReader}, path::Path};
+
+use itertools::Itertools;
+use located_error::prelude::*;
+
+use super::{ComparisonError, Individual};
+
+/// Pairs of individuals that should be compared.
+/// - `All`   : every pair of individuals.
+/// - `Groups`: only pairs spanning two groups of individuals, i.e. between the first `n` individuals and the remaining
+///   ones.
+/// - `Listed`: an explicit list of pairs, where each individual is referred to by its name, or by its pileup index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PairSelection {
+    #[default]
+    All,
+    Groups(usize),
+    Listed(Vec<[String; 2]>),
+}
+
+impl PairSelection {
+    /// Parse an explicit list of pairs from a file. Pairs are expected to be listed one per line, as two
+    /// whitespace-separated names or pileup indices. Empty lines and lines starting with a '#' character are ignored.
+    ///
+    /// # Errors
+    /// - if `path` targets an invalid location or cannot be read.
+    /// - `InvalidPairsLine` if any line does not contain exactly two fields.
+    pub fn from_file(path: &Path) -> Result<PairSelection> {
+        let loc_msg = || format!("While parsing pairs from {}", path.display());
+        let reader = File::open(path).map(BufReader::new).with_loc(loc_msg)?;
+
+        let mut pairs = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.with_loc(loc_msg)?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            let pair = line.split_whitespace().map(str::to_string).collect_tuple::<(String, String)>()
+                .ok_or_else(|| ComparisonError::InvalidPairsLine(i + 1, line.to_string()))
+                .with_loc(loc_msg)?;
+            pairs.push([pair.0, pair.1]);
+        }
+        Ok(PairSelection::Listed(pairs))
+    }
+
+    /// Select the pairs of `individuals` that should be compared. Returns the position of both members of each pair
+    /// within `individuals`.
+    ///
+    /// Listed pairs are returned in the order of the file, while the members of a pair follow the order of
+    /// `individuals`. Self-comparisons are only included when `allow_self_comparison` is set. When comparing groups,
+    /// each individual is then also compared to itself.
+    ///
+    /// # Errors
+    /// - `UnknownPairIndividual` if a listed individual matches neither the name nor the pileup index of any individual.
+    /// - `ListedSelfComparison` if a listed pair targets the same individual twice, while self-comparisons are disabled.
+    /// - `DuplicatePair` if a pair is listed more than once.
+    pub fn select(&self, individuals: &[Individual], allow_self_comparison: bool) -> Result<Vec<[usize; 2]>> {
+        let candidates = (0..individuals.len()).tuple_combinations().map(|(i, j)| [i, j]);
+        let self_pairs = (0..individuals.len()).map(|i| [i, i]).filter(|_| allow_self_comparison);
+        let mut pairs = match self {
+            Self::All => candidates.chain(self_pairs).collect::<Vec<_>>(),
+            Self::Groups(n) => candidates.filter(|[i, j]| (*i < *n) != (*j < *n)).chain(self_pairs).collect(),
+            Self::Listed(listed) => return Self::resolve(listed, individuals, allow_self_comparison),
+        };
+        pairs.sort_unstable();
+        Ok(pairs)
+    }
+
+    fn resolve(listed: &[[String; 2]], individuals: &[Individual], allow_self_comparison: bool) -> Result<Vec<[usize; 2]>> {
+        let position = |label: &String| individuals.iter().position(|ind| &ind.name == label)
+            .or_else(|| label.parse::<usize>().ok().and_then(|index| individuals.iter().position(|ind| ind.index == index)))
+            .ok_or_else(|| ComparisonError::UnknownPairIndividual(label.clone()));
+
+        let mut pairs: Vec<[usize; 2]> = Vec::with_capacity(listed.len());
+        let mut seen : HashSet<[usize; 2]> = HashSet::with_capacity(listed.len());
+        for labels in listed {
+            let mut pair = [position(&labels[0])?, position(&labels[1])?];
+            pair.sort_unstable();
+            if pair[0] == pair[1] && !allow_self_comparison {
+                return Err(ComparisonError::ListedSelfComparison(labels[0].clone())).loc("While resolving listed pairs")
+            }
+            if !seen.insert(pair) {
+                return Err(ComparisonError::DuplicatePair(labels.join(" "))).loc("While resolving listed pairs")
+            }
+            pairs.push(pair);
+        }
+        Ok(pairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use super::*;
+
+    fn mock_individuals() -> Vec<Individual> {
+        [("ART04", 0), ("ART16", 3), ("ART20", 5)].iter()
+            .map(|(name, index)| Individual::new(Some(&name.to_string()), *index, 1))
+            .collect()
+    }
+
+    #[test]
+    fn select_all() -> Result<()> {
+        let inds = mock_individuals();
+        assert_eq!(PairSelection::All.select(&inds, false)?, [[0, 1], [0, 2], [1, 2]]);
+        assert_eq!(PairSelection::All.select(&inds, true)?, [[0, 0], [0, 1], [0, 2], [1, 1], [1, 2], [2, 2]]);
+        Ok(())
+    }
+
+    #[test]
+    fn select_groups() -> Result<()> {
+        let inds = mock_individuals();
+        assert_eq!(PairSelection::Groups(1).select(&inds, false)?, [[0, 1], [0, 2]]);
+        assert_eq!(PairSelection::Groups(2).select(&inds, false)?, [[0, 2], [1, 2]]);
+        assert_eq!(PairSelection::Groups(1).select(&inds, true)?, [[0, 0], [0, 1], [0, 2], [1, 1], [2, 2]]);
+        Ok(())
+    }
+
+    #[test]
+    fn select_listed() -> Result<()> {
+        let inds = mock_individuals();
+        let listed = |pairs: &[[&str; 2]]| PairSelection::Listed(pairs.iter().map(|pair| pair.map(String::from)).collect());
+
+        assert_eq!(listed(&[["ART20", "ART04"], ["3", "5"]]).select(&inds, false)?, [[0, 2], [1, 2]]);
+        assert!(listed(&[["ART04", "1"]]).select(&inds, false).is_err());                   // Unknown pileup index.
+        assert!(listed(&[["ART04", "ART04"]]).select(&inds, false).is_err());               // Self-comparison.
+        assert_eq!(listed(&[["ART04", "0"]]).select(&inds, true)?, [[0, 0]]);
+        assert!(listed(&[["ART04", "ART16"], ["3", "0"]]).select(&inds, false).is_err());   // Duplicate pair.
+        Ok(())
+    }
+
+    #[test]
+    fn from_file() -> Result<()> {
+        let mut file = tempfile::NamedTempFile::new()?;
+        writeln!(file, "# Screen ART04 against published individuals\nART04 ART16\n\nART04\t5")?;
+        let expected = PairSelection::Listed(vec![["ART04", "ART16"], ["ART04", "5"]].into_iter().map(|pair| pair.map(String::from)).collect());
+        assert_eq!(PairSelection::from_file(file.path())?, expected);
+
+        writeln!(file, "ART04 ART16 ART20")?;
+        assert!(PairSelection::from_file(file.path()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+use std::{collections::{BTreeSet, HashMap}, fs::File, io::{self, BufReader, BufWriter, Read, Write}, path::Path};
+
+use genome::{coordinate::{ChrIdx, Coordinate, Position}, jackknife::{JackknifeBlock, JackknifeBlocks}};
+use located_error::prelude::*;
+
+use super::{Comparison, Comparisons, ComparisonError, Individual, Pwd};
+use super::store::{decode_class, encode_class};
+
+/// Leading bytes of an observations snapshot.
+const SNAPSHOT_MAGIC: &[u8; 8] = b"GRUPSOBS";
+
+/// Current version of the observations snapshot format. Must be incremented whenever the layout changes.
+const SNAPSHOT_VERSION: u32 = 3;
+
+/// Binary writer of observations snapshots. Every value is written in little-endian order.
+///
+/// # Layout:
+/// - header     : magic bytes, format version.
+/// - contigs    : id and name of every non-numbered contig (e.g. `X`, unplaced scaffolds). The ids of these contigs
+///   depend on their registration order within the process-wide `ContigDictionary`, and are thus remapped on load.
+/// - comparisons: number of comparisons, followed by the self-comparison flag, both individuals, jackknife blocks and overlapping SNPs of each pair (see
+///   `Comparison::write_snapshot()`).
+pub(super) struct SnapshotWriter<W: Write>(W);
+
+impl<W: Write> SnapshotWriter<W> {
+    pub fn u8(&mut self, value: u8) -> io::Result<()> {
+        self.0.write_all(&[value])
+    }
+
+    pub fn u16(&mut self, value: u16) -> io::Result<()> {
+        self.0.write_all(&value.to_le_bytes())
+    }
+
+    pub fn u32(&mut self, value: u32) -> io::Result<()> {
+        self.0.write_all(&value.to_le_bytes())
+    }
+
+    pub fn usize(&mut self, value: usize) -> io::Result<()> {
+        self.0.write_all(&(value as u64).to_le_bytes())
+    }
+
+    pub fn f64(&mut self, value: f64) -> io::Result<()> {
+        self.0.write_all(&value.to_le_bytes())
+    }
+
+    pub fn str(&mut self, value: &str) -> io::Result<()> {
+        self.usize(value.len())?;
+        self.0.write_all(value.as_bytes())
+    }
+
+    pub fn individual(&mut self, individual: &Individual) -> io::Result<()> {
+        self.str(&individual.name)?;
+        self.usize(individual.index)?;
+        self.u16(individual.min_depth)?;
+        self.u8(u8::from(individual.max_depth.is_some()))?;
+        self.u16(individual.max_depth.unwrap_or(0))
+    }
+
+    pub fn contigs(&mut self, contigs: &BTreeSet<ChrIdx>) -> io::Result<()> {
+        self.usize(contigs.len())?;
+        for contig in contigs {
+            self.u16(contig.0)?;
+            self.str(&contig.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn blocks(&mut self, blocks: &JackknifeBlocks) -> io::Result<()> {
+        self.usize(blocks.iter().count())?;
+        for block in blocks.iter() {
+            self.u16(block.chromosome.0)?;
+            self.u32(block.range.start.0)?;
+            self.u32(block.range.end.0)?;
+            self.u32(block.site_counts())?;
+            self.f64(block.pwd_counts())?;
+        }
+        Ok(())
+    }
+
+    pub fn pwd(&mut self, pwd: &Pwd) -> io::Result<()> {
+        self.u16(pwd.coordinate.chromosome.0)?;
+        self.u32(pwd.coordinate.position.0)?;
+        pwd.phred_sums.iter().try_for_each(|phred| self.f64(*phred))?;
+        self.f64(pwd.pwd)?;
+        self.f64(pwd.hom_alt_sum)?;
+        self.u8(encode_class(pwd.class))?;
+        self.u32(pwd.observations)
+    }
+}
+
+/// Binary reader of observations snapshots. See `SnapshotWriter`.
+/// - `reader` : underlying reader.
+/// - `contigs`: snapshot contig id -> current contig id, for every non-numbered contig of the snapshot.
+pub(super) struct SnapshotReader<R: Read> {
+    reader : R,
+    contigs: HashMap<u16, ChrIdx>,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    fn new(reader: R) -> Self {
+        Self{reader, contigs: HashMap::new()}
+    }
+
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.reader.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn u8(&mut self) -> io::Result<u8> {
+        self.bytes().map(u8::from_le_bytes)
+    }
+
+    pub fn u16(&mut self) -> io::Result<u16> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+
+    pub fn u32(&mut self) -> io::Result<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    pub fn usize(&mut self) -> Result<usize> {
+        Ok(usize::try_from(self.bytes().map(u64::from_le_bytes)?)?)
+    }
+
+    pub fn f64(&mut self) -> io::Result<f64> {
+        self.bytes().map(f64::from_le_bytes)
+    }
+
+    pub fn string(&mut self) -> Result<String> {
+        let mut buf = vec![0; self.usize()?];
+        self.reader.read_exact(&mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+
+    pub fn individual(&mut self) -> Result<Individual> {
+        let name      = self.string()?;
+        let index     = self.usize()?;
+        let min_depth = self.u16()?;
+        let max_depth = (self.u8()? != 0, self.u16()?);
+        Ok(Individual::new(Some(&name), index, min_depth).with_max_depth(max_depth.0.then_some(max_depth.1)))
+    }
+
+    /// Read the contig table of the snapshot, registering every contig within the process-wide contig dictionary.
+    pub fn contigs(&mut self) -> Result<()> {
+        for _ in 0..self.usize()? {
+            let id   = self.u16()?;
+            let name = self.string()?;
+            self.contigs.insert(id, ChrIdx::register(&name)?);
+        }
+        Ok(())
+    }
+
+    /// Read a contig id, and translate it into its current id if it is not a numbered contig.
+    pub fn chromosome(&mut self) -> Result<ChrIdx> {
+        let chromosome = ChrIdx(self.u16()?);
+        if chromosome.is_numbered() {
+            return Ok(chromosome)
+        }
+        self.contigs.get(&chromosome.0).copied()
+            .ok_or(ComparisonError::UnknownSnapshotContig(chromosome.0))
+            .loc("While reading a contig id")
+    }
+
+    pub fn blocks(&mut self) -> Result<JackknifeBlocks> {
+        (0..self.usize()?).map(|_| {
+            let chromosome = self.chromosome()?;
+            let (start, end) = (Position(self.u32()?), Position(self.u32()?));
+            Ok(JackknifeBlock::new(chromosome, start, end).with_counts(self.u32()?, self.f64()?))
+        }).collect()
+    }
+
+    pub fn pwd(&mut self) -> Result<Pwd> {
+        let coordinate = Coordinate{chromosome: self.chromosome()?, position: Position(self.u32()?)};
+        Ok(Pwd {
+            coordinate,
+            phred_sums  : [self.f64()?, self.f64()?],
+            pwd         : self.f64()?,
+            hom_alt_sum : self.f64()?,
+            class       : decode_class(self.u8()?),
+            observations: self.u32()?,
+        })
+    }
+}
+
+impl Comparisons {
+    /// Write a binary snapshot of every comparison (i.e. individuals, jackknife blocks and overlapping SNPs), which
+    /// may later be restored using `Comparisons::read_observations()`.
+    ///
+    /// # Errors
+    /// - if the output file cannot be created or written.
+    pub fn write_observations(&self, path: &Path) -> Result<()> {
+        let loc_msg = || format!("While writing observations to {}", path.display());
+        let file = File::create(path).with_loc(loc_msg)?;
+        let mut writer = SnapshotWriter(BufWriter::new(file));
+
+        // ---- Overlapping SNPs always lie within a jackknife block: blocks thus cover every contig of the snapshot.
+        let contigs = self.iter()
+            .flat_map(|comparison| comparison.blocks.iter().map(|block| block.chromosome))
+            .filter(|chromosome| !chromosome.is_numbered())
+            .collect::<BTreeSet<ChrIdx>>();
+
+        writer.0.write_all(SNAPSHOT_MAGIC).with_loc(loc_msg)?;
+        writer.u32(SNAPSHOT_VERSION).with_loc(loc_msg)?;
+        writer.contigs(&contigs).with_loc(loc_msg)?;
+        writer.usize(self.len()).with_loc(loc_msg)?;
+        for comparison in self.iter() {
+            comparison.write_snapshot(&mut writer).with_loc(loc_msg)?;
+        }
+        writer.0.flush().with_loc(loc_msg)
+    }
+
+    /// Restore a set of comparisons from a binary snapshot (see `Comparisons::write_observations()`). Overlapping SNPs
+    /// are spilled within `spill_dir`, if provided (see `PwdStore`).
+    ///
+    /// # Errors
+    /// - if the file cannot be opened or read.
+    /// - `InvalidSnapshot` if the file is not an observations snapshot.
+    /// - `SnapshotVersion` if the snapshot was written using an incompatible format.
+    /// - `UnknownSnapshotContig` if any contig id is missing from the contig table of the snapshot.
+    pub fn read_observations(path: &Path, spill_dir: Option<&Path>) -> Result<Comparisons> {
+        let loc_msg = || format!("While reading observations from {}", path.display());
+        let file = File::open(path).with_loc(loc_msg)?;
+        let mut reader = SnapshotReader::new(BufReader::new(file));
+
+        if reader.bytes().ok().as_ref() != Some(SNAPSHOT_MAGIC) {
+            return Err(ComparisonError::InvalidSnapshot).with_loc(loc_msg)
+        }
+        let version = reader.u32().with_loc(loc_msg)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(ComparisonError::SnapshotVersion{found: version, expected: SNAPSHOT_VERSION}).with_loc(loc_msg)
+        }
+        reader.contigs().with_loc(loc_msg)?;
+
+        let comparisons = (0..reader.usize().with_loc(loc_msg)?)
+            .map(|_| Comparison::read_snapshot(&mut reader, spill_dir))
+            .collect::<Result<Vec<Comparison>>>()
+            .with_loc(loc_msg)?;
+        Ok(Comparisons(comparisons))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genome::{Genome, snp::SubstitutionClass};
+    use crate::comparisons::PairSelection;
+    use crate::pileup::Line;
+
+    fn mock_comparisons() -> Result<Comparisons> {
+        let blocks = JackknifeBlocks::new(&Genome::default(), 1_000_000);
+        let names  = vec![String::from("A"), String::from("B")];
+        let mut comparisons = Comparisons::parse(&[0, 1], &[1, 2], &[10], &names, false, &PairSelection::All, &blocks)?;
+        let comparison = &mut comparisons[0];
+        comparison.compare(&Line::new("1\t10\tC\t2\tTT\tJJ\t2\tT.\tJ5", true)?, Some(SubstitutionClass::Transition))?;
+        comparison.compare(&Line::new("2\t10\tC\t2\tTT\tJJ\t2\t..\tJJ", true)?, None)?;
+        Ok(comparisons)
+    }
+
+    #[test]
+    fn write_and_read_observations() -> Result<()> {
+        let comparisons = mock_comparisons()?;
+        let snapshot = tempfile::NamedTempFile::new()?;
+        comparisons.write_observations(snapshot.path())?;
+
+        for spill_dir in [None, Some(tempfile::tempdir()?)] {
+            let restored = Comparisons::read_observations(snapshot.path(), spill_dir.as_ref().map(AsRef::as_ref))?;
+            assert_eq!(restored.get_pairs(), comparisons.get_pairs());
+            assert_eq!(restored.get_pairs_indices(), comparisons.get_pairs_indices());
+            assert_eq!(restored[0].to_string(), comparisons[0].to_string());
+            assert_eq!(restored[0].blocks.to_string(), comparisons[0].blocks.to_string());
+
+            let summary = |comparisons: &Comparisons| comparisons[0].positions.iter()
+                .map(|pwd| (pwd.coordinate, pwd.pwd.to_bits(), pwd.error_probs().map(f64::to_bits), pwd.class))
+                .collect::<Vec<_>>();
+            assert_eq!(summary(&restored), summary(&comparisons));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn remap_contigs() -> Result<()> {
+        // ---- Contig ids written by another process may differ from the ones of the current dictionary.
+        let mut bytes = Vec::new();
+        let mut writer = SnapshotWriter(&mut bytes);
+        writer.usize(1)?;
+        writer.u16(u16::MAX)?;
+        writer.str("snapshot_remapped_contig")?;
+        [u16::MAX, 22, u16::MAX - 1].into_iter().try_for_each(|id| writer.u16(id))?;
+
+        let mut reader = SnapshotReader::new(bytes.as_slice());
+        reader.contigs()?;
+        assert_eq!(reader.chromosome()?, "snapshot_remapped_contig".parse::<ChrIdx>()?);
+        assert_eq!(reader.chromosome()?, ChrIdx(22));
+        assert!(reader.chromosome().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_observations() -> Result<()> {
+        let mut snapshot = tempfile::NamedTempFile::new()?;
+        writeln!(snapshot, "1\t10\tC\t2\tTT\tJJ")?;
+        assert!(Comparisons::read_observations(snapshot.path(), None).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,528 @@
+use std::{fmt::{self, Debug, Formatter}, fs::OpenOptions, io::{BufWriter, Write}, mem, ops::Range, path::{Path, PathBuf}, slice};
+
+use genome::{coordinate::{ChrIdx, Coordinate, Position}, snp::SubstitutionClass};
+use located_error::prelude::*;
+use memmap2::{Mmap, MmapOptions};
+use tempfile::TempPath;
+
+use super::Pwd;
+
+/// Number of bytes required to store a single site within a memory-mapped segment.
+const BYTES_PER_SITE: usize = 2 * mem::size_of::<f32>() + 2 * mem::size_of::<u32>() + mem::size_of::<[u8; 2]>()
+    + mem::size_of::<u8>();
+
+/// Compact, columnar storage of the local pairwise differences of a pair of individuals.
+///
+/// Sites are grouped per chromosome, within sorted coordinate arrays, along with packed pwd, non-reference and phred
+/// values. This avoids the per-site overhead of a `BTreeSet<Pwd>`, while keeping range queries cheap. Note that values
+/// are stored with a reduced precision: pwd and non-reference sums are kept as `f32`, while the phred scores of each
+/// individual are kept as their average over all observations, rounded to the nearest `u8`. Optionally,
+/// every chromosome that is no longer being filled may be spilled to a memory-mapped temporary file (see `spill_to()`),
+/// thus bounding memory usage to the chromosome currently being compared.
+///
+/// As for a `BTreeSet`, inserting a site at an already known coordinate leaves the store unchanged.
+///
+/// # Fields:
+/// - `segments`: per-chromosome columns, sorted by chromosome.
+/// - `spill`   : temporary file where completed segments are spilled, if requested.
+#[derive(Debug, Default)]
+pub struct PwdStore {
+    segments: Vec<Segment>,
+    spill   : Option<SpillFile>,
+}
+
+impl PwdStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spill every completed chromosome to a memory-mapped temporary file, created within `dir`. Once set, chromosomes
+    /// are automatically spilled as soon as a site from another chromosome is inserted.
+    ///
+    /// # Errors
+    /// - if the temporary file cannot be created or written.
+    pub fn spill_to(&mut self, dir: &Path) -> Result<()> {
+        self.spill = Some(SpillFile::new(dir)?);
+        self.flush()
+    }
+
+    /// Spill every in-memory chromosome to the temporary file of this store. This is a no-op when spilling was not
+    /// requested.
+    ///
+    /// # Errors
+    /// - if the temporary file cannot be written or mapped.
+    pub fn flush(&mut self) -> Result<()> {
+        self.spill_segments(None)
+    }
+
+    /// Spill every in-memory segment to disk, except the one of the `current` chromosome (if any).
+    fn spill_segments(&mut self, current: Option<ChrIdx>) -> Result<()> {
+        let Some(spill) = self.spill.as_mut() else {
+            return Ok(())
+        };
+        for segment in self.segments.iter_mut().filter(|segment| Some(segment.chromosome) != current) {
+            if let Columns::Heap(columns) = &segment.columns {
+                segment.columns = Columns::Mapped(spill.write(columns)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert a local pairwise difference. Returns `false` if a site was already recorded at this coordinate.
+    ///
+    /// Inserting sites in coordinate order is cheap. Inserting a site within a previously spilled chromosome requires
+    /// loading said chromosome back into memory.
+    ///
+    /// # Errors
+    /// - if previously completed chromosomes cannot be spilled to disk.
+    pub fn insert(&mut self, pwd: Pwd) -> Result<bool> {
+        let chromosome = pwd.coordinate.chromosome;
+        let index = match self.segments.last() {
+            Some(segment) if segment.chromosome == chromosome => self.segments.len() - 1,
+            _ => match self.segments.binary_search_by_key(&chromosome, |segment| segment.chromosome) {
+                Ok(index) => index,
+                Err(index) => {
+                    self.spill_segments(Some(chromosome)).loc("While spilling completed chromosomes")?;
+                    self.segments.insert(index, Segment{chromosome, columns: Columns::Heap(HeapColumns::default())});
+                    index
+                }
+            }
+        };
+        Ok(self.segments[index].load().insert(&pwd))
+    }
+
+    /// Number of sites contained within this store.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.segments.iter().map(|segment| segment.view().len()).sum()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over every site, in coordinate order.
+    pub fn iter(&self) -> impl Iterator<Item = Pwd> + Clone + '_ {
+        self.segments.iter().flat_map(|segment| segment.view().iter(segment.chromosome, 0..segment.view().len()))
+    }
+
+    /// Iterate over the sites located within a given range of coordinates, in coordinate order.
+    pub fn range(&self, range: Range<Coordinate>) -> impl Iterator<Item = Pwd> + Clone + '_ {
+        self.segments.iter()
+            .filter(move |segment| (range.start.chromosome..=range.end.chromosome).contains(&segment.chromosome))
+            .flat_map(move |segment| {
+                let view  = segment.view();
+                let lower = |bound: &Coordinate| match bound.chromosome == segment.chromosome {
+                    true  => view.positions.partition_point(|position| *position < bound.position.0),
+                    false => 0,
+                };
+                let start = lower(&range.start);
+                let end   = match range.end.chromosome == segment.chromosome {
+                    true  => lower(&range.end),
+                    false => view.len(),
+                };
+                view.iter(segment.chromosome, start..end.max(start))
+            })
+    }
+
+    /// Retrieve the site recorded at a given coordinate, if any.
+    #[must_use]
+    pub fn get(&self, coordinate: &Coordinate) -> Option<Pwd> {
+        let segment = self.segments.binary_search_by_key(&coordinate.chromosome, |segment| segment.chromosome).ok()?;
+        let segment = &self.segments[segment];
+        let view    = segment.view();
+        let index   = view.positions.binary_search(&coordinate.position.0).ok()?;
+        Some(view.get(segment.chromosome, index))
+    }
+
+    #[must_use]
+    pub fn contains(&self, coordinate: &Coordinate) -> bool {
+        self.get(coordinate).is_some()
+    }
+
+    /// Only retain the sites satisfying the predicate `f`. Spilled chromosomes are filtered, and spilled again within
+    /// a new temporary file, thus reclaiming the disk space of discarded sites. The previous file is deleted afterwards.
+    ///
+    /// # Errors
+    /// - if filtered chromosomes cannot be spilled to disk.
+    pub fn retain(&mut self, mut f: impl FnMut(&Pwd) -> bool) -> Result<()> {
+        let mut spill = self.spill.as_ref().map(SpillFile::renew).transpose()?;
+        for segment in &mut self.segments {
+            let mut columns = HeapColumns::default();
+            for pwd in segment.view().iter(segment.chromosome, 0..segment.view().len()).filter(|pwd| f(pwd)) {
+                columns.push(&pwd);
+            }
+            segment.columns = match (&segment.columns, spill.as_mut()) {
+                (Columns::Mapped(_), Some(spill)) if !columns.positions.is_empty() => Columns::Mapped(spill.write(&columns)?),
+                _ => Columns::Heap(columns),
+            };
+        }
+        self.segments.retain(|segment| segment.view().len() > 0);
+        if spill.is_some() {
+            self.spill = spill;
+        }
+        Ok(())
+    }
+}
+
+/// Columns of a single chromosome.
+#[derive(Debug)]
+struct Segment {
+    chromosome: ChrIdx,
+    columns   : Columns,
+}
+
+impl Segment {
+    fn view(&self) -> ColumnsView<'_> {
+        match &self.columns {
+            Columns::Heap(columns)   => columns.view(),
+            Columns::Mapped(columns) => columns.view(),
+        }
+    }
+
+    /// Load the columns of this segment into memory, if they were spilled, and return them.
+    fn load(&mut self) -> &mut HeapColumns {
+        if let Columns::Mapped(columns) = &self.columns {
+            let view = columns.view();
+            let mut heap = HeapColumns::default();
+            view.iter(self.chromosome, 0..view.len()).for_each(|pwd| heap.push(&pwd));
+            self.columns = Columns::Heap(heap);
+        }
+        match &mut self.columns {
+            Columns::Heap(columns) => columns,
+            Columns::Mapped(_)     => unreachable!("Segment columns should have been loaded in memory"),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Columns {
+    Heap(HeapColumns),
+    Mapped(MappedColumns),
+}
+
+/// In-memory columns of a chromosome. Every column shares the same length, and `positions` is sorted.
+/// - `phreds`: average phred score of each individual, across all observations (see `encode_phreds()`).
+#[derive(Debug, Default)]
+struct HeapColumns {
+    positions   : Vec<u32>,
+    pwd         : Vec<f32>,
+    hom_alt_sum : Vec<f32>,
+    observations: Vec<u32>,
+    phreds      : Vec<[u8; 2]>,
+    classes     : Vec<u8>,
+}
+
+impl HeapColumns {
+    /// Append a site at the end of each column.
+    fn push(&mut self, pwd: &Pwd) {
+        self.insert_at(self.positions.len(), pwd);
+    }
+
+    /// Insert a site at its sorted position. Returns `false` if this position was already recorded.
+    fn insert(&mut self, pwd: &Pwd) -> bool {
+        let position = pwd.coordinate.position.0;
+        let index = match self.positions.last() {
+            Some(last) if *last >= position => match self.positions.binary_search(&position) {
+                Ok(_)      => return false,
+                Err(index) => index,
+            },
+            _ => self.positions.len(),
+        };
+        self.insert_at(index, pwd);
+        true
+    }
+
+    fn insert_at(&mut self, index: usize, pwd: &Pwd) {
+        #![allow(clippy::cast_possible_truncation)] // Reduced precision is intended. See `PwdStore`.
+        self.positions.insert(index, pwd.coordinate.position.0);
+        self.pwd.insert(index, pwd.pwd as f32);
+        self.hom_alt_sum.insert(index, pwd.hom_alt_sum as f32);
+        self.observations.insert(index, pwd.observations);
+        self.phreds.insert(index, encode_phreds(pwd));
+        self.classes.insert(index, encode_class(pwd.class));
+    }
+
+    fn view(&self) -> ColumnsView<'_> {
+        ColumnsView {
+            positions   : &self.positions,
+            pwd         : &self.pwd,
+            hom_alt_sum : &self.hom_alt_sum,
+            observations: &self.observations,
+            phreds      : &self.phreds,
+            classes     : &self.classes,
+        }
+    }
+}
+
+/// Memory-mapped columns of a chromosome. Columns are laid out contiguously, in order of decreasing alignment:
+/// `pwd`, `hom_alt_sum`, `positions`, `observations`, `phreds`, `classes`.
+struct MappedColumns {
+    map: Mmap,
+    len: usize,
+}
+
+impl Debug for MappedColumns {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedColumns").field("len", &self.len).finish_non_exhaustive()
+    }
+}
+
+impl MappedColumns {
+    fn view(&self) -> ColumnsView<'_> {
+        let len = self.len;
+        let (pwd, bytes)          = self.map.split_at(len * mem::size_of::<f32>());
+        let (hom_alt_sum, bytes)  = bytes.split_at(len * mem::size_of::<f32>());
+        let (positions, bytes)    = bytes.split_at(len * mem::size_of::<u32>());
+        let (observations, bytes) = bytes.split_at(len * mem::size_of::<u32>());
+        let (phreds, bytes)       = bytes.split_at(len * mem::size_of::<[u8; 2]>());
+        // SAFETY: Every column was written from a slice of the same type and length, and is aligned, since the mapped
+        //         segment starts on an 8-byte boundary, and columns are laid out in order of decreasing alignment.
+        unsafe {
+            ColumnsView {
+                positions   : cast_slice(positions, len),
+                pwd         : cast_slice(pwd, len),
+                hom_alt_sum : cast_slice(hom_alt_sum, len),
+                observations: cast_slice(observations, len),
+                phreds      : cast_slice(phreds, len),
+                classes     : &bytes[..len],
+            }
+        }
+    }
+}
+
+/// Reinterpret a region of a memory-mapped segment as a slice of `len` numeric values.
+/// # Safety
+/// `T` must be a plain numeric type (or an array of such), valid for any bit pattern. `bytes` must be aligned for `T`
+/// and contain at least `len` values.
+unsafe fn cast_slice<T>(bytes: &[u8], len: usize) -> &[T] {
+    debug_assert_eq!(bytes.as_ptr().align_offset(mem::align_of::<T>()), 0);
+    debug_assert!(bytes.len() >= len * mem::size_of::<T>());
+    slice::from_raw_parts(bytes.as_ptr().cast::<T>(), len)
+}
+
+/// Borrowed view over the columns of a chromosome, regardless of where they are stored.
+#[derive(Clone, Copy)]
+struct ColumnsView<'a> {
+    positions   : &'a [u32],
+    pwd         : &'a [f32],
+    hom_alt_sum : &'a [f32],
+    observations: &'a [u32],
+    phreds      : &'a [[u8; 2]],
+    classes     : &'a [u8],
+}
+
+impl<'a> ColumnsView<'a> {
+    fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn get(&self, chromosome: ChrIdx, index: usize) -> Pwd {
+        let observations = self.observations[index];
+        Pwd {
+            coordinate  : Coordinate{chromosome, position: Position(self.positions[index])},
+            phred_sums  : self.phreds[index].map(|phred| f64::from(phred) * f64::from(observations)),
+            pwd         : f64::from(self.pwd[index]),
+            hom_alt_sum : f64::from(self.hom_alt_sum[index]),
+            class       : decode_class(self.classes[index]),
+            observations,
+        }
+    }
+
+    fn iter(self, chromosome: ChrIdx, range: Range<usize>) -> impl Iterator<Item = Pwd> + Clone + 'a {
+        range.map(move |index| self.get(chromosome, index))
+    }
+
+    /// Write every column to `writer`, following the layout of `MappedColumns`.
+    fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        for value in self.pwd.iter().chain(self.hom_alt_sum) {
+            writer.write_all(&value.to_ne_bytes())?;
+        }
+        for value in self.positions.iter().chain(self.observations) {
+            writer.write_all(&value.to_ne_bytes())?;
+        }
+        for phreds in self.phreds {
+            writer.write_all(phreds)?;
+        }
+        writer.write_all(self.classes)
+    }
+}
+
+/// Temporary file where completed chromosomes are spilled. The file is deleted once dropped.
+/// - `dir` : directory of the temporary file.
+/// - `path`: path of the temporary file.
+/// - `len` : current length of the file, in bytes.
+#[derive(Debug)]
+struct SpillFile {
+    dir : PathBuf,
+    path: TempPath,
+    len : u64,
+}
+
+impl SpillFile {
+    /// Create a new, empty temporary file within `dir`.
+    fn new(dir: &Path) -> Result<Self> {
+        let loc_msg = || format!("While creating a temporary spill file within {}", dir.display());
+        let path = tempfile::Builder::new()
+            .prefix("grups-rs-")
+            .suffix(".pwd.tmp")
+            .tempfile_in(dir)
+            .with_loc(loc_msg)?
+            .into_temp_path();
+        Ok(Self{dir: dir.to_path_buf(), path, len: 0})
+    }
+
+    /// Create a new, empty temporary file within the directory of this one.
+    fn renew(&self) -> Result<Self> {
+        Self::new(&self.dir)
+    }
+
+    /// Append a set of columns at the end of the file, and memory-map them.
+    fn write(&mut self, columns: &HeapColumns) -> Result<MappedColumns> {
+        let loc_msg = || format!("While spilling observations to {}", self.path.display());
+        let len  = columns.positions.len();
+        let size = len * BYTES_PER_SITE;
+        let padding = (8 - size % 8) % 8; // Ensure every segment starts on an 8-byte boundary.
+
+        let file = OpenOptions::new().read(true).append(true).open(&self.path).with_loc(loc_msg)?;
+        let mut writer = BufWriter::new(&file);
+        columns.view().write_to(&mut writer).with_loc(loc_msg)?;
+        writer.write_all(&[0; 8][..padding]).with_loc(loc_msg)?;
+        writer.flush().with_loc(loc_msg)?;
+        drop(writer);
+
+        // SAFETY: The spilled region is never modified once written: subsequent segments are appended past its end.
+        let map = unsafe { MmapOptions::new().offset(self.len).len(size).map(&file) }.with_loc(loc_msg)?;
+        self.len += (size + padding) as u64;
+        Ok(MappedColumns{map, len})
+    }
+}
+
+/// Average phred score of each individual, across all the observations of a site, rounded to the nearest `u8`.
+fn encode_phreds(pwd: &Pwd) -> [u8; 2] {
+    #![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Values are clamped within the u8 range.
+    let observations = f64::from(pwd.observations.max(1));
+    pwd.phred_sums.map(|sum| (sum / observations).round().clamp(0.0, f64::from(u8::MAX)) as u8)
+}
+
+pub(super) fn encode_class(class: Option<SubstitutionClass>) -> u8 {
+    match class {
+        None                                => 0,
+        Some(SubstitutionClass::Transition)   => 1,
+        Some(SubstitutionClass::Transversion) => 2,
+    }
+}
+
+pub(super) fn decode_class(code: u8) -> Option<SubstitutionClass> {
+    match code {
+        1 => Some(SubstitutionClass::Transition),
+        2 => Some(SubstitutionClass::Transversion),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+
+    fn pwd(chromosome: u8, position: u32, value: f64) -> Pwd {
+        let mut pwd = Pwd::initialize(Coordinate::new(chromosome, position));
+        pwd.pwd = value;
+        pwd.observations = 1;
+        pwd.with_class(Some(SubstitutionClass::Transversion))
+    }
+
+    fn summary(store: &PwdStore) -> Vec<(u16, u32, f64)> {
+        store.iter().map(|pwd| (pwd.coordinate.chromosome.0, pwd.coordinate.position.0, pwd.pwd)).collect()
+    }
+
+    fn fill(store: &mut PwdStore) -> Result<()> {
+        for (chromosome, position, value) in [(2, 10, 1.0), (2, 30, 0.0), (1, 20, 0.5), (2, 20, 1.0), (1, 20, 0.0), (3, 5, 0.0)] {
+            store.insert(pwd(chromosome, position, value))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn insert_and_iter() -> Result<()> {
+        let mut store = PwdStore::new();
+        fill(&mut store)?;
+        assert_eq!(store.len(), 5);
+        assert_eq!(summary(&store), vec![(1, 20, 0.5), (2, 10, 1.0), (2, 20, 1.0), (2, 30, 0.0), (3, 5, 0.0)]);
+        assert!(!store.insert(pwd(2, 20, 0.0))?);
+        Ok(())
+    }
+
+    #[test]
+    fn get_and_range() -> Result<()> {
+        let mut store = PwdStore::new();
+        fill(&mut store)?;
+        assert_eq!(store.get(&Coordinate::new(2, 20)).map(|pwd| pwd.pwd), Some(1.0));
+        assert_eq!(store.get(&Coordinate::new(2, 20)).and_then(|pwd| pwd.class), Some(SubstitutionClass::Transversion));
+        assert!(!store.contains(&Coordinate::new(2, 25)));
+        assert!(!store.contains(&Coordinate::new(4, 20)));
+
+        let range = store.range(Coordinate::new(2, 15)..Coordinate::new(3, 5));
+        let positions: Vec<Coordinate> = range.map(|pwd| pwd.coordinate).collect();
+        assert_eq!(positions, vec![Coordinate::new(2, 20), Coordinate::new(2, 30)]);
+        Ok(())
+    }
+
+    #[test]
+    fn retain() -> Result<()> {
+        let mut store = PwdStore::new();
+        fill(&mut store)?;
+        store.retain(|pwd| pwd.coordinate.chromosome.0 != 1 && pwd.pwd == 0.0)?;
+        assert_eq!(summary(&store), vec![(2, 30, 0.0), (3, 5, 0.0)]);
+        Ok(())
+    }
+
+    #[test]
+    fn spill() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut expected = PwdStore::new();
+        fill(&mut expected)?;
+
+        let mut store = PwdStore::new();
+        store.spill_to(dir.path())?;
+        fill(&mut store)?;
+        assert!(matches!(store.segments[0].columns, Columns::Mapped(_)));
+        assert!(matches!(store.segments[2].columns, Columns::Heap(_)));
+
+        store.flush()?;
+        assert!(store.segments.iter().all(|segment| matches!(segment.columns, Columns::Mapped(_))));
+        assert_eq!(summary(&store), summary(&expected));
+        assert_eq!(store.get(&Coordinate::new(2, 30)).map(|pwd| pwd.observations), Some(1));
+
+        let spilled = store.spill.as_ref().map(|spill| spill.path.to_path_buf()).expect("Store should have spilled");
+        store.retain(|pwd| pwd.pwd > 0.0)?;
+        assert_eq!(summary(&store), vec![(1, 20, 0.5), (2, 10, 1.0), (2, 20, 1.0)]);
+        assert!(store.segments.iter().all(|segment| matches!(segment.columns, Columns::Mapped(_))));
+
+        // ---- Filtered sites are rewritten within a new file, and the previous one is deleted.
+        assert!(!spilled.exists());
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 1);
+        assert_eq!(store.spill.as_ref().map(|spill| spill.len), Some(24 + 40)); // 1 and 2 sites, padded to 8 bytes.
+        Ok(())
+    }
+
+    #[test]
+    fn reduced_precision() -> Result<()> {
+        let mut site = pwd(1, 10, 1.0 / 3.0);
+        site.hom_alt_sum  = 0.25;
+        site.phred_sums   = [81.0, 64.0];
+        site.observations = 2;
+
+        let mut store = PwdStore::new();
+        store.insert(site)?;
+        let stored = store.get(&Coordinate::new(1, 10)).expect("Site should have been stored");
+        assert!((stored.pwd - 1.0 / 3.0).abs() < 1e-7);
+        assert_eq!(stored.hom_alt_sum, 0.25);
+        assert_eq!(stored.phred_sums, [82.0, 64.0]); // Average phred of 40.5 rounds to 41.
+        assert_eq!(stored.observations, 2);
+        Ok(())
+    }
+}
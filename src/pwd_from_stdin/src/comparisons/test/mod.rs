@@ -6,7 +6,7 @@ pub mod common{
     use super::super::comparison::Comparison;
     use crate::comparisons::Individual;
     use crate::pileup::Pileup;
-    use genome::Genome;
+    use genome::{Genome, jackknife::JackknifeBlocks};
     use genome::snp::Allele;
     //use rand::prelude::SliceRandom;
     
@@ -23,7 +23,7 @@ pub mod common{
         };
     
         let default_genome = Genome::default();
-        Comparison::new([mockind_0, mockind_1], self_comparison, &default_genome, MOCK_BLOCKSIZE)
+        Comparison::new([mockind_0, mockind_1], self_comparison, JackknifeBlocks::new(&default_genome, MOCK_BLOCKSIZE))
     }
     
     
@@ -1,10 +1,21 @@
 use thiserror::Error;
 
+use genome::coordinate::Coordinate;
+
 #[derive(Error, Debug)]
 pub enum PwdFromStdinError {
     #[error("The use of '--exclude-transitions' requires an input SNP targets file, with known <REF> and <ALT> columns. Please provide this file, using the '--targets' argument")]
     MissingTargetPositions,
 
+    #[error("The use of '--filter-deamination' along with '--bam' requires an input SNP targets file, with a known <REF> column. Please provide this file, using the '--targets' argument")]
+    MissingDeaminationReference,
+
     #[error("Cannot filter known variants when REF/ALT allele are unknown! Please use a different file format.")]
-    MissingKnownVariant
+    MissingKnownVariant,
+
+    #[error("Requested sample index {index} is out of range: the provided genotype dataset only contains {count} individuals.")]
+    InvalidSampleIndex{index: usize, count: usize},
+
+    #[error("Position {} lies beyond the end of chromosome {} ({length} bp). Is the input aligned against the selected genome build? (see --genome-build and --genome)", coordinate.position, coordinate.chromosome)]
+    OutOfBounds{coordinate: Coordinate, length: u32},
 }
\ No newline at end of file
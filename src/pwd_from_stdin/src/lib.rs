@@ -1,21 +1,44 @@
-use std::{io, fs::File, collections::HashMap, io::{BufReader, BufRead}};
+use std::{io, collections::HashMap, io::BufRead, path::Path};
 
 use ahash::AHashSet;
+use itertools::Itertools;
 
 use log::{warn, info, debug};
 use located_error::prelude::*;
 
-use genome::{Genome, coordinate::Coordinate};
-use grups_io::{parse::{self, FileKey}, read::SNPReader};
+use parser::PwdMode;
+use genome::{Genome, GenomeMask, Phred, SNPCoord, coordinate::{ChrIdx, Coordinate}, jackknife::JackknifeBlocks, snp::{Allele, SubstitutionClass}, species::SexChromosome};
+use grups_io::{
+    parse::{self, FileKey},
+    read::{
+        SNPReader, SampleSheet,
+        bam::parse_bam_list,
+        compression,
+        likelihoods::{GenotypeLikelihoodReader, LikelihoodField},
+        pseudo_haploid::{GenotypeFormat, PseudoHaploidReader},
+        tabix::{Region, TabixIndex, TabixReader}
+    }
+};
 
 pub mod pileup;
+use pileup::{BamFilters, BamPileup, DamageFilter, MergedPileup, PileupColumns};
 
 pub mod comparisons;
-use comparisons::Comparisons;
+use comparisons::{Comparisons, PairSelection};
 
 pub mod error;
 pub use error::PwdFromStdinError;
 
+mod pipeline;
+use pipeline::{Pipeline, PipelineSettings, CHUNK_SIZE};
+
+/// Maximal distance (in base pairs) between two consecutive targets for them to be queried within the same region, when
+/// using an indexed pileup. Larger gaps are skipped by seeking.
+const MAX_TARGET_GAP: u64 = 100_000;
+
+/// Base quality assigned to pseudo-haploid genotype calls, when using `--eigenstrat` or `--plink`.
+const HAPLOID_CALL_PHRED: u8 = 40;
+
 /// Run pwd-from-stdin, using the user-provided parameters and input file.
 /// Returns a `Comparisons` struct, containing the results.
 /// 
@@ -26,42 +49,79 @@ pub fn run<'a>(
     com_cli           : &'a parser::Common,
     pwd_cli           : &'a parser::PwdFromStdin,
     requested_samples : &'a [usize],
+    sample_sheet      : Option<&'a SampleSheet>,
     genome            : &'a Genome,
+    sex_chromosome    : &'a SexChromosome,
 ) -> Result<(Comparisons, HashMap<String, String>)> {
     info!("Running 'pwd-from-stdin' module...");
     // ----------------------------- Sanity checks.
     pwd_cli.check_depth()?; // Ensure min_depths are > 2 when allowing self-comparisons
     com_cli.check_input()?; // Ensure the user has either requested stdin or --pileup
 
-    if pwd_cli.min_depth.len() < requested_samples.len() {
+    if pwd_cli.min_depth.len() < requested_samples.len() && !sample_sheet.is_some_and(|sheet| sheet.iter().all(|sample| sample.min_depth.is_some())) {
         warn!("Number of provided --min-depth values is less than that of --samples. Values will be recycled.");
     }
 
+    // ----------------------------- Open pseudo-haploid genotype dataset, if requested.
+    let genotype_reader = match (&com_cli.eigenstrat, &com_cli.plink) {
+        (Some(prefix), _)    => Some(PseudoHaploidReader::new(prefix, GenotypeFormat::Eigenstrat)?),
+        (None, Some(prefix)) => Some(PseudoHaploidReader::new(prefix, GenotypeFormat::Plink)?),
+        (None, None)         => None,
+    };
+
+    // ----------------------------- Open genotype likelihoods file, if requested.
+    let likelihood_reader = match &com_cli.gl_vcf {
+        Some(path) => {
+            let field = pwd_cli.gl_field.map(|field| match field {
+                parser::LikelihoodField::Gp => LikelihoodField::Gp,
+                parser::LikelihoodField::Pl => LikelihoodField::Pl,
+                parser::LikelihoodField::Gl => LikelihoodField::Gl,
+            });
+            Some(GenotypeLikelihoodReader::new(path, field, com_cli.decompression_threads)?)
+        },
+        None => None,
+    };
+
+    // ---- Name individuals after their dataset identifier, unless the user explicitly provided names (or a sample sheet).
+    let dataset_samples = genotype_reader.as_ref().map(PseudoHaploidReader::individuals)
+        .or(likelihood_reader.as_ref().map(GenotypeLikelihoodReader::samples));
+    let sample_names = match (sample_sheet, dataset_samples) {
+        (Some(sheet), _) => sheet.names(),
+        (None, Some(samples)) if com_cli.sample_names.is_empty() => requested_samples.iter()
+            .map(|&index| samples.get(index).cloned()
+                .ok_or(PwdFromStdinError::InvalidSampleIndex{index, count: samples.len()})
+            )
+            .collect::<Result<Vec<String>, _>>()
+            .loc("While retrieving sample names from the genotype dataset")?,
+        _ => com_cli.sample_names.clone(),
+    };
+
+    if genotype_reader.is_some() && pwd_cli.self_comparison {
+        warn!("Self-comparisons require a minimal depth of 2, and cannot be performed on pseudo-haploid genotype calls.");
+    }
+
     // ----------------------------- Parse Comparisons
     info!("Parsing Requested comparisons...");
-    let mut comparisons = Comparisons::parse(requested_samples, &pwd_cli.min_depth, &com_cli.sample_names, pwd_cli.self_comparison, genome, pwd_cli.blocksize);
+    let blocks = match &pwd_cli.block_bed {
+        Some(bed) => JackknifeBlocks::from_bed(bed, genome).with_loc(|| format!("While parsing jackknife blocks from {}", bed.display()))?,
+        None      => JackknifeBlocks::new(genome, pwd_cli.blocksize),
+    };
+    let pairs = match &pwd_cli.pairs {
+        Some(path)                        => PairSelection::from_file(path)?,
+        None if pwd_cli.versus.is_empty() => PairSelection::All,
+        None                              => PairSelection::Groups(parser::parse_user_ranges::<usize>(&pwd_cli.samples, "samples")?.len()),
+    };
+    let min_depths = sample_sheet.map_or_else(|| pwd_cli.min_depth.clone(), |sheet| sheet.min_depths(&pwd_cli.min_depth));
+    let mut comparisons = Comparisons::parse(requested_samples, &min_depths, &pwd_cli.max_depth, &sample_names, pwd_cli.self_comparison, &pairs, &blocks)?;
+    info!("Comparing {} pair(s) of individuals.", comparisons.len());
+    if let Some(dir) = &pwd_cli.spill_dir {
+        comparisons.spill_positions(dir)?;
+    }
 
     // ----------------------------- Prepare output files
-    // ---- Add pwd files.
-    let mut output_files = parse::get_output_files(
-        &mut com_cli.get_file_prefix(None)?,  // extract the user requested file prefix
-        com_cli.overwrite,                    // Should we allow file overwriting ?
-        FileKey::Ext,                         // What key are we using to hash these files ?
-        &[String::new()],                    // Vector of filename suffixes.
-        &["pwd"]                              // Vector of file extensions.
-    )?;
-
-    // ---- Add blocks files.
-    output_files.extend(
-        parse::get_output_files(
-            &mut com_cli.get_file_prefix(Some("blocks/"))?,
-            com_cli.overwrite,
-            FileKey::Suffix,
-            &comparisons.get_pairs(),
-            &["blk"]
-        )?);
-
-    debug!("Output files: {output_files:#?}");
+    // ---- Transition/Transversion breakdowns require known REF/ALT alleles.
+    let known_classes = com_cli.targets.is_some() || com_cli.gl_vcf.is_some();
+    let output_files = prepare_output_files(com_cli, pwd_cli, &comparisons, known_classes)?;
 
 
     // ----------------------------- Parse target_positions
@@ -78,47 +138,93 @@ pub fn run<'a>(
         return Err(PwdFromStdinError::MissingTargetPositions).loc("While initializing main event loop")
     }
 
+    // Early exit if the user requested deamination filtration on alignment files without specifying an SNP panel:
+    // reference alleles are unknown, and the filter would have no effect.
+    if ! target_required && pwd_cli.filter_deamination && com_cli.has_bam_input() {
+        return Err(PwdFromStdinError::MissingDeaminationReference).loc("While initializing main event loop")
+    }
+
     // ----------------------------- Parse requested Chromosomes
-    let valid_chromosomes : Vec<u8> = match com_cli.chr.clone() {
-        None         => genome.keys().copied().map(Into::into).collect(),
+    let valid_chromosomes : Vec<ChrIdx> = match &com_cli.chr {
+        None         => genome.keys().copied().collect(),
         Some(vector) => {
-            let vec: Vec<String> = vector.into_iter().map(|c| match c.as_ref() {
-                "X" | "chrX" => "88".to_string(),
-                other => other.to_string()
-            }).collect::<Vec<String>>();
-            parser::parse_user_ranges(&vec, "chr")?
+            let mut chromosomes = Vec::with_capacity(vector.len());
+            for chr in vector {
+                // ---- Contig names are resolved first. Anything else should be a range of numbered chromosomes.
+                match chr.parse::<ChrIdx>() {
+                    Ok(chromosome) => chromosomes.push(chromosome),
+                    Err(_)         => chromosomes.extend(parser::parse_user_ranges::<u8>(std::slice::from_ref(chr), "chr")?.into_iter().map(ChrIdx::from)),
+                }
+            }
+            chromosomes.sort_unstable();
+            chromosomes.dedup();
+            chromosomes
         }
     };
     info!("Valid chromosomes: {valid_chromosomes:?}");
 
-    // ---------------------------- Choose between file handle or standard input
-    info!("Opening pileup...");   
-    let pileup_reader: Box<dyn BufRead> = match &com_cli.pileup {
-        None => Box::new(BufReader::new(io::stdin())),
-        Some(filename) => Box::new(BufReader::new(File::open(filename)?))
-    };
+    // ----------------------------- Parse genomic inclusion/exclusion masks.
+    let mut mask = GenomeMask::new(com_cli.include.as_deref(), com_cli.mask.as_deref())?;
+    sex_chromosome.mask_pseudo_autosomal_regions(&mut mask);
+
+    // ----------------------------- Prepare damage and mapping quality filtration.
+    let damage_filter = DamageFilter{deamination: pwd_cli.filter_deamination, trim: pwd_cli.trim_reads};
+    let columns       = PileupColumns{mapping_qualities: pwd_cli.mapping_qualities, read_positions: pwd_cli.read_positions};
+    let read_input    = com_cli.has_bam_input() || pwd_cli.read_positions;
+    if damage_filter.trim > 0 && !read_input {
+        warn!("--trim-reads requires read positions. Use --read-positions along with 'samtools mpileup --output-BP-5', or --bam. Reads will not be trimmed.");
+    }
+    if pwd_cli.min_mapq > 0 && !(com_cli.has_bam_input() || pwd_cli.mapping_qualities) {
+        warn!("--min-mapq requires mapping qualities. Use --mapping-qualities along with 'samtools mpileup --output-MQ', or --bam. Reads will not be filtered according to their mapping quality.");
+    }
 
-    // ---------------------------- Read Pileup
-    info!("Parsing pileup...");   
+    // ---------------------------- Prepare read sampling, when using random-read pwd estimation.
+    if pwd_cli.pwd_mode == PwdMode::RandomRead {
+        info!("Sampling {} random read(s) per site (seed: {})", pwd_cli.sampled_reads, pwd_cli.seed);
+        if likelihood_reader.is_some() {
+            warn!("--pwd-mode random-read has no effect when using --gl-vcf. Pairwise differences will be computed from genotype posteriors.");
+        }
+    }
+
+    // ---------------------------- Filter a single pileup line. Returns the substitution class of the site if it should
+    //                              be compared, or `None` if it should be skipped.
     let loc_msg = {|c: &Coordinate| format!("While parsing coordinate coordinate: {c}")};
-    for (i, entry) in pileup_reader.lines().enumerate() {
-        // ----------------------- Parse line.
-        let entry = entry?;
-        let mut line = pileup::Line::new(&entry, !pwd_cli.consider_dels)
-            .with_loc(|| format!("While attempting to parse pileup line n°{}", i+1))?;
+    let is_requested = |coordinate: &Coordinate| {
+        valid_chromosomes.contains(&coordinate.chromosome)                // Skip if this is not a valid chromosome.
+            && (!target_required || target_positions.contains(coordinate)) // Skip if we're targeting snps + the current coordinate is not found.
+            && mask.allows(coordinate)                                     // Skip if the coordinate is masked.
+    };
 
+    // ---- Positions exceeding the length of their chromosome are a sign of a genome build mismatch.
+    let check_bounds = |coordinate: &Coordinate| -> Result<()> {
+        if genome.out_of_bounds(coordinate) {
+            let length = genome[&coordinate.chromosome].length;
+            return Err(PwdFromStdinError::OutOfBounds{coordinate: *coordinate, length}).with_loc(|| loc_msg(coordinate))
+        }
+        Ok(())
+    };
 
+    let filter_line = |line: &mut pileup::Line| -> Result<Option<Option<SubstitutionClass>>> {
         // ----------------------- Check if line should be skipped.
-        if ! valid_chromosomes.contains(&line.coordinate.chromosome) {
-            continue  // Skip line if this is not a valid chromosome. 
+        if ! is_requested(&line.coordinate) {
+            return Ok(None)
         }
+        check_bounds(&line.coordinate)?;
 
-        if target_required && !target_positions.contains(&line.coordinate) {  
-            continue  // Skip line if we're targeting snps + the current line is not found. 
+        // ------------------------ Alignment files lack reference alleles: retrieve these from the targets, if known.
+        if let Some(target) = target_positions.get(&line.coordinate) {
+            line.fill_reference(target.reference);
         }
 
         // ------------------------ Apply quality filtering on all individuals.
         line.filter_base_quality(com_cli.min_qual);
+        line.filter_mapping_quality(pwd_cli.min_mapq);
+
+        // ------------------------ Apply damage filtering if requested.
+        if damage_filter.is_active() {
+            let reference = target_positions.get(&line.coordinate).map_or(Allele::N, |snp| snp.reference);
+            line.filter_damage(&damage_filter, reference);
+        }
 
         // ------------------------ Apply target filtration if requested.
         if pwd_cli.known_variants {
@@ -128,25 +234,283 @@ pub fn run<'a>(
             line.filter_known_variants(known_coord)?;
         }
 
-        // ----------------------- Compute PWD (or simply print the line if there's an existing overlap)        
-        for comparison in comparisons.iter_mut() {
-            
-            let satisfactory_depth = comparison.satisfiable_depth(&line.individuals)
-                .with_context(|| loc_msg(&line.coordinate))
-                .with_loc(|| format!("While comparing pair {}", comparison.get_pair()))?;
+        Ok(Some(target_positions.get(&line.coordinate).and_then(SNPCoord::substitution_class)))
+    };
+
+    // ---------------------------- Parse, filter and compare pileup lines in parallel, by chunks of `CHUNK_SIZE`.
+    let settings = PipelineSettings{
+        ignore_dels  : !pwd_cli.consider_dels,
+        columns,
+        pwd_mode     : pwd_cli.pwd_mode,
+        sampled_reads: pwd_cli.sampled_reads,
+        filter_sites : pwd_cli.filter_sites,
+        seed         : pwd_cli.seed,
+    };
+    let mut pipeline = Pipeline::new(&comparisons, filter_line, settings, com_cli.threads)?;
+
+    // ---------------------------- Build the pileup from alignment files if requested.
+    let mut bam_paths = com_cli.bam.clone();
+    if let Some(bam_list) = &com_cli.bam_list {
+        bam_paths.extend(parse_bam_list(bam_list).loc("While parsing --bam-list")?);
+    }
+
+    // ---------------------------- Search for a tabix index, if the user requested a subset of the pileup.
+    let tabix_index = match &com_cli.pileup {
+        Some(filename) if com_cli.chr.is_some() || target_required => TabixIndex::find(Path::new(filename)),
+        _ => None
+    };
+
+    if let Some(genotype_reader) = genotype_reader {
+        info!("Parsing pseudo-haploid genotype calls...");
+        let phred = Phred::new(HAPLOID_CALL_PHRED);
+        for chunk in &genotype_reader.chunks(CHUNK_SIZE) {
+            let lines = chunk.map(|record| record.map(|record| pileup::Line::from_haploid_calls(&record, phred)))
+                .collect::<Result<Vec<_>>>()
+                .loc("While reading pseudo-haploid genotype calls")?;
+            pipeline.process_lines(&mut comparisons, lines)?;
+        }
+    } else if let Some(mut likelihood_reader) = likelihood_reader {
+        info!("Parsing genotype likelihoods...");
+        while let Some(record) = likelihood_reader.read_record()? {
+            let coordinate = record.coordinate.coordinate;
+            if ! is_requested(&coordinate) {
+                continue
+            }
+            check_bounds(&coordinate)?;
+
+            // ------------------------ Skip sites whose alleles do not match the known variant, if requested.
+            if pwd_cli.known_variants {
+                let known_coord = target_positions.get(&coordinate)
+                    .ok_or(PwdFromStdinError::MissingKnownVariant)
+                    .with_loc(|| loc_msg(&coordinate))?;
+                let alleles = [record.coordinate.reference, record.coordinate.alternate];
+                if ! (alleles.contains(&known_coord.reference) && alleles.contains(&known_coord.alternate)) {
+                    continue
+                }
+            }
+
+            let class = record.coordinate.substitution_class();
+            for comparison in comparisons.iter_mut() {
+                let posteriors = comparison.satisfiable_posteriors(&record.posteriors)
+                    .with_context(|| loc_msg(&coordinate))
+                    .with_loc(|| format!("While comparing pair {}", comparison.get_pair()))?;
 
-            if satisfactory_depth {
+                let Some(posteriors) = posteriors else { continue };
                 if pwd_cli.filter_sites {
-                    println!("{}", &entry);
+                    println!("{}", likelihood_reader.line());
                 } else {
-                    comparison.compare(&line)?;
+                    comparison.compare_posteriors(coordinate, posteriors, class)?;
                 }
             }
         }
+    } else if !bam_paths.is_empty() {
+        info!("Opening alignment files: {bam_paths:?}");
+        let filters = BamFilters{min_mapq: pwd_cli.min_mapq, keep_duplicates: pwd_cli.keep_duplicates};
+        let bam_pileup = BamPileup::new(&bam_paths, filters, !pwd_cli.consider_dels, com_cli.decompression_threads)?;
+
+        info!("Building pileup from alignment files...");
+        for chunk in &bam_pileup.chunks(CHUNK_SIZE) {
+            let lines = chunk.collect::<Result<Vec<_>>>().loc("While building pileup from alignment files")?;
+            pipeline.process_lines(&mut comparisons, lines)?;
+        }
+    } else if !com_cli.merge_pileups.is_empty() {
+        info!("Opening pileup files: {:?}", com_cli.merge_pileups);
+        let merged_pileup = MergedPileup::new(&com_cli.merge_pileups, !pwd_cli.consider_dels, columns, com_cli.decompression_threads, genome.order())?;
+
+        info!("Merging pileup files...");
+        for chunk in &merged_pileup.chunks(CHUNK_SIZE) {
+            let lines = chunk.collect::<Result<Vec<_>>>().loc("While merging pileup files")?;
+            pipeline.process_lines(&mut comparisons, lines)?;
+        }
+    } else if let (Some(filename), Some(index_path)) = (&com_cli.pileup, tabix_index) {
+        // ---------------------------- Only seek to the requested regions of an indexed pileup.
+        info!("Found pileup index {}. Querying requested regions...", index_path.display());
+        let mut reader = TabixReader::new(Path::new(filename), &index_path)?;
+        let regions = tabix_regions(reader.index(), &valid_chromosomes, &target_positions);
+        debug!("Tabix query regions: {}", regions.iter().map(ToString::to_string).collect::<Vec<String>>().join(", "));
+
+        info!("Parsing pileup...");
+        for region in &regions {
+            for chunk in &reader.fetch(region).chunks(CHUNK_SIZE) {
+                let entries = chunk.collect::<Result<Vec<String>>>()?;
+                pipeline.process_entries(&mut comparisons, entries, |_| format!("within region {region}"))?;
+            }
+        }
+    } else {
+        // ---------------------------- Choose between file handle or standard input
+        info!("Opening pileup...");   
+        let threads = com_cli.decompression_threads;
+        let pileup_reader: Box<dyn BufRead> = match &com_cli.pileup {
+            None           => compression::from_reader(io::stdin(), threads),
+            Some(filename) => compression::open(Path::new(filename), threads),
+        }.loc("While opening the input pileup")?;
+
+        // ---------------------------- Read Pileup
+        info!("Parsing pileup...");   
+        let mut offset = 0;
+        for chunk in &pileup_reader.lines().chunks(CHUNK_SIZE) {
+            let entries = chunk.collect::<Result<Vec<String>, _>>()?;
+            let n = entries.len();
+            pipeline.process_entries(&mut comparisons, entries, |i| format!("n°{}", offset + i + 1))?;
+            offset += n;
+        }
+    }
+
+    // ---- Jackknife blocks of equal SNP-counts can only be defined once every overlapping SNP is known.
+    if let Some(snps_per_block) = pwd_cli.snps_per_block {
+        comparisons.split_blocks_by_snp_count(snps_per_block);
     }
 
     // Run two-pass variance estimation algorithm.
     comparisons.update_variance_unbiased();
 
+    // ---- Ensure the last compared chromosome is spilled to disk as well, if requested.
+    comparisons.flush_positions()?;
+
+    // ---- Write a snapshot of our observations, if requested.
+    if let Some(output_file) = output_files.get("obs") {
+        info!("Writing observations to {output_file}");
+        comparisons.write_observations(Path::new(output_file))?;
+    }
+
+    Ok((comparisons, output_files))
+}
+
+/// Restore a set of `Comparisons` from a binary snapshot of observations (see `--write-observations`), instead of
+/// parsing an input pileup. Returns the restored `Comparisons`, along with their output files.
+///
+/// # Errors
+/// - If the snapshot is invalid, or cannot be read.
+/// - If any of the output files is invalid (`PermissionDenied`, etc.)
+pub fn load(
+    com_cli     : &parser::Common,
+    pwd_cli     : &parser::PwdFromStdin,
+    observations: &Path,
+) -> Result<(Comparisons, HashMap<String, String>)> {
+    info!("Loading observed comparisons from {}...", observations.display());
+    let mut comparisons = Comparisons::read_observations(observations, pwd_cli.spill_dir.as_deref())?;
+
+    let known_classes = comparisons.iter().any(|comparison| comparison.positions.iter().any(|pwd| pwd.class.is_some()));
+    let output_files  = prepare_output_files(com_cli, pwd_cli, &comparisons, known_classes)?;
+
+    comparisons.update_variance_unbiased();
     Ok((comparisons, output_files))
 }
+
+/// Define the output files of `pwd-from-stdin`: `.pwd`, `.chr` and `.blk` files, along with `.tstv` files if the
+/// substitution class of SNPs is known, and `.obs` files if the user requested to write observations.
+fn prepare_output_files(
+    com_cli      : &parser::Common,
+    pwd_cli      : &parser::PwdFromStdin,
+    comparisons  : &Comparisons,
+    known_classes: bool,
+) -> Result<HashMap<String, String>> {
+    // ---- Add pwd, per-chromosome and per-substitution class breakdown files.
+    let mut extensions = vec!["pwd", "chr"];
+    if known_classes {
+        extensions.push("tstv");
+    }
+    if pwd_cli.write_observations {
+        extensions.push("obs");
+    }
+    let mut output_files = parse::get_output_files(
+        &mut com_cli.get_file_prefix(None)?,  // extract the user requested file prefix
+        com_cli.overwrite,                    // Should we allow file overwriting ?
+        FileKey::Ext,                         // What key are we using to hash these files ?
+        &[String::new()],                    // Vector of filename suffixes.
+        &extensions                           // Vector of file extensions.
+    )?;
+
+    // ---- Add blocks files.
+    output_files.extend(
+        parse::get_output_files(
+            &mut com_cli.get_file_prefix(Some("blocks/"))?,
+            com_cli.overwrite,
+            FileKey::Suffix,
+            &comparisons.get_pairs(),
+            &["blk"]
+        )?);
+
+    debug!("Output files: {output_files:#?}");
+    Ok(output_files)
+}
+
+/// Build the list of regions that should be queried from an indexed pileup, ordered as within the index.
+/// 
+/// Sequences whose name cannot be parsed as a valid chromosome, or which were not requested by the user are skipped. When
+/// `target_positions` is not empty, nearby targets are clustered into regions, and sequences that do not carry any
+/// target are skipped as well.
+fn tabix_regions(index: &TabixIndex, valid_chromosomes: &[ChrIdx], target_positions: &AHashSet<SNPCoord>) -> Vec<Region> {
+    let mut regions = Vec::new();
+    for name in index.names() {
+        let Ok(chromosome) = name.parse::<ChrIdx>() else { continue };
+        if ! valid_chromosomes.contains(&chromosome) {
+            continue
+        }
+
+        if target_positions.is_empty() {
+            regions.push(Region::whole(name));
+            continue
+        }
+
+        let mut positions = target_positions.iter()
+            .filter(|snp| snp.coordinate.chromosome == chromosome)
+            .map(|snp| u64::from(snp.coordinate.position.0))
+            .collect::<Vec<u64>>();
+        positions.sort_unstable();
+
+        let Some(&first) = positions.first() else { continue };
+        let mut region = Region::new(name, first - 1, first);
+        for position in positions {
+            if position - region.end > MAX_TARGET_GAP {
+                regions.push(region.clone());
+                region.start = position - 1;
+            }
+            region.end = position;
+        }
+        regions.push(region);
+    }
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genome::snp::Allele;
+
+    fn mock_index(names: &[&str]) -> Result<TabixIndex> {
+        let l_nm = names.iter().map(|name| name.len() + 1).sum::<usize>();
+        let mut data = b"TBI\x01".to_vec();
+        for value in [names.len(), 0, 1, 2, 0, usize::from(b'#'), 0, l_nm] {
+            data.extend(i32::try_from(value)?.to_le_bytes());
+        }
+        for name in names {
+            data.extend(name.as_bytes());
+            data.push(0);
+        }
+        for _ in names {
+            data.extend([0i32.to_le_bytes(), 0i32.to_le_bytes()].concat()); // n_bin, n_intv
+        }
+        Ok(TabixIndex::from_bytes(&data)?)
+    }
+
+    #[test]
+    fn tabix_regions_chromosomes() -> Result<()> {
+        let index = mock_index(&["chr21", "chr22", "chrX", "chrUn_gl000220"])?;
+        let regions = tabix_regions(&index, &[ChrIdx(22), ChrIdx::X], &AHashSet::new());
+        assert_eq!(regions, vec![Region::whole("chr22"), Region::whole("chrX")]);
+        Ok(())
+    }
+
+    #[test]
+    fn tabix_regions_targets() -> Result<()> {
+        let index   = mock_index(&["21", "22"])?;
+        let targets = [(22u8, 500_000), (22, 100), (22, 1_000), (22, 1_000 + MAX_TARGET_GAP), (21, 10)].into_iter()
+            .map(|(chr, pos)| Ok(SNPCoord::new(chr, u32::try_from(pos)?, Allele::A, Allele::C)))
+            .collect::<Result<AHashSet<SNPCoord>>>()?;
+
+        let regions = tabix_regions(&index, &[ChrIdx(22)], &targets);
+        assert_eq!(regions, vec![Region::new("22", 99, 1_000 + MAX_TARGET_GAP), Region::new("22", 499_999, 500_000)]);
+        Ok(())
+    }
+}
@@ -0,0 +1,425 @@
+use std::path::PathBuf;
+
+use genome::{Nucleotide, coordinate::{ChrIdx, Coordinate, Position}, snp::Allele};
+use grups_io::read::bam::{BamReader, BamRecord, CigarOp};
+use located_error::prelude::*;
+use log::{debug, warn};
+
+use super::{Line, Pileup, PileupError};
+
+/// Read-level filters applied when building pileups from alignment files.
+/// - `min_mapq`       : minimum mapping quality of a read.
+/// - `keep_duplicates`: do not discard reads flagged as PCR/optical duplicates.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BamFilters {
+    pub min_mapq       : u8,
+    pub keep_duplicates: bool,
+}
+
+impl BamFilters {
+    /// Return `true` if a given record should be included within the pileup.
+    /// Secondary and QC-failed alignments are always discarded, as is the default for `samtools mpileup`.
+    fn accept(&self, record: &BamRecord) -> bool {
+        !record.is_secondary_or_qc_fail()
+            && record.mapq >= self.min_mapq
+            && (self.keep_duplicates || !record.is_duplicate())
+    }
+}
+
+/// Aligned nucleotides of a single read.
+/// - `start`: 0-based leftmost reference position of the alignment.
+/// - `end`  : 0-based reference position right after the end of the alignment (exclusive).
+/// - `bases`: aligned nucleotides, indexed by their offset from `start`. Reference skips (and deletions,
+///   when these are ignored) are set to `None`
+struct AlignedRead {
+    start: i32,
+    end  : i32,
+    bases: Vec<Option<Nucleotide>>,
+}
+
+impl AlignedRead {
+    fn new(record: &BamRecord, ignore_dels: bool) -> Self {
+        let missing_quals = record.missing_qualities();
+        let nucleotide = |qpos: usize, base: Option<Allele>| {
+            let qual  = if missing_quals { 0 } else { record.qual.get(qpos).copied().unwrap_or(0) };
+            let base  = base.or_else(|| record.seq.get(qpos).and_then(|b| Allele::try_from(char::from(*b)).ok()))?;
+            let end_distance = record.seq.len().checked_sub(qpos + 1)
+                .map(|from_end| usize::min(qpos, from_end))
+                .and_then(|distance| u32::try_from(distance).ok());
+            Some(Nucleotide::new(base, qual).with_read_context(record.is_reverse(), end_distance).with_mapping_quality(record.mapq))
+        };
+
+        let mut bases = Vec::with_capacity(record.reference_span() as usize);
+        let mut qpos  = 0;
+        for cigar in &record.cigar {
+            let len = cigar.len as usize;
+            match cigar.op {
+                CigarOp::Match | CigarOp::Equal | CigarOp::Diff => {
+                    bases.extend((qpos..qpos+len).map(|i| nucleotide(i, None).or(Some(Nucleotide::new(Allele::N, 0)))));
+                },
+                CigarOp::Del => {
+                    let deletion = if ignore_dels { None } else { nucleotide(qpos, Some(Allele::D)) };
+                    bases.resize(bases.len() + len, deletion);
+                },
+                CigarOp::RefSkip => bases.resize(bases.len() + len, None),
+                CigarOp::Ins | CigarOp::SoftClip | CigarOp::HardClip | CigarOp::Pad => (),
+            }
+            if cigar.op.consumes_query() {
+                qpos += len;
+            }
+        }
+        let end = record.pos + i32::try_from(bases.len()).unwrap_or(i32::MAX);
+        Self{start: record.pos, end, bases}
+    }
+
+    /// Return the nucleotide aligned at a given 0-based reference position, if any.
+    fn get(&self, pos: i32) -> Option<Nucleotide> {
+        let offset = usize::try_from(pos - self.start).ok()?;
+        self.bases.get(offset).copied().flatten()
+    }
+}
+
+/// Pileup state of a single alignment file.
+/// - `reader`: sequential reader of the alignment file.
+/// - `next`  : next record that passed filtration, but is still ahead of the current position.
+/// - `last`  : (`ref_id`, `pos`) of the last read record. Used to ensure the file is sorted.
+/// - `active`: reads overlapping the current position.
+struct BamSample {
+    reader: BamReader,
+    next  : Option<BamRecord>,
+    last  : (i32, i32),
+    active: Vec<AlignedRead>,
+}
+
+impl BamSample {
+    fn new(reader: BamReader) -> Self {
+        Self{reader, next: None, last: (0, 0), active: Vec::new()}
+    }
+
+    /// Fetch the next record passing filtration and set it as `self.next`.
+    fn advance(&mut self, index: usize, contigs: &[Option<ChrIdx>], filters: &BamFilters) -> Result<()> {
+        self.next = None;
+        while let Some(record) = self.reader.read_record()? {
+            if record.is_unmapped() {
+                continue
+            }
+            let coordinate = (record.ref_id, record.pos);
+            if coordinate < self.last {
+                return Err(PileupError::UnsortedBam(index)).loc("While building pileup from alignment files")
+            }
+            self.last = coordinate;
+
+            let valid_contig = usize::try_from(record.ref_id).ok()
+                .and_then(|idx| contigs.get(idx))
+                .is_some_and(Option::is_some);
+            if valid_contig && filters.accept(&record) {
+                self.next = Some(record);
+                break
+            }
+        }
+        Ok(())
+    }
+
+    /// Move every record starting at, or before `cursor` within the set of active reads.
+    fn fill(&mut self, index: usize, cursor: (i32, i32), contigs: &[Option<ChrIdx>], filters: &BamFilters, ignore_dels: bool) -> Result<()> {
+        while self.next.as_ref().is_some_and(|record| (record.ref_id, record.pos) <= cursor) {
+            let record = self.next.take().expect("Next record should be available");
+            let read = AlignedRead::new(&record, ignore_dels);
+            if read.end > cursor.1 {
+                self.active.push(read);
+            }
+            self.advance(index, contigs, filters)?;
+        }
+        Ok(())
+    }
+
+    /// Build the `Pileup` of this sample at a given 0-based position.
+    fn pileup(&self, pos: i32) -> Pileup {
+        let nucleotides: Vec<Nucleotide> = self.active.iter().filter_map(|read| read.get(pos)).collect();
+        let depth = u16::try_from(nucleotides.len()).unwrap_or(u16::MAX);
+        Pileup{depth, nucleotides}
+    }
+}
+
+/// Multi-sample pileup engine, building pileup `Line`s directly from a set of coordinate-sorted alignment files.
+/// Each alignment file is considered as a single individual, following the order in which they were provided.
+///
+/// Lines are generated for every position covered by at least one read, in any of the alignment files. Since no
+/// reference sequence is used, the reference allele of each `Line` is always set to `N`.
+///
+/// # Fields:
+/// - `samples`    : pileup state of each alignment file.
+/// - `contigs`    : chromosome index of each reference sequence. Set to `None` for unsupported contigs.
+/// - `filters`    : read-level filters.
+/// - `ignore_dels`: do not include deletions within the pileup.
+/// - `cursor`     : current 0-based (`ref_id`, `pos`) coordinate.
+pub struct BamPileup {
+    samples    : Vec<BamSample>,
+    contigs    : Vec<Option<ChrIdx>>,
+    filters    : BamFilters,
+    ignore_dels: bool,
+    cursor     : (i32, i32),
+}
+
+impl BamPileup {
+    /// Open a set of alignment files and instantiate a new `BamPileup`
+    ///
+    /// # Errors
+    /// - if any of the provided alignment files cannot be opened, or is not a valid BAM file.
+    /// - `BamHeaderMismatch` if the reference dictionaries of the alignment files differ.
+    pub fn new(paths: &[PathBuf], filters: BamFilters, ignore_dels: bool, threads: usize) -> Result<Self> {
+        let readers = paths.iter()
+            .map(|path| BamReader::new(path, threads))
+            .collect::<Result<Vec<BamReader>>>()?;
+        Self::from_readers(readers, filters, ignore_dels)
+    }
+
+    /// Instantiate a new `BamPileup` from a set of already opened `BamReader`.
+    ///
+    /// # Errors
+    /// - `BamHeaderMismatch` if the reference dictionaries of the alignment files differ.
+    /// - if the first record of any alignment file cannot be read.
+    pub fn from_readers(readers: Vec<BamReader>, filters: BamFilters, ignore_dels: bool) -> Result<Self> {
+        let loc_msg = "While initializing pileup from alignment files";
+        let Some(first) = readers.first() else {
+            return Err(PileupError::MissingBam).loc(loc_msg)
+        };
+
+        // ---- Ensure all files share the same reference dictionary.
+        if let Some(i) = readers.iter().position(|reader| reader.references() != first.references()) {
+            return Err(PileupError::BamHeaderMismatch(i)).loc(loc_msg)
+        }
+
+        let contigs: Vec<Option<ChrIdx>> = first.references().iter().map(|reference| {
+            let chr = reference.name.parse::<ChrIdx>().ok();
+            if chr.is_none() {
+                debug!("Skipping unsupported contig '{}' found within alignment files.", reference.name);
+            }
+            chr
+        }).collect();
+
+        if contigs.iter().all(Option::is_none) {
+            warn!("None of the reference sequences found within the provided alignment files could be parsed as a valid chromosome.");
+        }
+
+        let mut samples: Vec<BamSample> = readers.into_iter().map(BamSample::new).collect();
+        for (i, sample) in samples.iter_mut().enumerate() {
+            sample.advance(i, &contigs, &filters).loc(loc_msg)?;
+        }
+
+        Ok(Self{samples, contigs, filters, ignore_dels, cursor: (0, 0)})
+    }
+
+    /// Build the pileup `Line` at the current cursor position and move the cursor to the next position.
+    /// Returns `None` if no read overlaps the current position.
+    fn pileup_at_cursor(&mut self) -> Result<Option<Line>> {
+        let (ref_id, pos) = self.cursor;
+        for (i, sample) in self.samples.iter_mut().enumerate() {
+            sample.fill(i, self.cursor, &self.contigs, &self.filters, self.ignore_dels)?;
+        }
+
+        let individuals: Vec<Pileup> = self.samples.iter().map(|sample| sample.pileup(pos)).collect();
+
+        // ---- Move to the next position and discard reads that end before it.
+        self.cursor.1 += 1;
+        for sample in &mut self.samples {
+            sample.active.retain(|read| read.end > pos + 1);
+        }
+
+        if individuals.iter().all(|pileup| pileup.depth == 0) {
+            return Ok(None)
+        }
+
+        let chromosome = usize::try_from(ref_id).ok()
+            .and_then(|idx| self.contigs.get(idx).copied().flatten())
+            .expect("Active reads should only stem from valid contigs");
+        #[allow(clippy::cast_sign_loss)]
+        let position = Position(pos as u32 + 1);
+        Ok(Some(Line{coordinate: Coordinate::new(chromosome, position), reference: Allele::N, individuals}))
+    }
+}
+
+impl Iterator for BamPileup {
+    type Item = Result<Line>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // ---- Jump to the next record if no read overlaps the current position.
+            if self.samples.iter().all(|sample| sample.active.is_empty()) {
+                self.cursor = self.samples.iter()
+                    .filter_map(|sample| sample.next.as_ref().map(|record| (record.ref_id, record.pos)))
+                    .min()?;
+            }
+
+            match self.pileup_at_cursor() {
+                Ok(Some(line)) => return Some(Ok(line)),
+                Ok(None)       => continue,
+                Err(e)         => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grups_io::read::bam::{mock, BamReference, Cigar};
+    use std::io::Cursor;
+
+    /// (`ref_id`, `pos`, `mapq`, `flag`, `cigar`, `seq`)
+    type MockRecord<'a> = (i32, i32, u8, u16, &'a [(u32, u32)], &'a str);
+
+    fn encode_bam(references: &[&str], records: &[MockRecord]) -> Vec<u8> {
+        let references: Vec<BamReference> = references.iter()
+            .map(|name| BamReference{name: name.to_string(), length: 1_000_000})
+            .collect();
+        let mut bytes = mock::encode_header(&references);
+        for (ref_id, pos, mapq, flag, cigar, seq) in records {
+            let cigar = cigar.iter().map(|(len, op)| Cigar{op: CigarOp::try_from(*op).expect("Invalid CIGAR operation"), len: *len}).collect();
+            let seq   = seq.as_bytes().to_vec();
+            let qual  = vec![40; seq.len()];
+            bytes.extend(mock::encode_record(&BamRecord{ref_id: *ref_id, pos: *pos, mapq: *mapq, flag: *flag, cigar, seq, qual}));
+        }
+        bytes
+    }
+
+    fn bam_pileup(bams: Vec<Vec<u8>>, filters: BamFilters, ignore_dels: bool) -> Result<Vec<Line>> {
+        let readers = bams.into_iter()
+            .map(|bytes| BamReader::from_reader(Box::new(Cursor::new(bytes))))
+            .collect::<Result<Vec<_>>>()?;
+        BamPileup::from_readers(readers, filters, ignore_dels)?.collect()
+    }
+
+    #[test]
+    fn single_sample_pileup() -> Result<()> {
+        let bam = encode_bam(&["22"], &[
+            (0, 10, 30, 0, &[(3, 0)],                 "ACG"),
+            (0, 11, 30, 0, &[(1, 4), (1, 0), (1, 2), (1, 0)], "TCA"),
+        ]);
+        let lines = bam_pileup(vec![bam], BamFilters::default(), true)?;
+        let summary: Vec<(u32, String)> = lines.iter()
+            .map(|line| (line.coordinate.position.0, line.individuals[0].get_nucleotides()))
+            .collect();
+        assert_eq!(summary, vec![
+            (11, "A".to_string()),
+            (12, "CC".to_string()),
+            (13, "G".to_string()),  // Deletion is ignored.
+            (14, "A".to_string()),
+        ]);
+        assert!(lines.iter().all(|line| line.coordinate.chromosome == ChrIdx(22)));
+        Ok(())
+    }
+
+    #[test]
+    fn read_context() -> Result<()> {
+        let bam   = encode_bam(&["1"], &[(0, 0, 30, 0, &[(4, 0)], "ACGT"), (0, 1, 30, 0x10, &[(1, 4), (2, 0)], "AAC")]);
+        let lines = bam_pileup(vec![bam], BamFilters::default(), true)?;
+        let context: Vec<Vec<(bool, Option<u32>)>> = lines.iter()
+            .map(|line| line.individuals[0].nucleotides.iter().map(|nuc| (nuc.reverse, nuc.end_distance)).collect())
+            .collect();
+        assert_eq!(context, vec![
+            vec![(false, Some(0))],
+            vec![(false, Some(1)), (true, Some(1))],
+            vec![(false, Some(1)), (true, Some(0))],
+            vec![(false, Some(0))],
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn heterozygosity_ratio() -> Result<()> {
+        use crate::comparisons::{Comparison, Individual};
+        use genome::{Genome, jackknife::JackknifeBlocks};
+        let bams  = vec![encode_bam(&["22"], &[(0, 10, 30, 0, &[(2, 0)], "TT")]), encode_bam(&["22"], &[(0, 10, 30, 0, &[(2, 0)], "TC")])];
+        let pair  = || [Individual::new(None, 0, 1), Individual::new(None, 1, 1)];
+        let blocks = || JackknifeBlocks::new(&Genome::default(), 1000);
+
+        // ---- Alignment files lack reference alleles: shared non-reference observations cannot be counted.
+        let mut comparison = Comparison::new(pair(), false, blocks());
+        for line in bam_pileup(bams.clone(), BamFilters::default(), true)? {
+            assert_eq!(line.reference, Allele::N);
+            comparison.compare(&line, None)?;
+        }
+        assert!(comparison.get_heterozygosity_ratio().is_nan());
+
+        // ---- Reference alleles retrieved from the targets.
+        let mut comparison = Comparison::new(pair(), false, blocks());
+        for mut line in bam_pileup(bams, BamFilters::default(), true)? {
+            line.fill_reference(Allele::C);
+            comparison.compare(&line, None)?;
+        }
+        assert!((comparison.get_heterozygosity_ratio() - 1.0).abs() < f64::EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn consider_deletions() -> Result<()> {
+        let bam   = encode_bam(&["1"], &[(0, 0, 30, 0, &[(1, 0), (1, 2), (1, 0)], "AC")]);
+        let lines = bam_pileup(vec![bam], BamFilters::default(), false)?;
+        assert_eq!(lines[1].individuals[0].get_nucleotides(), "*");
+        Ok(())
+    }
+
+    #[test]
+    fn multi_sample_pileup() -> Result<()> {
+        let bam0 = encode_bam(&["1", "2"], &[(0, 5, 30, 0, &[(2, 0)], "AC"), (1, 0, 30, 0, &[(1, 0)], "T")]);
+        let bam1 = encode_bam(&["1", "2"], &[(0, 6, 30, 0, &[(2, 0)], "GT")]);
+        let lines = bam_pileup(vec![bam0, bam1], BamFilters::default(), true)?;
+
+        let summary: Vec<(String, String, String)> = lines.iter().map(|line| (
+            line.coordinate.to_string(),
+            line.individuals[0].get_nucleotides(),
+            line.individuals[1].get_nucleotides()
+        )).collect();
+        assert_eq!(summary.len(), 4);
+        assert_eq!(summary[0].1, "A");
+        assert_eq!(summary[0].2, "");
+        assert_eq!(summary[1].1, "C");
+        assert_eq!(summary[1].2, "G");
+        assert_eq!(summary[2].1, "");
+        assert_eq!(summary[2].2, "T");
+        assert_eq!(lines[3].coordinate, Coordinate::new(2, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn read_filters() -> Result<()> {
+        let records: [MockRecord; 4] = [
+            (0, 0, 10, 0,     &[(1, 0)], "A"), // Low MAPQ
+            (0, 0, 30, 0x400, &[(1, 0)], "C"), // Duplicate
+            (0, 0, 30, 0x100, &[(1, 0)], "G"), // Secondary
+            (0, 0, 30, 0,     &[(1, 0)], "T"),
+        ];
+
+        let filters = BamFilters{min_mapq: 20, keep_duplicates: false};
+        let lines = bam_pileup(vec![encode_bam(&["1"], &records)], filters, true)?;
+        assert_eq!(lines[0].individuals[0].get_nucleotides(), "T");
+
+        let filters = BamFilters{min_mapq: 0, keep_duplicates: true};
+        let lines = bam_pileup(vec![encode_bam(&["1"], &records)], filters, true)?;
+        assert_eq!(lines[0].individuals[0].get_nucleotides(), "ACT");
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_contigs_are_skipped() -> Result<()> {
+        let bam   = encode_bam(&["GL000192.1", "3"], &[(0, 0, 30, 0, &[(1, 0)], "A"), (1, 0, 30, 0, &[(1, 0)], "C")]);
+        let lines = bam_pileup(vec![bam], BamFilters::default(), true)?;
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].coordinate, Coordinate::new(3, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn unsorted_bam() {
+        let bam = encode_bam(&["1"], &[(0, 10, 30, 0, &[(1, 0)], "A"), (0, 2, 30, 0, &[(1, 0)], "C")]);
+        assert!(bam_pileup(vec![bam], BamFilters::default(), true).is_err());
+    }
+
+    #[test]
+    fn header_mismatch() {
+        let bam0 = encode_bam(&["1"], &[]);
+        let bam1 = encode_bam(&["2"], &[]);
+        assert!(bam_pileup(vec![bam0, bam1], BamFilters::default(), true).is_err());
+    }
+}
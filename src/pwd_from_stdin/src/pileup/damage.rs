@@ -0,0 +1,61 @@
+use genome::{Nucleotide, snp::Allele};
+
+/// Ancient DNA damage filters, applied on the nucleotides of a pileup.
+/// - `deamination`: discard C->T substitutions found on forward reads, and G->A substitutions found on reverse reads.
+/// - `trim`       : discard nucleotides located within the first (or last) `trim` positions of their read.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DamageFilter {
+    pub deamination: bool,
+    pub trim       : u32,
+}
+
+impl DamageFilter {
+    /// Return `true` if any of the damage filters was requested.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.deamination || self.trim > 0
+    }
+
+    /// Return `true` if a given nucleotide may stem from post-mortem damage, and should thus be discarded.
+    ///
+    /// Deamination filtering requires a known `reference` allele, while read trimming requires the distance of the
+    /// nucleotide to the nearest end of its read. Nucleotides lacking such information are always kept.
+    #[must_use]
+    pub fn is_damaged(&self, reference: Allele, nucleotide: &Nucleotide) -> bool {
+        let deaminated = match (reference, nucleotide.base, nucleotide.reverse) {
+            (Allele::C, Allele::T, false) | (Allele::G, Allele::A, true) => self.deamination,
+            _ => false
+        };
+        let trimmed = nucleotide.end_distance.is_some_and(|distance| distance < self.trim);
+        deaminated || trimmed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deamination() {
+        let filter = DamageFilter{deamination: true, trim: 0};
+        let nucleotide = |base, reverse| Nucleotide::new(base, 'J').with_read_context(reverse, None);
+
+        assert!( filter.is_damaged(Allele::C, &nucleotide(Allele::T, false)));
+        assert!(!filter.is_damaged(Allele::C, &nucleotide(Allele::T, true)));
+        assert!( filter.is_damaged(Allele::G, &nucleotide(Allele::A, true)));
+        assert!(!filter.is_damaged(Allele::G, &nucleotide(Allele::A, false)));
+        assert!(!filter.is_damaged(Allele::N, &nucleotide(Allele::T, false)));
+        assert!(!filter.is_damaged(Allele::C, &nucleotide(Allele::C, false)));
+    }
+
+    #[test]
+    fn trim() {
+        let filter = DamageFilter{deamination: false, trim: 2};
+        let nucleotide = |end_distance| Nucleotide::new(Allele::T, 'J').with_read_context(false, end_distance);
+
+        assert!( filter.is_damaged(Allele::C, &nucleotide(Some(0))));
+        assert!( filter.is_damaged(Allele::C, &nucleotide(Some(1))));
+        assert!(!filter.is_damaged(Allele::C, &nucleotide(Some(2))));
+        assert!(!filter.is_damaged(Allele::C, &nucleotide(None)));
+    }
+}
@@ -6,6 +6,10 @@ use thiserror::Error;
 ///  - `UnequalLength`:  Nucleotide and Quality string, should have an equal length after filtration.
 ///    `LengthError` is raised if this is not the case.
 /// 
+///  - `UnsortedBam`, `BamHeaderMismatch`: raised when building pileups from a set of inconsistent alignment files.
+/// 
+///  - `UnsortedPileup`, `MultiSamplePileup`, `InconsistentChromosomeOrder`: raised when merging a set of invalid single-sample pileup files.
+/// 
 ///  - `ParseLine`: Not yet Implemented. General error which is raised if a character failed to parse.
 /// 
 #[derive(Error, Debug)]
@@ -18,7 +22,7 @@ pub enum PileupError {
     #[error("Failed to parse allele within pileup")]
     ParseRef(#[from] genome::snp::ParseAlleleError),
 
-    #[error("Failed to parse chromosome into a valid u8")]
+    #[error("Failed to parse chromosome into a valid contig")]
     ParseChr(#[from] genome::coordinate::ChrIdxError),
     
     #[error("Failed to parse genomic position into a valid u32")]
@@ -26,6 +30,30 @@ pub enum PileupError {
 
     #[error("Failed to parse pileup depth int a valid u8")]
     ParseDepth(#[from] std::num::ParseIntError),
+
+    #[error("Failed to parse read position '{0}' into a valid, 1-based integer")]
+    ParseReadPosition(String),
+
+    #[error("No input alignment file was provided.")]
+    MissingBam,
+
+    #[error("Alignment file n°{0} is not sorted by coordinate. Please sort it beforehand (e.g. 'samtools sort')")]
+    UnsortedBam(usize),
+
+    #[error("The reference sequence dictionary of alignment file n°{0} differs from that of the first alignment file.")]
+    BamHeaderMismatch(usize),
+
+    #[error("No input pileup file was provided.")]
+    MissingPileup,
+
+    #[error("Pileup file n°{0} is not sorted by coordinate. Please sort it beforehand.")]
+    UnsortedPileup(usize),
+
+    #[error("Pileup file n°{0} contains {1} individuals, while merged pileup files should only contain a single individual.")]
+    MultiSamplePileup(usize, usize),
+
+    #[error("Pileup file n°{0} lists chromosome {1} after chromosome {2}. Pileup files must follow the chromosome order of the reference genome (see --genome).")]
+    InconsistentChromosomeOrder(usize, String, String),
 }
 
 #[cfg(test)]
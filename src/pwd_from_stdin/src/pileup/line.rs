@@ -1,11 +1,30 @@
-use genome::{Nucleotide, SNPCoord, coordinate::{ChrIdx, Coordinate, Position, derive::Coord}, snp::{Allele, ParseAlleleError}};
+use std::fmt::{self, Display, Formatter};
+
+use genome::{Nucleotide, Phred, SNPCoord, coordinate::{ChrIdx, Coordinate, Position, derive::Coord}, snp::{Allele, ParseAlleleError}};
+use grups_io::read::pseudo_haploid::HaploidCalls;
 use log::warn;
 use crate::comparisons::Individual;
 //use rand::seq::SliceRandom;
 
 use located_error::prelude::*;
 
-use super::Pileup;
+use super::{DamageFilter, Pileup, ReadFields};
+
+/// Optional per-individual columns of a `samtools mpileup` entry, found after the base qualities (in that order).
+/// - `mapping_qualities`: mapping quality of each read (`--output-MQ`).
+/// - `read_positions`   : positions of each base, from the 5' end of its read (`--output-BP-5`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PileupColumns {
+    pub mapping_qualities: bool,
+    pub read_positions   : bool,
+}
+
+impl PileupColumns {
+    /// Number of columns defining each individual.
+    fn len(self) -> usize {
+        3 + usize::from(self.mapping_qualities) + usize::from(self.read_positions)
+    }
+}
 
 /// Parsed line of a pileup file entry, containing the both coordinates and 
 /// Pileup of each individual.
@@ -30,7 +49,7 @@ impl Line {
     /// Instantiate a new pileup `Line`
     /// 
     /// # Errors
-    /// - [`ParseChr`]   if chromosome is an invalid u8               (field [0] of the pileup)
+    /// - [`ParseChr`]   if chromosome is an unknown contig           (field [0] of the pileup)
     /// - [`ParsePos`]   if position   is an invalid u32              (field [1])
     /// - [`ParseRef`]   if reference  is an invalid allele character (field [2])
    /// -  [`ParseDepth`] if any depth  field is an invalid u16        (fields[i%3])
@@ -38,7 +57,17 @@ impl Line {
     /// - [`PileupError::UnequalLength`] if the base and scores strings do not match in length.
     /// - If any indel is encountered and the program fails to skip it.
     pub fn new(line: &str, ignore_dels: bool) -> Result<Line> {
-        use super::PileupError::{ParseChr, ParsePos, ParseRef, ParseDepth};
+        Self::with_columns(line, ignore_dels, PileupColumns::default())
+    }
+
+    /// Instantiate a new pileup `Line`, containing additional per-individual `columns`.
+    /// 
+    /// # Errors
+    /// - See `Line::new()`
+    /// - `ParseReadPosition` if any of the read positions is invalid.
+    /// - `UnequalLength` if any of the optional columns is missing, or does not match the length of the bases.
+    pub fn with_columns(line: &str, ignore_dels: bool, columns: PileupColumns) -> Result<Line> {
+        use super::PileupError::{ParseChr, ParsePos, ParseRef, ParseDepth, UnequalLength};
         let err_context = "While parsing new pileup line";
         let fields: Vec<&str>    = line.split('\t').collect();
         let chromosome: ChrIdx   = fields[0].parse().map_err(ParseChr).loc(err_context)?;
@@ -57,21 +86,64 @@ impl Line {
 
         //Loop along individuals
         let mut individuals: Vec<Pileup> = Vec::new();
-        for i in (3..fields.len()).step_by(3) {
+        for i in (3..fields.len()).step_by(columns.len()) {
             let depth           = fields[i].parse().map_err(ParseDepth).loc(err_context)?;
             let (bases, scores) = (fields[i+1], fields[i+2]);
+            let mut optional    = fields[i+3..].iter().copied();
+            let mut next_column = |requested: bool| match requested {
+                true  => optional.next().map(Some).ok_or(UnequalLength).loc(err_context),
+                false => Ok(None),
+            };
+            let read_fields = ReadFields {
+                mapping_qualities: next_column(columns.mapping_qualities)?,
+                read_positions   : next_column(columns.read_positions)?,
+            };
 
-            individuals.push(Pileup::new(reference, depth, bases, scores, ignore_dels)?);
+            individuals.push(Pileup::with_read_fields(reference, depth, bases, scores, read_fields, ignore_dels)?);
         }
         Ok(Line { coordinate: Coordinate::new(chromosome, position), reference, individuals })
     }
 
+    /// Instantiate a pileup `Line` from a set of pseudo-haploid genotype calls. Each called individual is assigned a
+    /// single nucleotide of base quality `phred`, while missing individuals are assigned an empty pileup. 
+    #[must_use]
+    pub fn from_haploid_calls(record: &HaploidCalls, phred: Phred) -> Line {
+        let individuals = record.calls.iter()
+            .map(|call| Pileup{depth: u16::from(call.is_some()), nucleotides: call.iter().map(|base| Nucleotide::new(*base, phred)).collect()})
+            .collect();
+        Line { coordinate: record.coordinate.coordinate, reference: record.coordinate.reference, individuals }
+    }
+
     /// Apply base quality filtering on each individual pileup, according to a given treshold
     /// See: `Pileup::filter_base_quality()`
     pub fn filter_base_quality(&mut self, phred_treshold: u8) {
         self.individuals.iter_mut().for_each(|ind| ind.filter_base_quality(phred_treshold.into()));
     }
 
+    /// Apply mapping quality filtering on each individual pileup, according to a given treshold.
+    /// See: `Pileup::filter_mapping_quality()`
+    pub fn filter_mapping_quality(&mut self, min_mapq: u8) {
+        self.individuals.iter_mut().for_each(|ind| ind.filter_mapping_quality(min_mapq));
+    }
+
+    /// Set the reference allele of this line, if it is currently unknown (e.g. pileups built from alignment files).
+    pub fn fill_reference(&mut self, reference: Allele) {
+        if self.reference == Allele::N {
+            self.reference = reference;
+        }
+    }
+
+    /// Discard nucleotides that may stem from post-mortem damage, on each individual pileup.
+    /// `reference` is used in place of the line's own reference allele, when the latter is unknown.
+    /// See: `Pileup::filter_damage()`
+    pub fn filter_damage(&mut self, filter: &DamageFilter, reference: Allele) {
+        let reference = match self.reference {
+            Allele::N => reference,
+            known     => known,
+        };
+        self.individuals.iter_mut().for_each(|ind| ind.filter_damage(filter, reference));
+    }
+
     /// Apply `known_variant` filtering on each individual pileup, according to a given treshold.
     /// See: `Pileup::filter_known_variant()`
     /// 
@@ -86,26 +158,37 @@ impl Line {
     }
 
     /// Apply random sampling on a single individual for self-comparison.
-    /// Two nucleotide sampled (without replacement).
+    /// Two nucleotides sampled (without replacement). Returns `None` if the individual carries less than two nucleotides.
     #[must_use]
-    pub fn random_sample_self(&self, index: &usize) -> Vec<&Nucleotide> {
-        let mut rng = fastrand::Rng::new();
-        rng.choose_multiple(self.individuals[*index].nucleotides.iter(), 2) //.choose_multiple(&mut rng, 2).collect() 
+    pub fn random_sample_self(&self, index: usize, rng: &mut fastrand::Rng) -> Option<[&Nucleotide; 2]> {
+        let nucleotides = &self.individuals.get(index)?.nucleotides;
+        if nucleotides.len() < 2 {
+            return None
+        }
+        let first  = rng.usize(..nucleotides.len());
+        let second = (first + rng.usize(1..nucleotides.len())) % nucleotides.len();
+        Some([&nucleotides[first], &nucleotides[second]])
     }
 
     /// Apply random sampling on a a pair of individuals for pairwise-comparison.
-    /// One nucleotide sampled per individual (without replacement).
-    /// @TODO: rng should not be initialized within function.
+    /// One nucleotide sampled per individual. Returns `None` if any of the two individuals lacks nucleotides.
     #[must_use]
-    pub fn random_sample_pair(&self, pair: &[Individual; 2]) -> Option<Vec<&Nucleotide>> {
-        let mut rng = fastrand::Rng::new();
-        Some(vec![ // @TODO: Yuk! change this horrid allocation.
-            rng.choice(self.individuals[pair[0].index].nucleotides.iter())?,
-            rng.choice(self.individuals[pair[1].index].nucleotides.iter())?
+    pub fn random_sample_pair(&self, pair: &[Individual; 2], rng: &mut fastrand::Rng) -> Option<[&Nucleotide; 2]> {
+        Some([
+            rng.choice(self.individuals.get(pair[0].index)?.nucleotides.iter())?,
+            rng.choice(self.individuals.get(pair[1].index)?.nucleotides.iter())?
         ])
     }
 }
 
+impl Display for Line {
+    /// Format this line back into a `samtools mpileup`-like entry.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\t{}\t{}", self.coordinate.chromosome, self.coordinate.position, self.reference)?;
+        self.individuals.iter().try_for_each(|pileup| write!(f, "\t{pileup}"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -156,4 +239,91 @@ mod tests {
         assert_eq!(line.individuals[1].depth, 7);
         Ok(())
     }
+
+    #[test]
+    fn line_filter_damage() -> Result<()> {
+        use super::{DamageFilter, PileupColumns};
+        use genome::snp::Allele;
+        let raw_line="2\t21303470\tC\t6\t.TtAc,\tJJJJJJ\t1,5,40,5,1,2";
+        let columns = PileupColumns{mapping_qualities: false, read_positions: true};
+
+        let mut line = pileup::Line::with_columns(raw_line, true, columns)?;
+        line.filter_damage(&DamageFilter{deamination: true, trim: 0}, Allele::N);
+        assert_eq!(line.individuals[0].get_nucleotides(), "CTACC");
+
+        let mut line = pileup::Line::with_columns(raw_line, true, columns)?;
+        line.filter_damage(&DamageFilter{deamination: false, trim: 2}, Allele::N);
+        assert_eq!(line.individuals[0].get_nucleotides(), "TTA");
+        assert_eq!(line.individuals[0].depth, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn line_filter_damage_unknown_reference() -> Result<()> {
+        use super::DamageFilter;
+        use genome::snp::Allele;
+        let raw_line="2\t21303470\tN\t4\tTtAa\tJJJJ";
+        let filter = DamageFilter{deamination: true, trim: 0};
+
+        let mut line = pileup::Line::new(raw_line, true)?;
+        line.filter_damage(&filter, Allele::G);
+        assert_eq!(line.individuals[0].get_nucleotides(), "TTA");
+        Ok(())
+    }
+
+    #[test]
+    fn line_mapping_qualities_and_positions() -> Result<()> {
+        use super::PileupColumns;
+        let raw_line="2\t21303470\tC\t3\t.T,\tJJJ\t?!?\t1,2,3\t1\tA\tJ\t5\t4";
+        let columns  = PileupColumns{mapping_qualities: true, read_positions: true};
+        let mut line = pileup::Line::with_columns(raw_line, true, columns)?;
+        assert_eq!(line.individuals[0].nucleotides[2].end_distance, Some(2));
+        assert_eq!(line.individuals[1].nucleotides[0].mapq, Some(20));
+
+        line.filter_mapping_quality(25);
+        assert_eq!(line.individuals[0].get_nucleotides(), "CC");
+        assert_eq!(line.individuals[1].depth, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn line_missing_read_positions() {
+        let raw_line="2\t21303470\tC\t2\t.T\tJJ\t1";
+        assert!(pileup::Line::with_columns(raw_line, true, super::PileupColumns{mapping_qualities: false, read_positions: true}).is_err());
+    }
+
+    #[test]
+    fn line_random_samples() -> Result<()> {
+        use crate::comparisons::Individual;
+        use genome::snp::Allele;
+        let raw_line="2\t21303470\tT\t2\t.c\tJJ\t1\tA\tJ\t0\t*\t*";
+        let line = pileup::Line::new(raw_line, true)?;
+        let pair = [0, 1].map(|index| Individual::new(None, index, 1));
+        let mut rng = fastrand::Rng::with_seed(42);
+        for _ in 0..16 {
+            let [first, second] = line.random_sample_self(0, &mut rng).expect("Two nucleotides");
+            assert_ne!(first.base, second.base);
+            assert_eq!(line.random_sample_pair(&pair, &mut rng).expect("Non-empty pileups")[1].base, Allele::A);
+        }
+        assert!(line.random_sample_self(1, &mut rng).is_none());
+        assert!(line.random_sample_pair(&[pair[0].clone(), Individual::new(None, 2, 1)], &mut rng).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn line_display() -> Result<()> {
+        let raw_line="2\t21303470\tT\t0\t*\t*\t4\t..c,\tEEJF";
+        let line = pileup::Line::new(raw_line, true)?;
+        assert_eq!(line.to_string(), "2\t21303470\tT\t0\t*\t*\t4\tTTCT\tEEJF");
+        Ok(())
+    }
+
+    #[test]
+    fn line_from_haploid_calls() {
+        use genome::{Phred, snp::Allele::{A, G}};
+        use grups_io::read::pseudo_haploid::HaploidCalls;
+        let record = HaploidCalls{coordinate: SNPCoord::new(22, 16_050_408, A, G), calls: vec![Some(G), None, Some(A)]};
+        let line = pileup::Line::from_haploid_calls(&record, Phred::new(40u8));
+        assert_eq!(line.to_string(), "22\t16050408\tA\t1\tG\tI\t0\t*\t*\t1\tA\tI");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,271 @@
+use std::{io::{BufRead, Lines}, path::{Path, PathBuf}};
+
+use ahash::AHashMap;
+use genome::{coordinate::ChrIdx, snp::Allele};
+use grups_io::read::compression;
+use located_error::prelude::*;
+
+use super::{Line, Pileup, PileupColumns, PileupError};
+
+/// Expected chromosome order of the merged pileup files, following the reference genome (see `Genome::order()`).
+/// Chromosomes which are missing from the reference are expected after every reference chromosome, sorted by id.
+struct ChromosomeOrder(AHashMap<ChrIdx, usize>);
+
+impl ChromosomeOrder {
+    fn new(order: &[ChrIdx]) -> Self {
+        Self(order.iter().enumerate().map(|(rank, chromosome)| (*chromosome, rank)).collect())
+    }
+
+    /// Sort key of a chromosome.
+    fn key(&self, chromosome: ChrIdx) -> (usize, ChrIdx) {
+        (self.0.get(&chromosome).copied().unwrap_or(usize::MAX), chromosome)
+    }
+}
+
+/// Streaming state of a single-sample pileup file.
+/// - `lines`  : remaining entries of the pileup file.
+/// - `next`   : next parsed pileup line, still ahead of the current merge position.
+/// - `count`  : number of entries read so far. Used for error reporting.
+struct PileupSource {
+    lines  : Lines<Box<dyn BufRead>>,
+    next   : Option<Line>,
+    count  : usize,
+}
+
+impl PileupSource {
+    /// Parse the next entry of this pileup file and set it as `self.next`.
+    /// # Errors
+    /// - if the entry cannot be read or parsed.
+    /// - `MultiSamplePileup` if the entry does not contain exactly one individual.
+    /// - `UnsortedPileup` if the entry is located before the previous one.
+    /// - `InconsistentChromosomeOrder` if the entry is located on a chromosome preceding the previous one, according
+    ///   to the order of the reference genome.
+    fn advance(&mut self, index: usize, ignore_dels: bool, columns: PileupColumns, order: &ChromosomeOrder) -> Result<()> {
+        let previous = self.next.take().map(|line| line.coordinate);
+        let Some(entry) = self.lines.next() else {
+            return Ok(())
+        };
+        self.count += 1;
+        let loc_msg = || format!("While parsing line n°{} of pileup file n°{index}", self.count);
+
+        let line = Line::with_columns(&entry.with_loc(loc_msg)?, ignore_dels, columns).with_loc(loc_msg)?;
+        if line.individuals.len() != 1 {
+            return Err(PileupError::MultiSamplePileup(index, line.individuals.len())).with_loc(loc_msg)
+        }
+
+        let chromosome = line.coordinate.chromosome;
+        if let Some(previous) = previous {
+            if previous.chromosome == chromosome && line.coordinate.position <= previous.position {
+                return Err(PileupError::UnsortedPileup(index)).with_loc(loc_msg)
+            }
+            if order.key(chromosome) < order.key(previous.chromosome) {
+                return Err(PileupError::InconsistentChromosomeOrder(index, chromosome.to_string(), previous.chromosome.to_string())).with_loc(loc_msg)
+            }
+        }
+        self.next = Some(line);
+        Ok(())
+    }
+
+    /// Return the chromosome of the next entry of this file, if any.
+    fn next_chromosome(&self) -> Option<ChrIdx> {
+        self.next.as_ref().map(|line| line.coordinate.chromosome)
+    }
+}
+
+/// Stream-merge a set of single-sample pileup files into a single multi-sample pileup, one `Line` per site.
+/// Each file is considered as a single individual, following the order in which they were provided.
+///
+/// Lines are generated for every position found within at least one of the files. Individuals lacking any entry at a
+/// given position are assigned an empty pileup (i.e. a depth of zero).
+///
+/// Files must follow the chromosome order of the reference genome (i.e. the order of its `.fai` index), although any
+/// file may lack some of the chromosomes. Chromosomes which are missing from the reference are expected last.
+///
+/// # Fields:
+/// - `sources`    : streaming state of each pileup file.
+/// - `ignore_dels`: do not include deletions within the pileup.
+/// - `columns`    : optional per-individual columns found within the pileup files.
+/// - `order`      : expected chromosome order.
+/// - `current`    : chromosome currently being merged.
+pub struct MergedPileup {
+    sources    : Vec<PileupSource>,
+    ignore_dels: bool,
+    columns    : PileupColumns,
+    order      : ChromosomeOrder,
+    current    : Option<ChrIdx>,
+}
+
+impl MergedPileup {
+    /// Open a set of single-sample pileup files and instantiate a new `MergedPileup`.
+    /// Files may either be uncompressed, or gzip/BGZF-compressed.
+    /// # Arguments:
+    /// - `order`: chromosomes of the reference genome, in order (see `Genome::order()`).
+    ///
+    /// # Errors
+    /// - if any of the provided pileup files cannot be opened.
+    /// - if the first entry of any of the files is invalid.
+    pub fn new(paths: &[PathBuf], ignore_dels: bool, columns: PileupColumns, threads: usize, order: &[ChrIdx]) -> Result<Self> {
+        let readers = paths.iter()
+            .map(|path| compression::open(Path::new(path), threads))
+            .collect::<Result<Vec<Box<dyn BufRead>>>>()?;
+        Self::from_readers(readers, ignore_dels, columns, order)
+    }
+
+    /// Instantiate a new `MergedPileup` from a set of already opened readers.
+    ///
+    /// # Errors
+    /// - `MissingPileup` if `readers` is empty.
+    /// - if the first entry of any of the files is invalid.
+    pub fn from_readers(readers: Vec<Box<dyn BufRead>>, ignore_dels: bool, columns: PileupColumns, order: &[ChrIdx]) -> Result<Self> {
+        let loc_msg = "While initializing merged pileup";
+        if readers.is_empty() {
+            return Err(PileupError::MissingPileup).loc(loc_msg)
+        }
+
+        let sources = readers.into_iter()
+            .map(|reader| PileupSource{lines: reader.lines(), next: None, count: 0})
+            .collect();
+        let mut merged_pileup = Self{sources, ignore_dels, columns, order: ChromosomeOrder::new(order), current: None};
+        for i in 0..merged_pileup.sources.len() {
+            merged_pileup.advance(i).loc(loc_msg)?;
+        }
+        Ok(merged_pileup)
+    }
+
+    /// Move the pileup file at `index` to its next entry.
+    fn advance(&mut self, index: usize) -> Result<()> {
+        self.sources[index].advance(index, self.ignore_dels, self.columns, &self.order)
+    }
+
+    /// Select the chromosome that should be merged next. Returns `None` once every file has been entirely read.
+    fn next_chromosome(&mut self) -> Option<ChrIdx> {
+        let heads = self.sources.iter().filter_map(PileupSource::next_chromosome).collect::<Vec<ChrIdx>>();
+        if !self.current.is_some_and(|current| heads.contains(&current)) {
+            self.current = heads.into_iter().min_by_key(|chromosome| self.order.key(*chromosome));
+        }
+        self.current
+    }
+
+    /// Merge the entries of every file located at the next position of the current chromosome, and move these files to
+    /// their next entry.
+    fn merge_next(&mut self, chromosome: ChrIdx) -> Result<Line> {
+        let coordinate = self.sources.iter()
+            .filter_map(|source| source.next.as_ref().map(|line| line.coordinate))
+            .filter(|coordinate| coordinate.chromosome == chromosome)
+            .min()
+            .expect("At least one pileup file should carry the current chromosome");
+
+        let mut reference   = Allele::N;
+        let mut individuals = Vec::with_capacity(self.sources.len());
+        for i in 0..self.sources.len() {
+            match self.sources[i].next.as_mut() {
+                Some(line) if line.coordinate == coordinate => {
+                    reference = line.reference;
+                    individuals.push(line.individuals.pop().expect("Single-sample pileup line"));
+                    self.advance(i)?;
+                },
+                _ => individuals.push(Pileup{depth: 0, nucleotides: Vec::new()}),
+            }
+        }
+        Ok(Line{coordinate, reference, individuals})
+    }
+}
+
+impl Iterator for MergedPileup {
+    type Item = Result<Line>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_chromosome().map(|chromosome| self.merge_next(chromosome))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use genome::coordinate::Coordinate;
+
+    fn merged_pileup(pileups: &[&str], ignore_dels: bool, order: &[ChrIdx]) -> Result<Vec<Line>> {
+        let readers = pileups.iter()
+            .map(|pileup| Box::new(Cursor::new(pileup.to_string())) as Box<dyn BufRead>)
+            .collect();
+        MergedPileup::from_readers(readers, ignore_dels, PileupColumns::default(), order)?.collect()
+    }
+
+    fn summary(lines: &[Line]) -> Vec<(String, [u16; 2])> {
+        lines.iter().map(|line| (line.coordinate.to_string(), [0, 1].map(|i| line.individuals[i].depth))).collect()
+    }
+
+    #[test]
+    fn merge_pileups() -> Result<()> {
+        let pileup0 = "1\t10\tA\t2\t.C\tJJ\n1\t20\tC\t1\t.\tJ\n2\t5\tG\t1\tT\tJ\n";
+        let pileup1 = "1\t20\tC\t1\tT\tE\n1\t30\tT\t1\t.\tE\n";
+        let pileup2 = "";
+        let lines = merged_pileup(&[pileup0, pileup1, pileup2], true, &[ChrIdx(1), ChrIdx(2)])?;
+
+        let summary: Vec<String> = lines.iter().map(ToString::to_string).collect();
+        assert_eq!(summary, vec![
+            "1\t10\tA\t2\tAC\tJJ\t0\t*\t*\t0\t*\t*",
+            "1\t20\tC\t1\tC\tJ\t1\tT\tE\t0\t*\t*",
+            "1\t30\tT\t0\t*\t*\t1\tT\tE\t0\t*\t*",
+            "2\t5\tG\t1\tT\tJ\t0\t*\t*\t0\t*\t*",
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_lexicographic_pileups() -> Result<()> {
+        let pileup0 = "1\t10\tA\t1\t.\tJ\n10\t10\tA\t1\t.\tJ\n2\t10\tA\t1\t.\tJ\n";
+        let pileup1 = "1\t20\tA\t1\t.\tJ\n10\t10\tA\t1\t.\tJ\n11\t10\tA\t1\t.\tJ\n2\t10\tA\t1\t.\tJ\n";
+        let lines = merged_pileup(&[pileup0, pileup1], true, &[1, 10, 11, 2].map(ChrIdx))?;
+        assert_eq!(summary(&lines), vec![
+            (Coordinate::new(1, 10).to_string(), [1, 0]),
+            (Coordinate::new(1, 20).to_string(), [0, 1]),
+            (Coordinate::new(10, 10).to_string(), [1, 1]),
+            (Coordinate::new(11, 10).to_string(), [0, 1]),
+            (Coordinate::new(2, 10).to_string(), [1, 1]),
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_karyotypic_pileups() -> Result<()> {
+        // ---- Mitochondrial genome first, and chromosome 2 is missing from the first file.
+        let pileup0 = "MT\t10\tA\t1\t.\tJ\n1\t10\tA\t1\t.\tJ\nX\t10\tA\t1\t.\tJ\n";
+        let pileup1 = "2\t10\tA\t1\t.\tJ\nX\t10\tA\t1\t.\tJ\n";
+        let lines = merged_pileup(&[pileup0, pileup1], true, &[ChrIdx::MT, ChrIdx(1), ChrIdx(2), ChrIdx::X])?;
+        assert_eq!(summary(&lines), vec![
+            (Coordinate::new(ChrIdx::MT, 10).to_string(), [1, 0]),
+            (Coordinate::new(1, 10).to_string(), [1, 0]),
+            (Coordinate::new(2, 10).to_string(), [0, 1]),
+            (Coordinate::new(ChrIdx::X, 10).to_string(), [1, 1]),
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn inconsistent_chromosome_order() {
+        let pileup0 = "1\t10\tA\t1\t.\tJ\n2\t10\tA\t1\t.\tJ\n";
+        let pileup1 = "2\t10\tA\t1\t.\tJ\n1\t10\tA\t1\t.\tJ\n";
+        assert!(merged_pileup(&[pileup0, pileup1], true, &[ChrIdx(1), ChrIdx(2)]).is_err());
+        assert!(merged_pileup(&[pileup1], true, &[ChrIdx(1), ChrIdx(2)]).is_err());
+        assert!(merged_pileup(&[pileup1], true, &[ChrIdx(2), ChrIdx(1)]).is_ok());
+    }
+
+    #[test]
+    fn unsorted_pileup() {
+        let pileup = "1\t20\tC\t1\t.\tJ\n1\t10\tA\t1\t.\tJ\n";
+        assert!(merged_pileup(&[pileup], true, &[ChrIdx(1)]).is_err());
+    }
+
+    #[test]
+    fn multi_sample_pileup() {
+        let pileup = "1\t20\tC\t1\t.\tJ\t1\t.\tJ\n";
+        assert!(merged_pileup(&[pileup], true, &[ChrIdx(1)]).is_err());
+    }
+
+    #[test]
+    fn missing_pileup() {
+        assert!(merged_pileup(&[], true, &[]).is_err());
+    }
+}
@@ -1,8 +1,15 @@
 mod line;
-pub use line::Line;
+pub use line::{Line, PileupColumns};
 
 #[allow(clippy::module_inception)]
 mod pileup;
-pub use pileup::Pileup;
+pub use pileup::{Pileup, ReadFields};
+mod bam;
+pub use bam::{BamPileup, BamFilters};
+mod merge;
+pub use merge::MergedPileup;
+mod damage;
+pub use damage::DamageFilter;
+
 mod error;
 use error::PileupError;
\ No newline at end of file
@@ -1,10 +1,19 @@
 use genome::{Nucleotide, SNPCoord, snp::Allele, Phred} ;
-use std::{iter::Peekable, collections::HashMap};
+use std::{iter::Peekable, collections::HashMap, fmt::{self, Display, Formatter}};
 use located_error::prelude::*;
 
-use super::PileupError;
+use super::{DamageFilter, PileupError};
 
 
+/// Optional per-read fields of a single individual pileup entry.
+/// - `mapping_qualities`: PHRED-33 encoded mapping quality of each read (`samtools mpileup --output-MQ`).
+/// - `read_positions`   : comma-separated, 1-based position of each base, from the 5' end of its read (`--output-BP-5`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadFields<'a> {
+    pub mapping_qualities: Option<&'a str>,
+    pub read_positions   : Option<&'a str>,
+}
+
 /// Pileup record of a single individual, at a given position.
 /// Nested structure: Pileup +-> depth
 ///                          L-> Vec<Nucleotides> +-> base
@@ -26,13 +35,27 @@ impl Pileup {
     /// -  `RefSkip` if a reference skip is encountered ('[<>]' characters).
     /// - `UnequalLength` if the base and scores strings do not match in length.
     pub fn new(reference: Allele, depth: u16, bases: &str, scores: &str, ignore_dels: bool) -> Result<Pileup> {
+        Self::with_read_fields(reference, depth, bases, scores, ReadFields::default(), ignore_dels)
+    }
 
-        let bases = &bases.to_uppercase();    // Convert antisense to forward
-        //let bases = &bases.replace(',', "."); //
-
+    /// Instantiate a new `Pileup` entry, along with optional per-read fields (mapping qualities and read positions).
+    /// 
+    /// The strand of each nucleotide is inferred from its case ('.' and uppercase: forward, ',' and lowercase: reverse).
+    /// Read positions must be counted from the 5' end of each read (`samtools mpileup --output-BP-5`). Note that only
+    /// the distance to the 5' end of the read can be inferred from these, as read lengths are not provided by
+    /// `samtools mpileup`.
+    /// 
+    /// # Errors 
+    /// - If an indel is encountered and the program fails to skip it.
+    /// -  `RefSkip` if a reference skip is encountered ('[<>]' characters).
+    /// - `UnequalLength` if the base, scores, mapping qualities and positions strings do not match in length.
+    /// - `ParseReadPosition` if any of the read positions is not a valid, 1-based integer.
+    pub fn with_read_fields(reference: Allele, depth: u16, bases: &str, scores: &str, fields: ReadFields, ignore_dels: bool) -> Result<Pileup> {
         // Loop along nucleotides.
-        let mut nucleotides = Vec::with_capacity(scores.len());
-        let mut scores_vec  = scores.chars();
+        let mut nucleotides   = Vec::with_capacity(scores.len());
+        let mut scores_vec    = scores.chars();
+        let mut mapqs_vec     = fields.mapping_qualities.map(str::chars);
+        let mut positions_vec = fields.read_positions.map(|positions| positions.split(','));
 
         // Filter out non-selectible nucleotides.
         let mut chars = bases.chars().peekable();
@@ -41,20 +64,41 @@ impl Pileup {
                 '+'|'-' => {Self::skip_indel(&mut chars)?; continue},  // Skip indels
                 '^'     => {chars.next(); continue},                   // Skip starts
                 '$'     => {continue},                                 // Skip end
-                '*'     => if ignore_dels {continue},                  // Skip deletion if required
                 '>'|'<' => return Err(anyhow!(PileupError::RefSkip)),  //Bail if we found a refskip
                 _       => ()
             }
-            match scores_vec.next() {
-                Some(score) => {
-                    let n = match n {
-                        ',' | '.' => Nucleotide::new(reference, score), // These symbols imply it's the reference allele
-                        _         => Nucleotide::try_new(n, score).with_loc(||format!("While parsing {n}"))?,          // Else, try parsing the allele from the given char.
-                    };
-                    nucleotides.push(n);
-                },
-                None => return Err(anyhow!(PileupError::UnequalLength))
+
+            // ---- Every read carries a score and per-read fields, including deletions. These must thus get consumed
+            //      even when deletions are skipped, to keep all columns in sync.
+            let reverse = n == ',' || n.is_ascii_lowercase();
+            let end_distance = match positions_vec.as_mut().map(Iterator::next) {
+                None                => None,
+                Some(Some(position)) => Some(Self::parse_read_position(position)?),
+                Some(None)           => return Err(anyhow!(PileupError::UnequalLength)),
+            };
+            let mapq = match mapqs_vec.as_mut().map(Iterator::next) {
+                None             => None,
+                Some(Some(mapq)) => Some(Phred::from(mapq).score()),
+                Some(None)       => return Err(anyhow!(PileupError::UnequalLength)),
+            };
+            let Some(score) = scores_vec.next() else {
+                return Err(anyhow!(PileupError::UnequalLength))
+            };
+            if n == '*' && ignore_dels {
+                continue // Skip deletion if required
             }
+
+            let n = match n {
+                ',' | '.' => Nucleotide::new(reference, score), // These symbols imply it's the reference allele
+                _         => Nucleotide::try_new(n.to_ascii_uppercase(), score).with_loc(||format!("While parsing {n}"))?, // Else, try parsing the allele from the given char (converting antisense to forward).
+            };
+            let n = n.with_read_context(reverse, end_distance);
+            nucleotides.push(mapq.map_or(n, |mapq| n.with_mapping_quality(mapq)));
+        }
+
+        // ---- Per-read fields must not list more reads than the bases string either.
+        if mapqs_vec.is_some_and(|mut mapqs| mapqs.next().is_some()) || positions_vec.is_some_and(|mut positions| positions.next().is_some()) {
+            return Err(anyhow!(PileupError::UnequalLength))
         }
         Ok(Pileup { depth, nucleotides })
     }
@@ -86,6 +130,22 @@ impl Pileup {
         self.update_depth();
     }
 
+    /// Apply mapping quality filtration for each Nucleotide, using a given threshold. Nucleotides lacking a known mapping
+    /// quality are kept.
+    pub fn filter_mapping_quality(&mut self, min_mapq: u8) {
+        self.nucleotides.retain(|nucleotide| {
+            nucleotide.mapq.unwrap_or(u8::MAX) >= min_mapq
+        });
+        self.update_depth();
+    }
+
+    /// Discard nucleotides that may stem from post-mortem damage, given a known `reference` allele.
+    /// See: `DamageFilter::is_damaged()`
+    pub fn filter_damage(&mut self, filter: &DamageFilter, reference: Allele) {
+        self.nucleotides.retain(|nucleotide| !filter.is_damaged(reference, nucleotide));
+        self.update_depth();
+    }
+
     /// Apply triallelic filtration for each Nucleotide, using a given `SNPCoord`.
     /// 
     /// Note that the provided `SNPCoord` must contain values for their `reference` 
@@ -124,6 +184,13 @@ impl Pileup {
         pileup_nuc
     }
 
+    /// Convert a 1-based, 5'-relative read position into a 0-based distance to the 5' end of the read.
+    fn parse_read_position(position: &str) -> Result<u32, PileupError> {
+        position.parse::<u32>().ok()
+            .and_then(|position| position.checked_sub(1))
+            .ok_or_else(|| PileupError::ParseReadPosition(position.to_string()))
+    }
+
     /// Run through a Peekable Iterator of nucleotides to parse the number of characters that should be skipped
     /// When encountering an indel.
     /// 
@@ -155,6 +222,8 @@ impl Pileup {
     /// Refresh the depth after quality filtration.
     /// Mainly used by: - `self.filter_known_variants()`
     ///                 - `self.filter_base_quality()`
+    ///                 - `self.filter_damage()`
+    ///                 - `self.filter_mapping_quality()`
     fn update_depth(&mut self) {
         #![allow(clippy::cast_possible_truncation)] // Hopefully we don't expect a local sequencing depth of u32::MAX
         self.depth = self.nucleotides.len() as u16;
@@ -162,6 +231,19 @@ impl Pileup {
     }
 }
 
+impl Display for Pileup {
+    /// Format this pileup as `samtools mpileup`-like `depth`, `bases` and `scores` fields.
+    /// Empty pileups are formatted with '*' characters.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.nucleotides.is_empty() {
+            return write!(f, "{}\t*\t*", self.depth)
+        }
+        let bases : String = self.nucleotides.iter().map(|nuc| char::from(&nuc.base)).collect();
+        let scores: String = self.nucleotides.iter().map(Nucleotide::get_score_ascii).collect();
+        write!(f, "{}\t{bases}\t{scores}", self.depth)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -213,10 +295,12 @@ mod tests {
         println!("Testing missing base filtering");
         let line_input   = ",.$a*c.N^JTA*C,.";
         let line_expect  = "NNACNNTACNN";
-        let scores_input = "JEECc$cagGg";
+        let scores_input = "JEE#Cc$ca#gGg";
         let pileup = create_dummy_pileup(Allele::N, line_input, scores_input, true)?;
         println!("{:?}\n{:?}", pileup.get_nucleotides(), line_expect);
         assert_eq!(pileup.get_nucleotides(), line_expect);
+        let scores: String = pileup.nucleotides.iter().map(Nucleotide::get_score_ascii).collect();
+        assert_eq!(scores, "JEECc$cagGg");
         Ok(())
     }
 
@@ -232,6 +316,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn pileup_strand_and_positions() -> Result<()> {
+        let fields = ReadFields{mapping_qualities: None, read_positions: Some("1,2,3,4,5")};
+        let pileup = Pileup::with_read_fields(Allele::C, 5, ".,^Jt$A*", "JJJJJ", fields, false)?;
+        assert_eq!(pileup.get_nucleotides(), "CCTA*");
+        let reverse: Vec<bool> = pileup.nucleotides.iter().map(|nuc| nuc.reverse).collect();
+        assert_eq!(reverse, vec![false, true, true, false, false]);
+        let distances: Vec<Option<u32>> = pileup.nucleotides.iter().map(|nuc| nuc.end_distance).collect();
+        assert_eq!(distances, [0, 1, 2, 3, 4].map(Some));
+
+        let fields = ReadFields{mapping_qualities: None, read_positions: Some("1,0")};
+        assert!(Pileup::with_read_fields(Allele::C, 2, ".,", "JJ", fields, false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn pileup_trim_after_skipped_deletion() -> Result<()> {
+        // The deletion still owns the first read position and score, even when skipped.
+        let fields = ReadFields{mapping_qualities: None, read_positions: Some("10,1,10")};
+        let mut pileup = Pileup::with_read_fields(Allele::C, 3, "*.A", "#EJ", fields, true)?;
+        assert_eq!(pileup.get_nucleotides(), "CA");
+        let distances: Vec<Option<u32>> = pileup.nucleotides.iter().map(|nuc| nuc.end_distance).collect();
+        assert_eq!(distances, [0, 9].map(Some));
+
+        pileup.filter_damage(&DamageFilter{deamination: false, trim: 2}, Allele::C);
+        assert_eq!(pileup.get_nucleotides(), "A");
+        assert_eq!(pileup.nucleotides[0].get_score_ascii(), 'J');
+        Ok(())
+    }
+
+    #[test]
+    fn pileup_filter_mapping_quality() -> Result<()> {
+        let fields = ReadFields{mapping_qualities: Some("?5!]"), read_positions: None};
+        let mut pileup = Pileup::with_read_fields(Allele::C, 4, ".,AT", "JJJJ", fields, false)?;
+        let mapqs: Vec<Option<u8>> = pileup.nucleotides.iter().map(|nuc| nuc.mapq).collect();
+        assert_eq!(mapqs, [30, 20, 0, 60].map(Some));
+
+        pileup.filter_mapping_quality(25);
+        assert_eq!(pileup.get_nucleotides(), "CT");
+        assert_eq!(pileup.depth, 2);
+
+        let fields = ReadFields{mapping_qualities: Some("??"), read_positions: None};
+        assert!(Pileup::with_read_fields(Allele::C, 4, ".,AT", "JJJJ", fields, false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn pileup_filter_mapping_quality_after_skipped_deletion() -> Result<()> {
+        // The deletion still owns the first mapping quality, even when skipped.
+        let fields = ReadFields{mapping_qualities: Some("]!]"), read_positions: None};
+        let mut pileup = Pileup::with_read_fields(Allele::C, 3, "*.A", "JJJ", fields, true)?;
+        let mapqs: Vec<Option<u8>> = pileup.nucleotides.iter().map(|nuc| nuc.mapq).collect();
+        assert_eq!(mapqs, [0, 60].map(Some));
+
+        pileup.filter_mapping_quality(25);
+        assert_eq!(pileup.get_nucleotides(), "A");
+
+        let fields = ReadFields{mapping_qualities: Some("]!]]"), read_positions: None};
+        assert!(Pileup::with_read_fields(Allele::C, 3, "*.A", "JJJ", fields, true).is_err());
+        Ok(())
+    }
+
     #[test]
     fn pileup_refskip_error() {
         println!("Testing reverse to forward conversion: [atcgn] -> [ATCGN] ");
@@ -0,0 +1,158 @@
+use genome::snp::SubstitutionClass;
+use located_error::prelude::*;
+use parser::PwdMode;
+use rayon::prelude::*;
+
+use crate::comparisons::Comparisons;
+use crate::pileup::{Line, PileupColumns};
+
+/// Number of pileup lines buffered before being dispatched across threads.
+pub const CHUNK_SIZE: usize = 4096;
+
+/// Parameters of the pipeline.
+/// - `ignore_dels`  : do not include deletions within parsed pileup lines.
+/// - `columns`      : optional per-individual columns found within pileup entries.
+/// - `pwd_mode`     : method used to estimate pairwise differences.
+/// - `sampled_reads`: number of random draws per site, when using `PwdMode::RandomRead`.
+/// - `filter_sites` : print overlapping sites instead of comparing them.
+/// - `seed`         : seed of the random read sampling.
+#[derive(Debug, Clone, Copy)]
+pub struct PipelineSettings {
+    pub ignore_dels  : bool,
+    pub columns      : PileupColumns,
+    pub pwd_mode     : PwdMode,
+    pub sampled_reads: u16,
+    pub filter_sites : bool,
+    pub seed         : u64,
+}
+
+/// A pileup line which passed every filter.
+/// - `line` : parsed and filtered pileup line.
+/// - `class`: substitution class of the SNP, if known.
+/// - `entry`: raw pileup entry, if any. Only used when printing filtered sites.
+struct Site {
+    line : Line,
+    class: Option<SubstitutionClass>,
+    entry: Option<String>,
+}
+
+/// Chunked, parallel processing of pileup lines.
+///
+/// Each chunk of lines is first parsed and filtered in parallel. Sites are then dispatched across comparisons: each
+/// thread handles a subset of the pairs, and processes every site of the chunk, in order. When sampling random reads,
+/// each pair draws from its own random number generator, derived from the user-provided seed. Thus, results do not
+/// depend on the number of threads.
+///
+/// # Fields:
+/// - `filter`  : per-line filtration. Returns `None` if the line should be skipped, or the substitution class of the
+///   site otherwise.
+/// - `settings`: parameters of the pipeline.
+/// - `rngs`    : random number generator of each comparison.
+/// - `pool`    : thread pool.
+pub struct Pipeline<F> {
+    filter  : F,
+    settings: PipelineSettings,
+    rngs    : Vec<fastrand::Rng>,
+    pool    : rayon::ThreadPool,
+}
+
+impl<F> Pipeline<F>
+where
+    F: Fn(&mut Line) -> Result<Option<Option<SubstitutionClass>>> + Sync,
+{
+    /// Instantiate a new pipeline, for a given set of `comparisons`, using `threads` worker threads.
+    ///
+    /// # Errors
+    /// - if the thread pool cannot be built.
+    pub fn new(comparisons: &Comparisons, filter: F, settings: PipelineSettings, threads: usize) -> Result<Self> {
+        let mut rng = fastrand::Rng::with_seed(settings.seed);
+        let rngs = comparisons.iter().map(|_| rng.fork()).collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .loc("While building the thread pool of the pwd-from-stdin pipeline")?;
+        Ok(Self{filter, settings, rngs, pool})
+    }
+
+    /// Parse, filter and compare a chunk of raw pileup entries. `context` provides the location of the i-th entry of
+    /// the chunk, and is solely used for error reporting.
+    ///
+    /// # Errors
+    /// - if any of the entries cannot be parsed or filtered.
+    /// - if any of the comparisons fails.
+    pub fn process_entries<C>(&mut self, comparisons: &mut Comparisons, entries: Vec<String>, context: C) -> Result<()>
+    where
+        C: Fn(usize) -> String + Sync,
+    {
+        let (filter, settings) = (&self.filter, self.settings);
+        let sites = self.pool.install(|| entries.into_par_iter().enumerate()
+            .map(|(i, entry)| {
+                let line = Line::with_columns(&entry, settings.ignore_dels, settings.columns)
+                    .with_loc(|| format!("While attempting to parse pileup line {}", context(i)))?;
+                Self::filter_site(filter, line, Some(entry))
+            })
+            .collect::<Result<Vec<Option<Site>>>>()
+        )?;
+        self.compare(comparisons, sites.into_iter().flatten().collect())
+    }
+
+    /// Filter and compare a chunk of already parsed pileup lines.
+    ///
+    /// # Errors
+    /// - if any of the lines cannot be filtered.
+    /// - if any of the comparisons fails.
+    pub fn process_lines(&mut self, comparisons: &mut Comparisons, lines: Vec<Line>) -> Result<()> {
+        let filter = &self.filter;
+        let sites = self.pool.install(|| lines.into_par_iter()
+            .map(|line| Self::filter_site(filter, line, None))
+            .collect::<Result<Vec<Option<Site>>>>()
+        )?;
+        self.compare(comparisons, sites.into_iter().flatten().collect())
+    }
+
+    fn filter_site(filter: &F, mut line: Line, entry: Option<String>) -> Result<Option<Site>> {
+        Ok(filter(&mut line)?.map(|class| Site{line, class, entry}))
+    }
+
+    /// Compare every pair at each site of the chunk (or print the sites where any pair overlaps, when filtering
+    /// sites).
+    fn compare(&mut self, comparisons: &mut Comparisons, sites: Vec<Site>) -> Result<()> {
+        let settings = self.settings;
+        let loc_msg  = |site: &Site| format!("While parsing coordinate coordinate: {}", site.line.coordinate);
+
+        // ---- Printing must follow the order of the input: keep it on a single thread.
+        if settings.filter_sites {
+            for site in &sites {
+                for comparison in comparisons.iter() {
+                    let satisfactory_depth = comparison.satisfiable_depth(&site.line.individuals)
+                        .with_context(|| loc_msg(site))
+                        .with_loc(|| format!("While comparing pair {}", comparison.get_pair()))?;
+                    if satisfactory_depth {
+                        match &site.entry {
+                            Some(entry) => println!("{entry}"),
+                            None        => println!("{}", site.line),
+                        }
+                    }
+                }
+            }
+            return Ok(())
+        }
+
+        let rngs = &mut self.rngs;
+        self.pool.install(|| comparisons.par_iter_mut().zip(rngs.par_iter_mut()).try_for_each(|(comparison, rng)| {
+            for site in &sites {
+                let satisfactory_depth = comparison.satisfiable_depth(&site.line.individuals)
+                    .with_context(|| loc_msg(site))
+                    .with_loc(|| format!("While comparing pair {}", comparison.get_pair()))?;
+
+                if satisfactory_depth {
+                    match settings.pwd_mode {
+                        PwdMode::Deterministic => comparison.compare(&site.line, site.class)?,
+                        PwdMode::RandomRead    => comparison.compare_random(&site.line, site.class, settings.sampled_reads, rng)?,
+                    }
+                }
+            }
+            Ok(())
+        }))
+    }
+}
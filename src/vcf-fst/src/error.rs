@@ -1,8 +1,8 @@
 use thiserror::Error;
-use genome::coordinate::{Coordinate, CoordinateError};
+use genome::coordinate::{Coordinate, CoordinateError, COORDINATE_BYTES};
 
 fn locate_genomic_position(coord: &[u8]) -> String {
-    match Coordinate::try_from(&coord[0..5]).ok() {
+    match coord.get(0..COORDINATE_BYTES).and_then(|coord| Coordinate::try_from(coord).ok()) {
         Some(coord) => format!("{coord}"),
         None        => "[None]".to_string()
     }
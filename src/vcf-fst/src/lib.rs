@@ -1,12 +1,12 @@
-use std::{collections::BTreeMap, fs::File, io::{BufRead, BufWriter}, path::Path, process};
+use std::{collections::{BTreeMap, BTreeSet}, fs::File, io::{BufRead, BufWriter, Write}, path::Path, process};
 
-use genome::coordinate::Coordinate;
+use genome::{Genome, coordinate::{ChrIdx, Coordinate, COORDINATE_BYTES}};
 
 use located_error::prelude::*;
 use parser::VCFFst;
 use grups_io::read::{
     SampleTag, PanelReader,
-    genotype_reader::{GenotypeReader, VCFReader, FST_EXT, FRQ_EXT},
+    genotype_reader::{GenotypeReader, VCFReader, FST_EXT, FRQ_EXT, CTG_EXT},
 };
 
 use fst::SetBuilder;
@@ -45,7 +45,12 @@ fn create_set_builder(file: &str) -> Result<SetBuilder<BufWriter<File>>> {
 /// - The second set is identified by the `.fst.frq` file extension and contains population allele frequencies
 ///   - Fields (space-separated): <CHR>    <POS>    <POP>    <FREQUENCY>
 /// 
-/// - <POS> fields are padded with up to 9 leading '0' characters, to ensure they are properly sorted.
+/// - <CHR> and <POS> fields are respectively encoded as big-endian u16 and u32 (see `Coordinate`), to ensure they are
+///   properly sorted.
+/// 
+/// - Contigs which are not numbered (e.g. `X`, `MT`, unplaced scaffolds), are listed within a companion `.fst.contigs`
+///   file, along with their id. This allows readers to ensure they share the same contig dictionary. Sets lacking this
+///   file are considered as using the legacy `{chr(u8)}{pos(u32_be)}` layout.
 /// 
 /// `VCFIndexer` will automatically filter out unrelevant positions from the vcf file. This include:
 ///  - Duplicate coordinate entries
@@ -63,6 +68,8 @@ fn create_set_builder(file: &str) -> Result<SetBuilder<BufWriter<File>>> {
 /// - `genotypes_buffer`   : Genotypes of the current VCF line
 /// - `genotypes_keys`     : Temporary buffer of the values that should be inserted into the genotypes set builder
 /// - `frequency_keys`     : Temporary buffer of the values that should be inserted into the frequency set builder
+/// - `contigs`            : Non-numbered contigs encountered so far.
+/// - `output_file`        : Output file stem of the `.fst`, `.fst.frq` and `.fst.contigs` files.
 pub struct VCFIndexer<'a, 'panel>{
     reader              : VCFReader<'a>,
     gen_builder         : SetBuilder<BufWriter<File>>,
@@ -73,7 +80,9 @@ pub struct VCFIndexer<'a, 'panel>{
     previous_coordinate : Vec<u8>,
     genotypes_buffer    : Vec<u8>,
     genotype_keys       : Vec<Vec<u8>>,
-    frequency_keys      : Vec<Vec<u8>>
+    frequency_keys      : Vec<Vec<u8>>,
+    contigs             : BTreeSet<ChrIdx>,
+    output_file         : String,
 }      
 
 impl<'a, 'panel> VCFIndexer<'a, 'panel> {
@@ -101,6 +110,8 @@ impl<'a, 'panel> VCFIndexer<'a, 'panel> {
             genotypes_buffer   : Vec::new(),
             genotype_keys      : Vec::new(),
             frequency_keys     : Vec::new(),
+            contigs            : BTreeSet::new(),
+            output_file        : output_file.to_string(),
         })
     }
 
@@ -162,7 +173,7 @@ impl<'a, 'panel> VCFIndexer<'a, 'panel> {
                 AFStrategy::ParseFromVcf       => self.parse_allele_frequencies(&info)?,
             };
             
-            debug!("{} | Pop_afs: {pop_afs:?}", Coordinate::try_from(&self.coordinate_buffer[0..5])?);
+            debug!("{} | Pop_afs: {pop_afs:?}", Coordinate::try_from(&self.coordinate_buffer[0..COORDINATE_BYTES])?);
 
            // ---- and insert the relevant entries within our frequency_set.
             self.insert_frequency_keys(&pop_afs);
@@ -179,12 +190,21 @@ impl<'a, 'panel> VCFIndexer<'a, 'panel> {
     }
 
     /// Finish the construction of our genotype/frequency sets and flush their underlying writers.
+    /// Non-numbered contigs are then listed within a companion `.fst.contigs` file (`<ID>\t<NAME>`). This file is
+    /// always written, even if empty, since readers rely on its presence to detect the current key layout.
     /// # Errors
     /// - if either the `.fst` or `.fst.frq` file fails to finish building and writing itself.
+    /// - if the `.fst.contigs` file cannot be written.
     pub fn finish_build(self) -> Result<()> {
-        use GenomeFstError::CompleteBuild;
+        use GenomeFstError::{CompleteBuild, CreateFile};
         self.gen_builder.finish().map_err(CompleteBuild)?;
         self.frq_builder.finish().map_err(CompleteBuild)?;
+
+        let mut writer = BufWriter::new(File::create(format!("{}.{CTG_EXT}", self.output_file)).map_err(CreateFile)?);
+        for contig in &self.contigs {
+            writeln!(writer, "{}\t{contig}", contig.0).map_err(CreateFile)?;
+        }
+        writer.flush().map_err(CreateFile)?;
         Ok(())
     }
 
@@ -201,23 +221,17 @@ impl<'a, 'panel> VCFIndexer<'a, 'panel> {
         self.reader.source.read_until(b'\t', &mut pos_buffer).with_loc(||ReadField { c: self.coordinate_buffer.clone() })?;
         pos_buffer.pop();
 
-        let chr: u8 = str::from_utf8(&chr_buffer).ok()
-            .and_then(|c| {
-                match c.parse::<u8>() {
-                    Ok(b) => Some(b),
-                    Err(_) => {
-                        match c == "X" || c == "chrX" {
-                            true =>   Some(b'X'),
-                            false =>  None
-                        }
-                    }
-                }
-            }).with_loc(||EncodeChr {c: self.coordinate_buffer.clone() })?;
+        let chr: ChrIdx = str::from_utf8(&chr_buffer).ok()
+            .and_then(|c| c.parse::<ChrIdx>().ok())
+            .with_loc(||EncodeChr {c: self.coordinate_buffer.clone() })?;
 
         let pos: [u8; 4] = str::from_utf8(&pos_buffer).ok().and_then(|c| c.parse::<u32>().ok()).map(u32::to_be_bytes)
             .with_loc(||EncodePos { c: self.coordinate_buffer.clone() })?;
 
-        self.coordinate_buffer.push(chr);
+        if !chr.is_numbered() {
+            self.contigs.insert(chr);
+        }
+        self.coordinate_buffer.extend(chr.0.to_be_bytes());
         self.coordinate_buffer.extend(pos);
         Ok(())
     }
@@ -251,7 +265,7 @@ impl<'a, 'panel> VCFIndexer<'a, 'panel> {
     fn insert_frequency_keys(&mut self, pop_afs: &BTreeMap<&str, f32>) {
         // ---- and insert the relevant entries within our frequency_set.
         for (pop_tag, pop_af) in pop_afs {
-            // ---- Parse to: "{chromosome(u7)}{position(u32_be)}{pop(chars)}{freq(f32_be)}"
+            // ---- Parse to: "{chromosome(u16_be)}{position(u32_be)}{pop(chars)}{freq(f32_be)}"
             let pop_tag    = pop_tag.as_bytes();
             let pop_af_be  = pop_af.to_be_bytes();
     
@@ -321,7 +335,7 @@ impl<'a, 'panel> VCFIndexer<'a, 'panel> {
     /// Attempt to parse the current coordinate buffer as a valid coordinate
     #[inline]
     fn current_coordinate(&self) -> Result<Coordinate> {
-        Ok(Coordinate::try_from(&self.coordinate_buffer[0..5]).map_err(GenomeFstError::InvalidCoordinate)?)
+        Ok(Coordinate::try_from(&self.coordinate_buffer[0..COORDINATE_BYTES]).map_err(GenomeFstError::InvalidCoordinate)?)
     }
 
     /// Log this Indexer's progress into the console, if `self.counter` is a multiple of `every_n`
@@ -350,7 +364,7 @@ impl<'a, 'panel> VCFIndexer<'a, 'panel> {
             let geno_idx = sample_tag.idx().expect("Missing sample tag index"); 
             let sample_genotype = sample_genotypes.get(geno_idx)
                 .with_loc(|| GenomeFstError::InvalidGenotypeIndex { 
-                    c: self.coordinate_buffer[0..5].to_vec(), s: (sample_tag.id()).clone(), i: geno_idx
+                    c: self.coordinate_buffer[0..COORDINATE_BYTES].to_vec(), s: (sample_tag.id()).clone(), i: geno_idx
                 })?;
 
             trace!("  - {} | {}:{}",
@@ -362,7 +376,7 @@ impl<'a, 'panel> VCFIndexer<'a, 'panel> {
             let (left, right) = match sample_genotype.len() {
                 3 => Ok((sample_genotype[0], sample_genotype[2])), // Autosomal or Pseudo-autosomal region
                 1 => Ok((sample_genotype[0], sample_genotype[0])), // X-chromosome (for male samples)
-                o => Err(GenomeFstError::InvalidGenotypeKey{c: self.coordinate_buffer[0..5].to_vec(), s: (sample_tag.id()).clone(), l: o})
+                o => Err(GenomeFstError::InvalidGenotypeKey{c: self.coordinate_buffer[0..COORDINATE_BYTES].to_vec(), s: (sample_tag.id()).clone(), l: o})
             }?;
 
             key.extend_from_slice(&[left, right]); // haplo1
@@ -406,13 +420,13 @@ impl<'a, 'panel> VCFIndexer<'a, 'panel> {
             let genotype_index = sample_tag.idx().expect("Missing sample tag index");
             let sample_genotype = sample_genotypes.get(genotype_index)
                 .with_loc(|| GenomeFstError::InvalidGenotypeIndex { 
-                    c: self.coordinate_buffer[0..5].to_vec(), s: (sample_tag.id()).clone(), i: genotype_index
+                    c: self.coordinate_buffer[0..COORDINATE_BYTES].to_vec(), s: (sample_tag.id()).clone(), i: genotype_index
                 })?;
             
             let (left, right) = match sample_genotype.len() {
                 3  => Ok((sample_genotype[0], sample_genotype[2])), // Autosomal or Pseudo-autosomal region
                 1  => Ok((sample_genotype[0], sample_genotype[0])), // X-chromosome (for male samples)
-                o  => Err(GenomeFstError::InvalidGenotypeKey{c: self.coordinate_buffer[0..5].to_vec(), s: (sample_tag.id()).clone(), l: o})
+                o  => Err(GenomeFstError::InvalidGenotypeKey{c: self.coordinate_buffer[0..COORDINATE_BYTES].to_vec(), s: (sample_tag.id()).clone(), l: o})
             }?;
 
             if sample_genotypes.len() > 1 {
@@ -449,6 +463,11 @@ impl<'a, 'panel> VCFIndexer<'a, 'panel> {
 pub fn run(fst_cli: &VCFFst) -> Result<()> {
     let loc_msg = "While attempting to initialize FST Set Builder";
 
+    // -------------------- Register the contigs of the reference genome, if provided.
+    if let Some(genome) = &fst_cli.genome {
+        Genome::from_fasta_index(genome).loc(loc_msg)?;
+    }
+
     // -------------------- Fetch the input vcf paths within `vcf_dir`
     info!("Fetching input VCF files in {}", &fst_cli.vcf_dir.to_string_lossy());
     let mut input_vcf_paths = VCFReader::fetch_input_files(&fst_cli.vcf_dir).loc(loc_msg)?;